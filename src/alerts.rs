@@ -0,0 +1,310 @@
+//! Recent alerts, kept after their condition clears
+//!
+//! The table only shows what is wrong right now; a port that flapped or a
+//! burst of errors between two glances leaves no trace there. The monitor
+//! turns such transitions into timestamped alerts in a bounded log.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::history::RingBuffer;
+use crate::metrics::MetricsCollector;
+use crate::types::{AdapterInfo, PortCounters, PortState};
+use crate::ui::{calculate_totals, port_max_rate, sustained_over_line_rate};
+use crate::warnings;
+
+/// Alerts kept before the oldest are dropped
+pub const ALERT_LOG_CAPACITY: usize = 200;
+
+/// How urgent an alert is
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+/// One event worth remembering
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Alert {
+    /// Seconds since the epoch
+    pub timestamp: f64,
+    pub severity: Severity,
+    /// Port (`adapter:port`) or adapter the alert is about
+    pub source: String,
+    pub message: String,
+}
+
+/// Bounded log of recent alerts, dropping the oldest when full
+#[derive(Debug, Clone)]
+pub struct AlertLog {
+    alerts: RingBuffer<Alert>,
+}
+
+impl Default for AlertLog {
+    fn default() -> Self {
+        Self::with_capacity(ALERT_LOG_CAPACITY)
+    }
+}
+
+impl AlertLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            alerts: RingBuffer::new(capacity),
+        }
+    }
+
+    /// Record an alert that happened at `timestamp`
+    pub fn push(
+        &mut self,
+        timestamp: f64,
+        severity: Severity,
+        source: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.alerts.push(Alert {
+            timestamp,
+            severity,
+            source: source.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Alerts from newest to oldest
+    pub fn newest_first(&self) -> impl Iterator<Item = &Alert> {
+        let oldest_first: Vec<&Alert> = self.alerts.iter().collect();
+        oldest_first.into_iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.alerts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+}
+
+/// Conditions that alert once when they start, not on every refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Condition {
+    Errors,
+    OverLineRate,
+    BondRateMismatch,
+    NodeTotal,
+}
+
+/// Source of alerts about the node as a whole
+const NODE_SOURCE: &str = "node";
+
+/// Whether node throughput (RX + TX, bytes per second) exceeds `limit_bps`
+/// (bits per second, as link rates and `--alert-total` are given)
+pub fn over_total_limit(total_bytes_per_sec: f64, limit_bps: f64) -> bool {
+    total_bytes_per_sec * 8.0 > limit_bps
+}
+
+/// Watches successive refreshes and logs transitions as alerts
+#[derive(Debug, Default)]
+pub struct AlertMonitor {
+    /// State and counters of every port at the previous refresh
+    ports: HashMap<(String, u16), (PortState, PortCounters)>,
+    /// Conditions currently in effect, by source
+    firing: HashSet<(String, Condition)>,
+    /// Node throughput limit in bits per second (`--alert-total`)
+    total_limit: Option<f64>,
+}
+
+impl AlertMonitor {
+    /// A monitor that also alerts when node throughput exceeds `total_limit`
+    pub fn with_total_limit(total_limit: Option<f64>) -> Self {
+        Self {
+            total_limit,
+            ..Self::default()
+        }
+    }
+
+    /// Compare a refresh with the previous one and log what changed
+    pub fn observe(
+        &mut self,
+        adapters: &[AdapterInfo],
+        metrics: &MetricsCollector,
+        log: &mut AlertLog,
+    ) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+
+        for adapter in adapters {
+            for port in &adapter.ports {
+                let source = format!("{}:{}", adapter.name, port.port_number);
+                let key = (adapter.name.clone(), port.port_number);
+
+                if let Some((state, counters)) = self.ports.get(&key) {
+                    match (*state, port.state) {
+                        (PortState::Active, PortState::Down) => {
+                            log.push(now, Severity::Critical, &source, "port went DOWN");
+                        }
+                        (PortState::Active, PortState::Init | PortState::Armed) => {
+                            let message = format!("port fell back to {}", port.state);
+                            log.push(now, Severity::Warning, &source, &message);
+                        }
+                        (
+                            PortState::Down | PortState::Init | PortState::Armed,
+                            PortState::Active,
+                        ) => {
+                            log.push(now, Severity::Info, &source, "port is ACTIVE again");
+                        }
+                        _ => {}
+                    }
+                    if counters_went_backwards(counters, &port.counters) {
+                        log.push(now, Severity::Warning, &source, "counters were reset");
+                    }
+                }
+                self.ports.insert(key, (port.state, port.counters.clone()));
+
+                let Some(m) = metrics.get_metrics(&adapter.name, port.port_number) else {
+                    continue;
+                };
+                if self.starts(&source, Condition::Errors, m.error_rate > 0.0) {
+                    let message = format!("errors at {:.1}/s", m.error_rate);
+                    log.push(now, Severity::Warning, &source, message);
+                }
+                let history = metrics.get_history(&adapter.name, port.port_number);
+                let over = sustained_over_line_rate(m, history, port_max_rate(port));
+                if self.starts(&source, Condition::OverLineRate, over) {
+                    log.push(
+                        now,
+                        Severity::Critical,
+                        &source,
+                        "throughput above line rate",
+                    );
+                }
+            }
+
+            let mismatch = warnings::detect_bond_rate_mismatch(adapter);
+            if self.starts(
+                &adapter.name,
+                Condition::BondRateMismatch,
+                mismatch.is_some(),
+            ) {
+                if let Some(warning) = mismatch {
+                    log.push(now, Severity::Warning, &adapter.name, warning.message);
+                }
+            }
+        }
+
+        if let Some(limit) = self.total_limit {
+            let (rx, tx) = calculate_totals(adapters, metrics);
+            let over = over_total_limit(rx + tx, limit);
+            if self.starts(NODE_SOURCE, Condition::NodeTotal, over) {
+                let message = format!(
+                    "node throughput {:.1} Gb/s above the {:.1} Gb/s limit",
+                    (rx + tx) * 8.0 / 1e9,
+                    limit / 1e9
+                );
+                log.push(now, Severity::Critical, NODE_SOURCE, message);
+            }
+        }
+    }
+
+    /// Track whether `condition` holds for `source`; true only when it starts
+    fn starts(&mut self, source: &str, condition: Condition, active: bool) -> bool {
+        let key = (source.to_string(), condition);
+        if active {
+            self.firing.insert(key)
+        } else {
+            self.firing.remove(&key);
+            false
+        }
+    }
+}
+
+/// A counter lower than before means the counters were cleared
+fn counters_went_backwards(before: &PortCounters, after: &PortCounters) -> bool {
+    after.rx_bytes < before.rx_bytes
+        || after.tx_bytes < before.tx_bytes
+        || after.rx_packets < before.rx_packets
+        || after.tx_packets < before.tx_packets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PortInfo;
+
+    #[test]
+    fn test_alert_log_drops_oldest_past_capacity() {
+        let mut log = AlertLog::with_capacity(3);
+        log.push(1.0, Severity::Info, "mlx5_0:1", "first");
+        log.push(2.0, Severity::Critical, "mlx5_0:1", "second");
+        log.push(3.0, Severity::Warning, "mlx5_0:2", "third");
+        log.push(4.0, Severity::Critical, "mlx5_1:1", "fourth");
+
+        assert_eq!(log.len(), 3);
+        let kept: Vec<(&str, Severity)> = log
+            .newest_first()
+            .map(|alert| (alert.message.as_str(), alert.severity))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("fourth", Severity::Critical),
+                ("third", Severity::Warning),
+                ("second", Severity::Critical),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_node_total_limit() {
+        let limit = crate::ui::parse_rate_bps("350G").unwrap();
+        assert!((limit - 350e9).abs() < 1.0);
+
+        // 350 Gb/s is 43.75 GB/s of RX + TX
+        assert!(!over_total_limit(43.0e9, limit));
+        assert!(!over_total_limit(43.75e9, limit));
+        assert!(over_total_limit(44.0e9, limit));
+    }
+
+    #[test]
+    fn test_monitor_logs_flap_and_reset_once() {
+        let adapters = |state, rx_bytes| {
+            vec![AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![PortInfo {
+                    port_number: 1,
+                    state,
+                    counters: PortCounters {
+                        rx_bytes,
+                        ..PortCounters::default()
+                    },
+                    ..PortInfo::default()
+                }],
+                ..AdapterInfo::default()
+            }]
+        };
+        let metrics = MetricsCollector::new();
+        let mut monitor = AlertMonitor::default();
+        let mut log = AlertLog::default();
+
+        monitor.observe(&adapters(PortState::Active, 100), &metrics, &mut log);
+        assert_eq!(log.len(), 0);
+        monitor.observe(&adapters(PortState::Down, 100), &metrics, &mut log);
+        monitor.observe(&adapters(PortState::Down, 100), &metrics, &mut log);
+        monitor.observe(&adapters(PortState::Active, 5), &metrics, &mut log);
+
+        let alerts: Vec<(Severity, &str)> = log
+            .newest_first()
+            .map(|alert| (alert.severity, alert.message.as_str()))
+            .collect();
+        assert_eq!(
+            alerts,
+            vec![
+                (Severity::Warning, "counters were reset"),
+                (Severity::Info, "port is ACTIVE again"),
+                (Severity::Critical, "port went DOWN"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Baseline snapshots for "what happened since" comparisons
+//!
+//! A baseline remembers every port's raw counters at one moment so the UI
+//! can show bytes and errors accrued since then instead of lifetime totals.
+//! Saved baselines use the `--json` output format, so any earlier
+//! `ibtop --json` snapshot can be loaded as one.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::types::{AdapterInfo, IbtopOutput, PortCounters, PortInfo};
+
+/// Counters of every port at the moment the baseline was taken
+#[derive(Debug, Default, Clone)]
+pub struct Baseline {
+    counters: HashMap<(String, u16), PortCounters>,
+    /// Count ports missing from the baseline from zero; set for baselines
+    /// loaded from a file, which may predate ports that exist now
+    missing_is_zero: bool,
+}
+
+impl Baseline {
+    /// Remember the current counters of every port
+    pub fn capture(adapters: &[AdapterInfo]) -> Self {
+        let counters = adapters
+            .iter()
+            .flat_map(|adapter| {
+                adapter.ports.iter().map(|port| {
+                    (
+                        (adapter.name.clone(), port.port_number),
+                        port.counters.clone(),
+                    )
+                })
+            })
+            .collect();
+        Self {
+            counters,
+            missing_is_zero: false,
+        }
+    }
+
+    /// Load a snapshot written by `--json` or `--baseline-save`
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("reading baseline {}: {err}", path.display()),
+            )
+        })?;
+        let snapshot: IbtopOutput = serde_json::from_str(&contents).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not an ibtop --json snapshot: {err}", path.display()),
+            )
+        })?;
+        Ok(Self {
+            missing_is_zero: true,
+            ..Self::capture(&snapshot.adapters)
+        })
+    }
+
+    /// Counters accrued by `port` since the baseline
+    ///
+    /// Returns `None` for ports that did not exist when a captured baseline
+    /// was taken; a loaded baseline counts them from zero instead.
+    pub fn delta(&self, adapter: &str, port: &PortInfo) -> Option<PortCounters> {
+        match self.counters.get(&(adapter.to_string(), port.port_number)) {
+            Some(base) => Some(counters_since(&port.counters, base)),
+            None if self.missing_is_zero => Some(port.counters.clone()),
+            None => None,
+        }
+    }
+}
+
+/// Write a snapshot so it can later be loaded as a baseline
+pub fn save(path: &Path, snapshot: &IbtopOutput) -> io::Result<()> {
+    std::fs::write(path, serde_json::to_string_pretty(snapshot)?)
+}
+
+/// Field-wise difference between two counter readings
+fn counters_since(current: &PortCounters, base: &PortCounters) -> PortCounters {
+    PortCounters {
+        rx_bytes: since(current.rx_bytes, base.rx_bytes),
+        tx_bytes: since(current.tx_bytes, base.tx_bytes),
+        rx_packets: since(current.rx_packets, base.rx_packets),
+        tx_packets: since(current.tx_packets, base.tx_packets),
+        rx_errors: since(current.rx_errors, base.rx_errors),
+        tx_discards: since(current.tx_discards, base.tx_discards),
+        rx_dropped: since(current.rx_dropped, base.rx_dropped),
+        link_error_recovery: since(current.link_error_recovery, base.link_error_recovery),
+        link_downed: since(current.link_downed, base.link_downed),
+        symbol_errors: since(current.symbol_errors, base.symbol_errors),
+        local_link_integrity_errors: since(
+            current.local_link_integrity_errors,
+            base.local_link_integrity_errors,
+        ),
+        xmit_wait: since(current.xmit_wait, base.xmit_wait),
+    }
+}
+
+/// Amount accrued since `base`; a counter below its baseline was reset,
+/// so everything it holds now accrued after the reset
+fn since(current: u64, base: u64) -> u64 {
+    current.checked_sub(base).unwrap_or(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, rx_bytes: u64, rx_errors: u64) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_string(),
+            ports: vec![PortInfo {
+                port_number: 1,
+                counters: PortCounters {
+                    rx_bytes,
+                    rx_errors,
+                    ..PortCounters::default()
+                },
+                ..PortInfo::default()
+            }],
+            ..AdapterInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_delta_against_saved_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let snapshot = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![adapter("mlx5_0", 1_000, 2)],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+        save(&path, &snapshot).unwrap();
+
+        let saved: IbtopOutput =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let baseline = Baseline::capture(&saved.adapters);
+        let now = adapter("mlx5_0", 5_000, 7);
+        let delta = baseline.delta("mlx5_0", &now.ports[0]).unwrap();
+        assert_eq!(delta.rx_bytes, 4_000);
+        assert_eq!(delta.rx_errors, 5);
+
+        // Ports that appeared after the baseline have nothing to compare to
+        let new_port = adapter("mlx5_1", 10, 0);
+        assert_eq!(baseline.delta("mlx5_1", &new_port.ports[0]), None);
+    }
+
+    #[test]
+    fn test_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("before.json");
+        let snapshot = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![adapter("mlx5_0", 1_000, 2)],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&snapshot).unwrap()).unwrap();
+
+        let baseline = Baseline::load(&path).unwrap();
+        let now = adapter("mlx5_0", 5_000, 7);
+        let delta = baseline.delta("mlx5_0", &now.ports[0]).unwrap();
+        assert_eq!(delta.rx_bytes, 4_000);
+        assert_eq!(delta.rx_errors, 5);
+
+        // Ports missing from the file count from zero
+        let new_port = adapter("mlx5_1", 10, 1);
+        let delta = baseline.delta("mlx5_1", &new_port.ports[0]).unwrap();
+        assert_eq!(delta.rx_bytes, 10);
+        assert_eq!(delta.rx_errors, 1);
+
+        std::fs::write(&path, "{\"not\": \"a snapshot\"}").unwrap();
+        let err = Baseline::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(Baseline::load(&dir.path().join("missing.json")).is_err());
+    }
+
+    #[test]
+    fn test_load_reads_tx_errors_as_discards() {
+        let counters: PortCounters = serde_json::from_str(
+            r#"{"rx_bytes":0,"tx_bytes":0,"rx_packets":0,"tx_packets":0,
+                "rx_errors":1,"tx_errors":4,"rx_dropped":0}"#,
+        )
+        .unwrap();
+        assert_eq!(counters.tx_discards, 4);
+    }
+
+    #[test]
+    fn test_delta_after_counter_reset() {
+        let baseline = Baseline::capture(&[adapter("mlx5_0", 1_000, 0)]);
+        let reset = adapter("mlx5_0", 300, 0);
+        assert_eq!(
+            baseline.delta("mlx5_0", &reset.ports[0]).unwrap().rx_bytes,
+            300
+        );
+    }
+}
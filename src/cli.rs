@@ -0,0 +1,832 @@
+//! Command-line option parsing
+//!
+//! Kept dependency-free on purpose: ibtop only has a handful of flags.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::discovery::SwapRxTx;
+use crate::expectations::Expectation;
+use crate::ui::{
+    parse_columns, parse_counters, parse_rate_bps, Column, Counter, CounterNotation, SortKey,
+    Theme, TimeUnit,
+};
+
+pub const USAGE: &str = "\
+Usage: ibtop [OPTIONS]
+       ibtop doctor
+       ibtop --merge <file>...
+
+Commands:
+  doctor              Print environment diagnostics and exit
+
+Options:
+  --json              Print a JSON snapshot of all adapters and exit
+  --json-flat         Print one flat JSON object per port and exit
+  --csv               Print one CSV row per port, after a header line, and exit
+  --prometheus        Print port counters in Prometheus text format and exit
+  --list              Print each adapter and its port states and exit
+  --serve <[host]:port>
+                      Serve port counters to Prometheus at /metrics, read
+                      afresh on every scrape, until Ctrl-C
+  --watch             Print every port's state and rates, one line each
+                      behind a timestamp, every --interval without the full
+                      screen, until Ctrl-C (for logs and pipes)
+  --merge <file>...   Sum the byte counters in --json snapshots from several
+                      nodes, per HCA model and in total, and exit; files
+                      that do not parse are skipped (quoted globs expand)
+  --columns <list>    Table columns in order, comma-separated, from:
+                      port,state,link,util,rx,tx,errors,share,burst,history
+  --error-counters <list>
+                      Counters on the detail view's Errors tab in order,
+                      comma-separated, from: rx_bytes,tx_bytes,rx_packets,
+                      tx_packets,rx_errors,tx_discards,rx_dropped,
+                      link_error_recovery,link_downed,symbol_errors,
+                      local_link_integrity_errors,xmit_wait (default:
+                      link_error_recovery,link_downed,symbol_errors,
+                      local_link_integrity_errors)
+  --counter-notation <auto|eng>
+                      How the Errors tab writes counters: auto (bytes in KB
+                      to PB, counts in full) or eng, from a million up in
+                      engineering notation (45.7e9)
+  --export-avg <secs> With --json, add per-port rates averaged over <secs>
+  --with-history      With --json, also add every port's recorded samples
+                      (over --export-avg, else 5s; at most --history-seconds)
+  --active-only       With --json, --json-flat, --csv, --prometheus or --serve, emit
+                      only ACTIVE ports whose counters are not all zero
+  --baseline <file>   Show deltas from a snapshot saved earlier with --json
+  --baseline-save <file>
+                      Capture a baseline at startup (and on `b`) into <file>
+  --history-file <file>
+                      Restore chart history from <file> and save it on exit
+  --history-seconds <secs>
+                      Keep <secs> of history for charts and sparklines
+                      (default: 120 samples, 30s at the default --interval)
+  --interval <ms>     Read counters every <ms> milliseconds (default: 250)
+  --time-unit <unit>  Chart time axis in s (seconds), m (minutes) or auto
+                      (default: seconds under a minute, else minutes)
+  --refresh-on-change Re-read sysfs when it reports changes instead of every
+                      interval (needs the inotify feature, else polls)
+  --title <text>      Name the session in the header, before the hostname
+                      (e.g. \"Rack 3 Node 7\" for screenshots)
+  --theme <name>      Colours: dark (default), light for light backgrounds,
+                      or mono for none (the default when NO_COLOR is set)
+  --highlight <regex> Emphasise ports whose adapter:port matches <regex>
+  --expect <adapter:port=STATE@rate>
+                      Flag the port when it is not in STATE or links slower
+                      than rate; port may be *, STATE or @rate may be left
+                      out (repeatable)
+  --alert-total <rate>
+                      Alert and show a banner when RX + TX summed over all
+                      ports exceeds <rate>, in bits/s like link rates (350G)
+  --error-threshold <n>
+                      Show ports receiving more than <n> errors/s in bold
+                      red; with --json, mark their rates \"alert\": true
+                      (sampled over --export-avg, else 5s)
+  --adaptive-refresh  Refresh faster while throughput changes quickly and
+                      slower while it is steady (100ms to 2s)
+  --interval-jitter   Skip the rates of a refresh that came more than 3x the
+                      interval late (a stalled or suspended ibtop) instead
+                      of averaging the stall into them
+  --swap-rxtx         Read RX counters as TX and the other way round, for
+                      drivers that report them swapped (`ibtop doctor` hints)
+  --swap-rxtx-adapter <adapter>
+                      Like --swap-rxtx, for one adapter only (repeatable)
+  --show-empty        Show adapters that expose no ports
+  --split-sparkline   Show separate RX/TX sparklines in the History column
+  --sparkline-clip <percentile>
+                      Scale sparklines to this percentile of their samples
+                      (e.g. 95) rather than the peak, so one spike does not
+                      flatten the rest
+  --no-sparkline      Hide the History column (toggle at runtime with `h`)
+  --wrap              Wrap port selection from the last port to the first
+  --no-auto-degrade   Keep sparklines and animation on even when rendering
+                      falls far behind the refresh rate
+  --smooth <factor>   Smooth rates; weight of the newest sample in (0, 1]
+  --divide-by <n>     Show throughput per consumer, e.g. per GPU sharing an HCA
+  -h, --help          Print this help and exit
+
+Environment:
+  INFINIBAND_PATH     sysfs root to scan (default: /sys/class/infiniband/)
+  IBTOP_FAKE_DATA     Use simulated adapters instead of real hardware
+  IBTOP_DEMO          Fall back to simulated adapters when none are found
+  NO_COLOR            Draw without colours unless --theme says otherwise
+
+Files:
+  $XDG_CONFIG_HOME/ibtop/config.toml, else ~/.config/ibtop/config.toml
+                      Defaults for interval, history_seconds, sort (name,
+                      rx, tx, total or errors), theme and counter_notation;
+                      flags win over it
+
+Exit status (--json, --json-flat, --csv, --prometheus, --list, --merge):
+  0                   Success, at least one adapter found
+  1                   I/O error
+  2                   Invalid command-line usage
+  3                   No adapters found (--merge: no snapshot could be read)
+  4                   Permission denied reading the sysfs root
+";
+
+/// What ibtop should do once options are parsed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Full-screen TUI
+    #[default]
+    Interactive,
+    /// Print a JSON snapshot and exit
+    Json,
+    /// Print a flat JSON array with one object per port and exit
+    JsonFlat,
+    /// Print one CSV row per port and exit
+    Csv,
+    /// Print port counters in Prometheus exposition format and exit
+    Prometheus,
+    /// Print one line per adapter with its port states and exit
+    List,
+    /// Answer Prometheus scrapes over HTTP until interrupted
+    Serve { addr: SocketAddr },
+    /// Sum saved `--json` snapshots from several nodes and exit
+    Merge,
+    /// Print one line per port every refresh, without the TUI, until Ctrl-C
+    Watch,
+    /// Print environment diagnostics and exit
+    Doctor,
+    /// Print usage and exit
+    Help,
+    /// Render one frame at a fixed size as text and exit (`--render-once`)
+    RenderOnce { width: u16, height: u16 },
+}
+
+/// Options controlling how ibtop runs
+#[derive(Debug, Default, Clone, PartialEq)]
+#[allow(clippy::struct_excessive_bools)] // One per on/off flag
+pub struct Options {
+    pub mode: Mode,
+    /// Show adapters that expose no ports
+    pub show_empty: bool,
+    /// Start with split RX/TX sparklines instead of the combined one
+    pub split_sparkline: bool,
+    /// Initial port order; only the config file sets it (`sort`)
+    pub sort_key: Option<SortKey>,
+    /// Percentile of the window sparklines are scaled to, clipping spikes
+    pub sparkline_clip: Option<f64>,
+    /// Rate smoothing factor (None = raw rates)
+    pub smoothing: Option<f64>,
+    /// Window over which exported rates are averaged
+    pub export_avg: Option<Duration>,
+    /// Table columns chosen with `--columns`
+    pub columns: Option<Vec<Column>>,
+    /// Errors tab counters chosen with `--error-counters`
+    pub error_counters: Option<Vec<Counter>>,
+    /// How the Errors tab writes large counters (`--counter-notation`)
+    pub counter_notation: Option<CounterNotation>,
+    /// Snapshot to compare against from the start (`--baseline`)
+    pub baseline: Option<PathBuf>,
+    /// Where captured baselines are written
+    pub baseline_save: Option<PathBuf>,
+    /// Chart history restored at startup and saved on exit
+    pub history_file: Option<PathBuf>,
+    /// How much history to keep (`--history-seconds`); 120 samples when not
+    /// given, whatever the interval
+    pub history_window: Option<Duration>,
+    /// Counter polling interval (`--interval`); 250ms when not given
+    pub interval: Option<Duration>,
+    /// Unit of the chart's time axis label
+    pub time_unit: TimeUnit,
+    /// Refresh on sysfs change notifications rather than a fixed interval
+    pub refresh_on_change: bool,
+    /// Pattern for ports to emphasise, already checked to compile
+    pub highlight: Option<String>,
+    /// Label shown before the hostname in the header (`--title`)
+    pub title: Option<String>,
+    /// Colour theme (`--theme`); `NO_COLOR` decides when not given
+    pub theme: Option<Theme>,
+    /// Consumers to split displayed throughput across (never zero)
+    pub divide_by: Option<u32>,
+    /// Let the refresh interval follow how fast throughput changes
+    pub adaptive_refresh: bool,
+    /// Drop samples taken long after the refresh interval (a stalled process)
+    pub interval_jitter: bool,
+    /// Start with the History column hidden
+    pub no_sparkline: bool,
+    /// Let j/k wrap around from the last port to the first and back
+    pub wrap: bool,
+    /// Leave DOWN and never-used ports out of JSON output
+    pub active_only: bool,
+    /// Add each port's sampled history to JSON output
+    pub with_history: bool,
+    /// Keep every feature on even when frames cannot keep up
+    pub no_auto_degrade: bool,
+    /// Expected port states and rates (`--expect`, repeatable)
+    pub expect: Vec<Expectation>,
+    /// Adapters whose RX and TX counters are read the other way round
+    pub swap_rxtx: SwapRxTx,
+    /// Alert when RX + TX over all ports exceeds this, in bits per second
+    pub alert_total: Option<f64>,
+    /// Errors per second above which a port is flagged
+    pub error_threshold: Option<f64>,
+    /// Snapshot files or patterns given to `--merge`
+    pub merge: Vec<String>,
+}
+
+impl Options {
+    /// Parse options from the arguments following the program name
+    #[allow(clippy::too_many_lines)] // One flat arm per flag reads best
+    pub fn parse<I>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut options = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "doctor" => options.mode = Mode::Doctor,
+                "--json" => set_output_mode(&mut options, Mode::Json)?,
+                "--json-flat" => set_output_mode(&mut options, Mode::JsonFlat)?,
+                "--csv" => set_output_mode(&mut options, Mode::Csv)?,
+                "--prometheus" => set_output_mode(&mut options, Mode::Prometheus)?,
+                "--list" => set_output_mode(&mut options, Mode::List)?,
+                "--watch" => set_output_mode(&mut options, Mode::Watch)?,
+                "--serve" => {
+                    let addr = parse_listen_addr(&parse_value::<String>(&arg, args.next())?)?;
+                    set_output_mode(&mut options, Mode::Serve { addr })?;
+                }
+                "--merge" => {
+                    set_output_mode(&mut options, Mode::Merge)?;
+                    // Everything up to the next option, as the shell expanded it
+                    while let Some(file) = args.next_if(|next| !next.starts_with('-')) {
+                        options.merge.push(file);
+                    }
+                    if options.merge.is_empty() {
+                        return Err("--merge requires at least one file".to_string());
+                    }
+                }
+                // Unlisted in USAGE: meant for docs screenshots and CI checks
+                "--render-once" => {
+                    let size: String = parse_value(&arg, args.next())?;
+                    let (width, height) = parse_size(&size).ok_or_else(|| {
+                        format!("invalid size '{size}' for {arg}, expected WIDTHxHEIGHT")
+                    })?;
+                    options.mode = Mode::RenderOnce { width, height };
+                }
+                "--columns" => {
+                    let list: String = parse_value(&arg, args.next())?;
+                    options.columns = Some(parse_columns(&list)?);
+                }
+                "--error-counters" => {
+                    let list: String = parse_value(&arg, args.next())?;
+                    options.error_counters = Some(parse_counters(&list)?);
+                }
+                "--export-avg" => {
+                    options.export_avg = Some(parse_positive_secs(&arg, args.next())?);
+                }
+                "--history-seconds" => {
+                    options.history_window = Some(parse_positive_secs(&arg, args.next())?);
+                }
+                "--baseline" => {
+                    options.baseline = Some(parse_value(&arg, args.next())?);
+                }
+                "--baseline-save" => {
+                    options.baseline_save = Some(parse_value(&arg, args.next())?);
+                }
+                "--history-file" => {
+                    options.history_file = Some(parse_value(&arg, args.next())?);
+                }
+                "--interval" => {
+                    let ms: u64 = parse_value(&arg, args.next())?;
+                    if ms == 0 {
+                        return Err("--interval must be at least 1 ms".to_string());
+                    }
+                    options.interval = Some(Duration::from_millis(ms));
+                }
+                "--counter-notation" => {
+                    let notation: String = parse_value(&arg, args.next())?;
+                    options.counter_notation = Some(parse_counter_notation(&notation)?);
+                }
+                "--time-unit" => {
+                    let unit: String = parse_value(&arg, args.next())?;
+                    options.time_unit = unit.parse().map_err(|()| {
+                        format!("invalid value '{unit}' for --time-unit (expected s, m or auto)")
+                    })?;
+                }
+                "--refresh-on-change" => options.refresh_on_change = true,
+                "--adaptive-refresh" => options.adaptive_refresh = true,
+                "--interval-jitter" => options.interval_jitter = true,
+                "--highlight" => {
+                    let pattern: String = parse_value(&arg, args.next())?;
+                    regex::Regex::new(&pattern)
+                        .map_err(|err| format!("invalid regex for --highlight: {err}"))?;
+                    options.highlight = Some(pattern);
+                }
+                "--title" => options.title = Some(parse_value(&arg, args.next())?),
+                "--theme" => {
+                    let name: String = parse_value(&arg, args.next())?;
+                    options.theme = Some(parse_theme(&name)?);
+                }
+                "--show-empty" => options.show_empty = true,
+                "--split-sparkline" => options.split_sparkline = true,
+                "--sparkline-clip" => {
+                    let percentile: f64 = parse_value(&arg, args.next())?;
+                    if !(percentile > 0.0 && percentile <= 100.0) {
+                        return Err(format!(
+                            "--sparkline-clip must be a percentile in (0, 100], got {percentile}"
+                        ));
+                    }
+                    options.sparkline_clip = Some(percentile);
+                }
+                "--no-sparkline" => options.no_sparkline = true,
+                "--wrap" => options.wrap = true,
+                "--active-only" => options.active_only = true,
+                "--with-history" => options.with_history = true,
+                "--no-auto-degrade" => options.no_auto_degrade = true,
+                "--swap-rxtx" => options.swap_rxtx.all = true,
+                "--swap-rxtx-adapter" => {
+                    options
+                        .swap_rxtx
+                        .adapters
+                        .push(parse_value(&arg, args.next())?);
+                }
+                "--alert-total" => {
+                    let rate: String = parse_value(&arg, args.next())?;
+                    options.alert_total = Some(parse_rate_bps(&rate).ok_or_else(|| {
+                        format!("invalid rate '{rate}' for --alert-total (e.g. 350G)")
+                    })?);
+                }
+                "--error-threshold" => {
+                    let n: f64 = parse_value(&arg, args.next())?;
+                    if !(n.is_finite() && n >= 0.0) {
+                        return Err("--error-threshold must be a number of errors/s".to_string());
+                    }
+                    options.error_threshold = Some(n);
+                }
+                "--expect" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    options.expect.push(Expectation::parse(&spec)?);
+                }
+                "--smooth" => {
+                    let factor: f64 = parse_value(&arg, args.next())?;
+                    if !(factor > 0.0 && factor <= 1.0) {
+                        return Err(format!("--smooth must be in (0, 1], got {factor}"));
+                    }
+                    options.smoothing = Some(factor);
+                }
+                "--divide-by" => {
+                    let n: u32 = parse_value(&arg, args.next())?;
+                    if n == 0 {
+                        return Err("--divide-by must be at least 1".to_string());
+                    }
+                    options.divide_by = Some(n);
+                }
+                "-h" | "--help" => options.mode = Mode::Help,
+                other => return Err(format!("unknown option '{other}'")),
+            }
+        }
+
+        check_rate_flags(&options)?;
+        Ok(options)
+    }
+}
+
+/// Refuse flags that sample rates for a format that would drop them; only
+/// `--json` prints rates, the other snapshot formats carry counters alone
+fn check_rate_flags(options: &Options) -> Result<(), String> {
+    let format = match options.mode {
+        Mode::JsonFlat => "--json-flat",
+        Mode::Csv => "--csv",
+        Mode::Prometheus => "--prometheus",
+        Mode::Serve { .. } => "--serve",
+        _ => return Ok(()),
+    };
+    let rate_flags = [
+        ("--export-avg", options.export_avg.is_some()),
+        ("--with-history", options.with_history),
+        ("--error-threshold", options.error_threshold.is_some()),
+    ];
+    match rate_flags.into_iter().find(|&(_, given)| given) {
+        Some((flag, _)) => Err(format!("{flag} only applies to --json, not {format}")),
+        None => Ok(()),
+    }
+}
+
+/// Pick a snapshot output format, refusing to mix JSON, CSV and Prometheus
+fn set_output_mode(options: &mut Options, mode: Mode) -> Result<(), String> {
+    // Both JSON flavours count as one format; the last one given wins
+    let format = |mode| match mode {
+        Mode::Json | Mode::JsonFlat => Some("--json"),
+        Mode::Csv => Some("--csv"),
+        Mode::Prometheus => Some("--prometheus"),
+        Mode::List => Some("--list"),
+        Mode::Serve { .. } => Some("--serve"),
+        Mode::Merge => Some("--merge"),
+        Mode::Watch => Some("--watch"),
+        _ => None,
+    };
+    if let (Some(earlier), Some(later)) = (format(options.mode), format(mode)) {
+        if earlier != later {
+            return Err(format!(
+                "{earlier} and {later} are mutually exclusive; pick one output format"
+            ));
+        }
+    }
+    options.mode = mode;
+    Ok(())
+}
+
+/// Parse the value following a flag
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} requires a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}' for {flag}"))
+}
+
+/// Parse a positive number of seconds, fractions allowed
+fn parse_positive_secs(flag: &str, value: Option<String>) -> Result<Duration, String> {
+    let secs: f64 = parse_value(flag, value)?;
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| format!("{flag} must be positive, got {secs}"))
+}
+
+/// Parse a counter notation, for `--counter-notation` and the config file
+pub fn parse_counter_notation(name: &str) -> Result<CounterNotation, String> {
+    name.parse()
+        .map_err(|()| format!("invalid counter notation '{name}' (expected auto or eng)"))
+}
+
+/// Parse a theme name, for `--theme` and the config file
+pub fn parse_theme(name: &str) -> Result<Theme, String> {
+    name.parse()
+        .map_err(|()| format!("invalid theme '{name}' (expected dark, light or mono)"))
+}
+
+/// Parse a listen address; a bare `:port` listens on every interface
+fn parse_listen_addr(addr: &str) -> Result<SocketAddr, String> {
+    let parsed = match addr.strip_prefix(':') {
+        Some(port) => port
+            .parse()
+            .ok()
+            .map(|port| SocketAddr::from(([0, 0, 0, 0], port))),
+        None => addr.parse().ok(),
+    };
+    parsed.ok_or_else(|| format!("invalid address '{addr}' for --serve (e.g. :9300)"))
+}
+
+/// Parse a terminal size such as `120x40`; both sides must be non-zero
+fn parse_size(size: &str) -> Option<(u16, u16)> {
+    let (width, height) = size.split_once(['x', 'X'])?;
+    let width: u16 = width.parse().ok()?;
+    let height: u16 = height.parse().ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let options = parse(&[
+            "--json",
+            "--show-empty",
+            "--split-sparkline",
+            "--refresh-on-change",
+            "--adaptive-refresh",
+            "--interval-jitter",
+            "--no-sparkline",
+            "--wrap",
+            "--active-only",
+            "--no-auto-degrade",
+        ])
+        .unwrap();
+        assert_eq!(options.mode, Mode::Json);
+        assert!(options.show_empty);
+        assert!(options.split_sparkline);
+        assert!(options.refresh_on_change);
+        assert!(options.adaptive_refresh);
+        assert!(options.interval_jitter);
+        assert!(options.no_sparkline);
+        assert!(options.wrap);
+        assert!(options.active_only);
+        assert!(options.no_auto_degrade);
+    }
+
+    #[test]
+    fn test_parse_json_flat() {
+        assert_eq!(parse(&["--json-flat"]).unwrap().mode, Mode::JsonFlat);
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(parse(&["--csv"]).unwrap().mode, Mode::Csv);
+        for conflicting in [["--json", "--csv"], ["--csv", "--json-flat"]] {
+            let err = parse(&conflicting).unwrap_err();
+            assert!(err.contains("mutually exclusive"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse(&["--list"]).unwrap().mode, Mode::List);
+        assert!(parse(&["--list", "--csv"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        let mode = |args: &[&str]| parse(args).map(|options| options.mode);
+        assert_eq!(
+            mode(&["--serve", ":9300"]),
+            Ok(Mode::Serve {
+                addr: "0.0.0.0:9300".parse().unwrap()
+            })
+        );
+        assert_eq!(
+            mode(&["--serve", "127.0.0.1:9300"]),
+            Ok(Mode::Serve {
+                addr: "127.0.0.1:9300".parse().unwrap()
+            })
+        );
+        assert!(parse(&["--serve", "9300"]).is_err());
+        assert!(parse(&["--serve", ":http"]).is_err());
+        assert!(parse(&["--serve", ":9300", "--prometheus"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        let options = parse(&["--watch", "--interval", "1000"]).unwrap();
+        assert_eq!(options.mode, Mode::Watch);
+        assert_eq!(options.interval, Some(Duration::from_secs(1)));
+        assert!(parse(&["--watch", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_merge() {
+        let options = parse(&["--merge", "a.json", "b.json", "--show-empty"]).unwrap();
+        assert_eq!(options.mode, Mode::Merge);
+        assert_eq!(options.merge, vec!["a.json", "b.json"]);
+        assert!(options.show_empty);
+
+        assert!(parse(&["--merge"]).is_err());
+        assert!(parse(&["--merge", "--json"]).is_err());
+        assert!(parse(&["--json", "--merge", "a.json"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_prometheus() {
+        assert_eq!(parse(&["--prometheus"]).unwrap().mode, Mode::Prometheus);
+        let err = parse(&["--prometheus", "--json"]).unwrap_err();
+        assert!(err.contains("--prometheus and --json"), "{err}");
+        assert!(parse(&["--csv", "--prometheus"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_render_once() {
+        assert_eq!(
+            parse(&["--render-once", "120x40"]).unwrap().mode,
+            Mode::RenderOnce {
+                width: 120,
+                height: 40
+            }
+        );
+        assert!(parse(&["--render-once", "120"]).is_err());
+        assert!(parse(&["--render-once", "0x40"]).is_err());
+        assert!(parse(&["--render-once"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_doctor() {
+        assert_eq!(parse(&["doctor"]).unwrap().mode, Mode::Doctor);
+    }
+
+    #[test]
+    fn test_parse_smooth() {
+        assert_eq!(parse(&["--smooth", "0.5"]).unwrap().smoothing, Some(0.5));
+        assert!(parse(&["--smooth"]).is_err());
+        assert!(parse(&["--smooth", "abc"]).is_err());
+        assert!(parse(&["--smooth", "0"]).is_err());
+        assert!(parse(&["--smooth", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_sparkline_clip() {
+        let clip = |args: &[&str]| parse(args).map(|options| options.sparkline_clip);
+        assert_eq!(clip(&[]), Ok(None));
+        assert_eq!(clip(&["--sparkline-clip", "95"]), Ok(Some(95.0)));
+        assert!(clip(&["--sparkline-clip", "0"]).is_err());
+        assert!(clip(&["--sparkline-clip", "101"]).is_err());
+        assert!(clip(&["--sparkline-clip", "NaN"]).is_err());
+    }
+
+    #[test]
+    fn test_rate_flags_need_json() {
+        for format in ["--json-flat", "--csv", "--prometheus"] {
+            let err = parse(&[format, "--export-avg", "2"]).unwrap_err();
+            assert_eq!(
+                err,
+                format!("--export-avg only applies to --json, not {format}")
+            );
+        }
+        // Flag order does not matter
+        let err = parse(&["--error-threshold", "1", "--csv"]).unwrap_err();
+        assert!(err.starts_with("--error-threshold"), "{err}");
+        let err = parse(&["--serve", ":9300", "--with-history"]).unwrap_err();
+        assert!(err.starts_with("--with-history"), "{err}");
+
+        assert!(parse(&["--json", "--export-avg", "2", "--with-history"]).is_ok());
+        // The TUI flags ports by --error-threshold too
+        assert!(parse(&["--error-threshold", "1"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_export_avg() {
+        let options = parse(&["--json", "--export-avg", "1.5"]).unwrap();
+        assert_eq!(options.export_avg, Some(Duration::from_millis(1500)));
+        assert!(parse(&["--export-avg", "0"]).is_err());
+        assert!(parse(&["--export-avg", "-1"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_history_seconds() {
+        let options = parse(&["--history-seconds", "600"]).unwrap();
+        assert_eq!(options.history_window, Some(Duration::from_mins(10)));
+        let err = parse(&["--history-seconds", "0"]).unwrap_err();
+        assert!(err.contains("--history-seconds must be positive"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_with_history() {
+        assert!(!parse(&["--json"]).unwrap().with_history);
+        assert!(parse(&["--json", "--with-history"]).unwrap().with_history);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        let options = parse(&["--interval", "2000"]).unwrap();
+        assert_eq!(options.interval, Some(Duration::from_secs(2)));
+        assert!(parse(&["--interval", "0"]).is_err());
+        assert!(parse(&["--interval", "1.5"]).is_err());
+        assert!(parse(&["--interval"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_alert_total() {
+        let options = parse(&["--alert-total", "350G"]).unwrap();
+        assert_eq!(options.alert_total, Some(350e9));
+        assert!(parse(&["--alert-total", "fast"]).is_err());
+        assert!(parse(&["--alert-total"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_threshold() {
+        assert_eq!(parse(&[]).unwrap().error_threshold, None);
+        let options = parse(&["--error-threshold", "0.5"]).unwrap();
+        assert_eq!(options.error_threshold, Some(0.5));
+        assert!(parse(&["--error-threshold", "-1"]).is_err());
+        assert!(parse(&["--error-threshold", "NaN"]).is_err());
+        assert!(parse(&["--error-threshold"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_unit() {
+        assert_eq!(parse(&[]).unwrap().time_unit, TimeUnit::Auto);
+        let options = parse(&["--time-unit", "s"]).unwrap();
+        assert_eq!(options.time_unit, TimeUnit::Seconds);
+        assert!(parse(&["--time-unit", "h"])
+            .unwrap_err()
+            .contains("s, m or auto"));
+    }
+
+    #[test]
+    fn test_parse_columns_flag() {
+        let options = parse(&["--columns", "port,rx,tx"]).unwrap();
+        assert_eq!(
+            options.columns,
+            Some(vec![Column::Port, Column::Rx, Column::Tx])
+        );
+        assert!(parse(&["--columns", "port,nope"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_counter_notation() {
+        assert_eq!(parse(&[]).unwrap().counter_notation, None);
+        let options = parse(&["--counter-notation", "eng"]).unwrap();
+        assert_eq!(options.counter_notation, Some(CounterNotation::Engineering));
+        assert!(parse(&["--counter-notation", "sci"])
+            .unwrap_err()
+            .contains("invalid counter notation 'sci'"));
+    }
+
+    #[test]
+    fn test_parse_error_counters() {
+        let options = parse(&["--error-counters", "xmit_wait,symbol_errors"]).unwrap();
+        assert_eq!(
+            options.error_counters,
+            Some(vec![Counter::XmitWait, Counter::SymbolErrors])
+        );
+        assert!(parse(&["--error-counters", "nope"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_swap_rxtx() {
+        assert_eq!(parse(&[]).unwrap().swap_rxtx, SwapRxTx::default());
+        assert!(parse(&["--swap-rxtx"]).unwrap().swap_rxtx.all);
+
+        let options = parse(&[
+            "--swap-rxtx-adapter",
+            "mlx5_0",
+            "--swap-rxtx-adapter",
+            "mlx5_2",
+        ])
+        .unwrap();
+        assert!(!options.swap_rxtx.all);
+        assert_eq!(options.swap_rxtx.adapters, vec!["mlx5_0", "mlx5_2"]);
+        assert!(parse(&["--swap-rxtx-adapter"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_history_file() {
+        let options = parse(&["--history-file", "history.json"]).unwrap();
+        assert_eq!(options.history_file, Some(PathBuf::from("history.json")));
+        assert!(parse(&["--history-file"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_baseline_save() {
+        let options = parse(&["--baseline-save", "before.json"]).unwrap();
+        assert_eq!(options.baseline_save, Some(PathBuf::from("before.json")));
+        assert!(parse(&["--baseline-save"]).is_err());
+
+        let options = parse(&["--baseline", "before.json"]).unwrap();
+        assert_eq!(options.baseline, Some(PathBuf::from("before.json")));
+        assert!(parse(&["--baseline"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_highlight() {
+        let options = parse(&["--highlight", "mlx5_1"]).unwrap();
+        assert_eq!(options.highlight.as_deref(), Some("mlx5_1"));
+        assert!(parse(&["--highlight", "mlx5_("]).is_err());
+        assert!(parse(&["--highlight"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        assert_eq!(parse(&[]).unwrap().theme, None);
+        assert_eq!(
+            parse(&["--theme", "light"]).unwrap().theme,
+            Some(Theme::LIGHT)
+        );
+        assert_eq!(
+            parse(&["--theme", "mono"]).unwrap().theme,
+            Some(Theme::MONO)
+        );
+        let err = parse(&["--theme", "solarized"]).unwrap_err();
+        assert!(err.contains("invalid theme 'solarized'"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_title() {
+        assert_eq!(parse(&[]).unwrap().title, None);
+        let options = parse(&["--title", "Rack 3 Node 7"]).unwrap();
+        assert_eq!(options.title.as_deref(), Some("Rack 3 Node 7"));
+        assert!(parse(&["--title"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_expect() {
+        let options = parse(&[
+            "--expect",
+            "mlx5_0:1=ACTIVE@200G",
+            "--expect",
+            "mlx5_1:*=ACTIVE",
+        ])
+        .unwrap();
+        assert_eq!(options.expect.len(), 2);
+        assert_eq!(options.expect[1].port, None);
+        assert!(parse(&["--expect", "mlx5_0:1=UP"]).is_err());
+        assert!(parse(&["--expect"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_divide_by() {
+        assert_eq!(parse(&["--divide-by", "8"]).unwrap().divide_by, Some(8));
+        assert!(parse(&["--divide-by", "0"]).is_err());
+        assert!(parse(&["--divide-by", "-2"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_option() {
+        assert!(parse(&["--bogus"]).is_err());
+    }
+}
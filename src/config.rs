@@ -0,0 +1,223 @@
+//! Defaults from a config file, so common flags need not be typed every time
+//!
+//! The file is `$XDG_CONFIG_HOME/ibtop/config.toml`, else
+//! `~/.config/ibtop/config.toml`; without one, ibtop runs on its built-in
+//! defaults. Flags given on the command line win over the file:
+//!
+//! ```toml
+//! interval = 500           # ms, like --interval
+//! history_seconds = 600    # like --history-seconds
+//! sort = "total"           # initial port order: name, rx, tx, total, errors
+//! theme = "light"          # like --theme
+//! counter_notation = "eng" # like --counter-notation
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::cli::{parse_counter_notation, parse_theme, Options};
+use crate::ui::{CounterNotation, SortKey, Theme};
+
+/// Settings read from the config file; unset keys keep the built-in default
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "ConfigFile")]
+pub struct Config {
+    /// Counter polling interval
+    pub interval: Option<Duration>,
+    /// History kept for charts and sparklines
+    pub history_window: Option<Duration>,
+    /// Port order at startup, as `s` cycles through it
+    pub sort_key: Option<SortKey>,
+    /// Colour preset, as `--theme` picks it
+    pub theme: Option<Theme>,
+    /// How the Errors tab writes counters, as `--counter-notation` sets it
+    pub counter_notation: Option<CounterNotation>,
+}
+
+/// The keys as written in the file, checked when converted into `Config`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    /// Milliseconds, like `--interval`
+    interval: Option<u64>,
+    history_seconds: Option<f64>,
+    sort: Option<String>,
+    theme: Option<String>,
+    counter_notation: Option<String>,
+}
+
+impl TryFrom<ConfigFile> for Config {
+    type Error = String;
+
+    fn try_from(file: ConfigFile) -> Result<Self, Self::Error> {
+        let interval = match file.interval {
+            Some(0) => return Err("interval must be at least 1 ms".to_string()),
+            ms => ms.map(Duration::from_millis),
+        };
+        let history_window = file
+            .history_seconds
+            .map(|secs| {
+                Duration::try_from_secs_f64(secs)
+                    .ok()
+                    .filter(|d| !d.is_zero())
+                    .ok_or_else(|| format!("history_seconds must be positive, got {secs}"))
+            })
+            .transpose()?;
+        let sort_key = file
+            .sort
+            .map(|sort| {
+                sort.parse().map_err(|()| {
+                    format!("invalid sort '{sort}' (expected name, rx, tx, total or errors)")
+                })
+            })
+            .transpose()?;
+        let theme = file.theme.as_deref().map(parse_theme).transpose()?;
+        let counter_notation = file
+            .counter_notation
+            .as_deref()
+            .map(parse_counter_notation)
+            .transpose()?;
+        Ok(Config {
+            interval,
+            history_window,
+            sort_key,
+            theme,
+            counter_notation,
+        })
+    }
+}
+
+impl Config {
+    /// Parse the text of a config file
+    pub fn parse(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|err| err.message().to_string())
+    }
+
+    /// Fill in the options the command line left unset
+    pub fn apply(&self, options: &mut Options) {
+        options.interval = options.interval.or(self.interval);
+        options.history_window = options.history_window.or(self.history_window);
+        options.sort_key = options.sort_key.or(self.sort_key);
+        options.theme = options.theme.or(self.theme);
+        options.counter_notation = options.counter_notation.or(self.counter_notation);
+    }
+}
+
+/// Where the config file is looked for, in order
+fn candidates(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Vec<PathBuf> {
+    // An empty variable counts as unset, as the XDG spec asks
+    let dir = |value: Option<OsString>| value.filter(|v| !v.is_empty()).map(PathBuf::from);
+    let mut paths = Vec::new();
+    if let Some(config_home) = dir(xdg_config_home) {
+        paths.push(config_home.join("ibtop").join("config.toml"));
+    }
+    if let Some(home) = dir(home) {
+        let path = home.join(".config").join("ibtop").join("config.toml");
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Read the first config file that exists; no file is an empty config
+///
+/// Errors name the file, for the message printed before exiting.
+pub fn load() -> Result<Config, String> {
+    let paths = candidates(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    );
+    match paths.iter().find(|path| path.is_file()) {
+        Some(path) => load_from(path),
+        None => Ok(Config::default()),
+    }
+}
+
+fn load_from(path: &Path) -> Result<Config, String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| Config::parse(&text))
+        .map_err(|err| format!("{}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_wins_over_config() {
+        let config = Config::parse(
+            "interval = 500\n\
+             history_seconds = 600\n\
+             sort = \"total\"\n\
+             theme = \"mono\"\n\
+             counter_notation = \"eng\"\n",
+        )
+        .unwrap();
+
+        let mut options = Options::default();
+        config.apply(&mut options);
+        assert_eq!(options.interval, Some(Duration::from_millis(500)));
+        assert_eq!(options.history_window, Some(Duration::from_mins(10)));
+        assert_eq!(options.sort_key, Some(SortKey::Total));
+        assert_eq!(options.theme, Some(Theme::MONO));
+        assert_eq!(options.counter_notation, Some(CounterNotation::Engineering));
+
+        let args = ["--interval", "2000", "--theme", "light"].map(String::from);
+        let mut options = Options::parse(args).unwrap();
+        config.apply(&mut options);
+        assert_eq!(options.interval, Some(Duration::from_secs(2)));
+        assert_eq!(options.theme, Some(Theme::LIGHT));
+        assert_eq!(options.history_window, Some(Duration::from_mins(10)));
+    }
+
+    #[test]
+    fn test_bad_config_is_reported() {
+        assert!(Config::parse("intervall = 500").is_err());
+        assert!(Config::parse("interval = \"fast\"").is_err());
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+
+        for text in [
+            "interval = 0",
+            "history_seconds = -1",
+            "sort = \"name,rx\"",
+            "theme = \"neon\"",
+            "counter_notation = \"sci\"",
+        ] {
+            assert!(Config::parse(text).is_err(), "{text}");
+        }
+        assert!(Config::parse("sort = \"up\"")
+            .unwrap_err()
+            .contains("invalid sort 'up'"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "sort = 3").unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.starts_with(&path.display().to_string()), "{err}");
+    }
+
+    #[test]
+    fn test_config_search_order() {
+        assert_eq!(
+            candidates(Some("/etc/xdg".into()), Some("/home/ib".into())),
+            vec![
+                PathBuf::from("/etc/xdg/ibtop/config.toml"),
+                PathBuf::from("/home/ib/.config/ibtop/config.toml"),
+            ]
+        );
+        assert_eq!(
+            candidates(Some(OsString::new()), Some("/home/ib".into())),
+            vec![PathBuf::from("/home/ib/.config/ibtop/config.toml")]
+        );
+        assert_eq!(
+            candidates(Some("/home/ib/.config".into()), Some("/home/ib".into())).len(),
+            1
+        );
+        assert!(candidates(None, None).is_empty());
+    }
+}
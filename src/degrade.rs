@@ -0,0 +1,112 @@
+//! Shedding optional rendering work when frames cannot keep up
+//!
+//! On an overloaded node a frame can take longer than the UI refresh
+//! interval, so input lags and the display stutters. The governor watches
+//! how long each frame's work takes against its budget and, once frames
+//! have been far too slow for a while, asks the UI to drop its costlier
+//! extras (sparklines, the state pulse). They return once frames are
+//! comfortably fast again.
+
+use std::time::Duration;
+
+/// A frame slower than this many budgets means under half the target rate
+const SLOW_FACTOR: u32 = 2;
+/// Consecutive slow frames (about a second at 30 Hz) before degrading
+const DEGRADE_AFTER: u32 = 30;
+/// Consecutive fast frames (about five seconds) before restoring; longer
+/// than `DEGRADE_AFTER` so a node at the edge does not flip back and forth
+const RESTORE_AFTER: u32 = 150;
+
+/// What the UI should do after a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Degrade,
+    Restore,
+}
+
+/// Feedback controller deciding when to degrade and restore
+#[derive(Debug, Default)]
+pub struct FrameGovernor {
+    degraded: bool,
+    /// Frames in a row that were slow (while normal) or fast (while degraded)
+    streak: u32,
+}
+
+impl FrameGovernor {
+    /// Record how long a frame's work took against its `budget`
+    ///
+    /// Only slow frames count towards degrading, and only frames that used
+    /// under half their budget count towards restoring; anything else
+    /// restarts the streak.
+    pub fn record(&mut self, work: Duration, budget: Duration) -> Option<Decision> {
+        let counts = if self.degraded {
+            work < budget / 2
+        } else {
+            work > budget * SLOW_FACTOR
+        };
+        self.streak = if counts { self.streak + 1 } else { 0 };
+
+        let needed = if self.degraded {
+            RESTORE_AFTER
+        } else {
+            DEGRADE_AFTER
+        };
+        if self.streak < needed {
+            return None;
+        }
+
+        self.streak = 0;
+        self.degraded = !self.degraded;
+        Some(if self.degraded {
+            Decision::Degrade
+        } else {
+            Decision::Restore
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: Duration = Duration::from_millis(33);
+    const SLOW: Duration = Duration::from_millis(80);
+    const OK: Duration = Duration::from_millis(25);
+    const FAST: Duration = Duration::from_millis(5);
+
+    fn run(governor: &mut FrameGovernor, work: Duration, frames: u32) -> Vec<Decision> {
+        (0..frames)
+            .filter_map(|_| governor.record(work, BUDGET))
+            .collect()
+    }
+
+    #[test]
+    fn test_degrades_only_when_slow_is_sustained() {
+        let mut governor = FrameGovernor::default();
+
+        // Short stalls are ignored
+        assert!(run(&mut governor, SLOW, DEGRADE_AFTER - 1).is_empty());
+        assert!(run(&mut governor, OK, 1).is_empty());
+        assert!(run(&mut governor, SLOW, DEGRADE_AFTER - 1).is_empty());
+
+        assert_eq!(run(&mut governor, SLOW, 1), vec![Decision::Degrade]);
+        // No repeated decisions while it stays slow
+        assert!(run(&mut governor, SLOW, DEGRADE_AFTER * 3).is_empty());
+    }
+
+    #[test]
+    fn test_restores_after_sustained_headroom() {
+        let mut governor = FrameGovernor::default();
+        assert_eq!(
+            run(&mut governor, SLOW, DEGRADE_AFTER),
+            vec![Decision::Degrade]
+        );
+
+        // Merely keeping up is not enough headroom to restore
+        assert!(run(&mut governor, OK, RESTORE_AFTER * 2).is_empty());
+
+        assert!(run(&mut governor, FAST, RESTORE_AFTER - 1).is_empty());
+        assert_eq!(run(&mut governor, FAST, 1), vec![Decision::Restore]);
+        assert!(run(&mut governor, FAST, RESTORE_AFTER).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+//! Member links of RoCE LAG (`mlx5_bond<N>`) devices
+//!
+//! A bonded RDMA device stays ACTIVE while any member link is up, so its
+//! port state hides a degraded bond. The members live on the netdev side:
+//! the port's GID table names the bond netdev, and the kernel bonding
+//! driver lists its slaves and their MII status below it.
+
+use std::path::Path;
+
+use crate::types::PortState;
+
+/// Where the kernel exposes network devices
+const NET_CLASS_PATH: &str = "/sys/class/net";
+
+/// Member links of the bond behind `adapter_path`, with their state
+///
+/// Empty when the bond netdev or its bonding directory cannot be read.
+pub(crate) fn read_bond_members(adapter_path: &Path) -> Vec<(String, PortState)> {
+    read_bond_members_in(adapter_path, Path::new(NET_CLASS_PATH))
+}
+
+fn read_bond_members_in(adapter_path: &Path, net_root: &Path) -> Vec<(String, PortState)> {
+    let Some(bond) = bond_netdev(adapter_path) else {
+        return Vec::new();
+    };
+    let Ok(slaves) = std::fs::read_to_string(net_root.join(&bond).join("bonding/slaves")) else {
+        return Vec::new();
+    };
+
+    slaves
+        .split_whitespace()
+        .map(|slave| {
+            // The bond's own view of each slave, else the slave's operstate
+            let status = std::fs::read_to_string(
+                net_root
+                    .join(&bond)
+                    .join(format!("lower_{slave}"))
+                    .join("bonding_slave/mii_status"),
+            )
+            .or_else(|_| std::fs::read_to_string(net_root.join(slave).join("operstate")))
+            .unwrap_or_default();
+            let state = match status.trim() {
+                "up" => PortState::Active,
+                "down" => PortState::Down,
+                _ => PortState::Unknown,
+            };
+            (slave.to_string(), state)
+        })
+        .collect()
+}
+
+/// Netdev behind the device's first port, from its default GID entry
+fn bond_netdev(adapter_path: &Path) -> Option<String> {
+    let ports = std::fs::read_dir(adapter_path.join("ports")).ok()?;
+    let mut port_paths: Vec<_> = ports.flatten().map(|entry| entry.path()).collect();
+    port_paths.sort();
+    port_paths.iter().find_map(|port| {
+        std::fs::read_to_string(port.join("gid_attrs/ndevs/0"))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_bond_members_from_sysfs_fixture() {
+        let root = tempfile::tempdir().unwrap();
+        let adapter = root.path().join("infiniband/mlx5_bond_0");
+        let net = root.path().join("net");
+
+        fs::create_dir_all(adapter.join("ports/1/gid_attrs/ndevs")).unwrap();
+        fs::write(adapter.join("ports/1/gid_attrs/ndevs/0"), "bond0\n").unwrap();
+        fs::create_dir_all(net.join("bond0/bonding")).unwrap();
+        fs::write(
+            net.join("bond0/bonding/slaves"),
+            "ens1f0np0 ens1f1np1 ens2\n",
+        )
+        .unwrap();
+        for (slave, status) in [("ens1f0np0", "up"), ("ens1f1np1", "down")] {
+            let dir = net.join(format!("bond0/lower_{slave}/bonding_slave"));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("mii_status"), format!("{status}\n")).unwrap();
+        }
+        // No bonding_slave directory: fall back to the slave's operstate
+        fs::create_dir_all(net.join("ens2")).unwrap();
+        fs::write(net.join("ens2/operstate"), "up\n").unwrap();
+
+        assert_eq!(
+            read_bond_members_in(&adapter, &net),
+            vec![
+                ("ens1f0np0".to_string(), PortState::Active),
+                ("ens1f1np1".to_string(), PortState::Down),
+                ("ens2".to_string(), PortState::Active),
+            ]
+        );
+
+        // Without a bond netdev there is nothing to report
+        fs::remove_file(adapter.join("ports/1/gid_attrs/ndevs/0")).unwrap();
+        assert!(read_bond_members_in(&adapter, &net).is_empty());
+    }
+}
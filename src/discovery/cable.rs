@@ -0,0 +1,119 @@
+//! Cable details for the Info tab
+//!
+//! They are not part of the counters ibtop polls: a few drivers expose the
+//! QSFP module EEPROM, which names the cable. hfi1 does so through debugfs;
+//! mlx5 only through ethtool, so most hosts have no cable info.
+
+use std::path::Path;
+
+/// debugfs root where hfi1 exposes `qsfp<port>` EEPROM dumps
+const HFI1_DEBUGFS: &str = "/sys/kernel/debug/hfi1";
+
+/// SFF-8024 identifiers of modules using the SFF-8636 memory map
+const SFF8636_IDENTIFIERS: [u8; 3] = [0x0C, 0x0D, 0x11];
+
+/// Byte ranges of the ASCII fields in SFF-8636 page 00h
+const VENDOR_NAME: std::ops::Range<usize> = 148..164;
+const VENDOR_PN: std::ops::Range<usize> = 168..184;
+const VENDOR_SN: std::ops::Range<usize> = 196..212;
+/// Length of a copper or active cable, in meters
+const CABLE_LENGTH_M: usize = 146;
+
+/// Cable identification from a QSFP module EEPROM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CableInfo {
+    pub(crate) vendor: String,
+    pub(crate) part_number: String,
+    pub(crate) serial: String,
+    /// Cable length in meters; `None` for optics, which report reach instead
+    pub(crate) length_m: Option<u8>,
+}
+
+/// What the Info tab shows beyond `PortInfo`; every part is optional
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct LinkDetails {
+    pub(crate) cable: Option<CableInfo>,
+}
+
+/// Read the link details of one port
+pub(crate) fn read_link_details(adapter: &str, port: u16) -> LinkDetails {
+    let eeprom = Path::new(HFI1_DEBUGFS)
+        .join(adapter)
+        .join(format!("qsfp{port}"));
+
+    LinkDetails {
+        cable: std::fs::read(eeprom)
+            .ok()
+            .and_then(|bytes| parse_sff8636(&bytes)),
+    }
+}
+
+/// Decode the identification fields of an SFF-8636 (QSFP/QSFP28) EEPROM dump
+pub(crate) fn parse_sff8636(eeprom: &[u8]) -> Option<CableInfo> {
+    if eeprom.len() < VENDOR_SN.end || !SFF8636_IDENTIFIERS.contains(&eeprom[0]) {
+        return None;
+    }
+
+    let text = |range: std::ops::Range<usize>| {
+        String::from_utf8_lossy(&eeprom[range])
+            .trim_matches(|c: char| c == ' ' || c == '\0')
+            .to_string()
+    };
+    let info = CableInfo {
+        vendor: text(VENDOR_NAME),
+        part_number: text(VENDOR_PN),
+        serial: text(VENDOR_SN),
+        length_m: Some(eeprom[CABLE_LENGTH_M]).filter(|len| *len > 0),
+    };
+
+    // An erased or unreadable EEPROM decodes to blanks
+    (!info.vendor.is_empty() || !info.part_number.is_empty()).then_some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eeprom(identifier: u8) -> Vec<u8> {
+        let mut bytes = vec![0u8; 256];
+        bytes[0] = identifier;
+        bytes[CABLE_LENGTH_M] = 3;
+        let mut put = |range: std::ops::Range<usize>, text: &str| {
+            let field = &mut bytes[range];
+            field.fill(b' ');
+            field[..text.len()].copy_from_slice(text.as_bytes());
+        };
+        put(VENDOR_NAME, "Mellanox");
+        put(VENDOR_PN, "MCP1600-E003E30");
+        put(VENDOR_SN, "MT1945VS01234");
+        bytes
+    }
+
+    #[test]
+    fn test_parse_sff8636_fixture() {
+        assert_eq!(
+            parse_sff8636(&eeprom(0x11)),
+            Some(CableInfo {
+                vendor: "Mellanox".to_string(),
+                part_number: "MCP1600-E003E30".to_string(),
+                serial: "MT1945VS01234".to_string(),
+                length_m: Some(3),
+            })
+        );
+
+        // Optics report no copper length
+        let mut optic = eeprom(0x0D);
+        optic[CABLE_LENGTH_M] = 0;
+        assert_eq!(parse_sff8636(&optic).unwrap().length_m, None);
+    }
+
+    #[test]
+    fn test_parse_sff8636_rejects_unknown_data() {
+        // SFP identifier, truncated dump, blank EEPROM
+        assert_eq!(parse_sff8636(&eeprom(0x03)), None);
+        assert_eq!(parse_sff8636(&eeprom(0x11)[..128]), None);
+        let mut blank = vec![0u8; 256];
+        blank[0] = 0x11;
+        assert_eq!(parse_sff8636(&blank), None);
+    }
+}
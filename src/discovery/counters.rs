@@ -0,0 +1,246 @@
+//! Pluggable readers for the small sysfs counter files
+//!
+//! Every port exposes a handful of ~10 byte counter files, so on large nodes a
+//! refresh is dominated by open/read/close syscalls rather than by data. The
+//! reader is abstracted so that cost can be batched where the kernel allows it.
+
+use std::path::PathBuf;
+
+/// Reads a batch of small files in one go
+pub(crate) trait CounterReader: Sync {
+    /// Short name reported by `ibtop doctor`
+    fn name(&self) -> &'static str;
+
+    /// Read every file in `paths`, `None` for files that could not be read
+    fn read_all(&self, paths: &[PathBuf]) -> Vec<Option<String>>;
+}
+
+/// Plain `std::fs` reader, one file at a time
+pub(crate) struct StdReader;
+
+impl CounterReader for StdReader {
+    fn name(&self) -> &'static str {
+        "std"
+    }
+
+    fn read_all(&self, paths: &[PathBuf]) -> Vec<Option<String>> {
+        paths
+            .iter()
+            .map(|path| std::fs::read_to_string(path).ok())
+            .collect()
+    }
+}
+
+/// Reader used for live discovery
+pub(crate) fn default_reader() -> &'static dyn CounterReader {
+    #[cfg(feature = "io-uring")]
+    {
+        &uring::UringReader
+    }
+    #[cfg(not(feature = "io-uring"))]
+    {
+        &StdReader
+    }
+}
+
+#[cfg(feature = "io-uring")]
+mod uring {
+    //! `io_uring` reader: opens, reads and closes a whole batch with three
+    //! submissions instead of three syscalls per file.
+
+    use std::cell::RefCell;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    use io_uring::{opcode, squeue, types, IoUring};
+
+    use super::{CounterReader, StdReader};
+
+    /// Submission queue size; larger batches are submitted in chunks
+    const RING_ENTRIES: u32 = 64;
+    /// Counter files hold a single decimal number
+    const READ_BUF_LEN: u32 = 64;
+
+    thread_local! {
+        // `None` when io_uring is unavailable (old kernel, seccomp, ...)
+        static RING: RefCell<Option<IoUring>> = RefCell::new(IoUring::new(RING_ENTRIES).ok());
+    }
+
+    pub(crate) struct UringReader;
+
+    impl CounterReader for UringReader {
+        fn name(&self) -> &'static str {
+            "io_uring"
+        }
+
+        fn read_all(&self, paths: &[PathBuf]) -> Vec<Option<String>> {
+            RING.with(|ring| {
+                let mut ring = ring.borrow_mut();
+                if let Some(uring) = ring.as_mut() {
+                    match read_batch(uring, paths) {
+                        Ok(values) => return values,
+                        // A failed batch may leave entries in flight, so this
+                        // thread's ring is not used again
+                        Err(_) => *ring = None,
+                    }
+                }
+                StdReader.read_all(paths)
+            })
+        }
+    }
+
+    fn read_batch(ring: &mut IoUring, paths: &[PathBuf]) -> io::Result<Vec<Option<String>>> {
+        let c_paths = paths
+            .iter()
+            .map(|path| CString::new(path.as_os_str().as_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut bufs = vec![[0u8; READ_BUF_LEN as usize]; paths.len()];
+        let mut opened = vec![-libc::EBADF; paths.len()];
+        let mut read = vec![-libc::EBADF; paths.len()];
+
+        let opens = c_paths.iter().enumerate().map(|(i, path)| {
+            let entry = opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+                .flags(libc::O_RDONLY | libc::O_CLOEXEC)
+                .build();
+            (i, entry)
+        });
+        let mut outcome = submit_all(ring, opens, &mut opened);
+        let fds: Vec<Option<i32>> = opened
+            .iter()
+            .map(|&res| (res >= 0).then_some(res))
+            .collect();
+
+        if outcome.is_ok() {
+            let reads = fds
+                .iter()
+                .zip(bufs.iter_mut())
+                .enumerate()
+                .filter_map(|(i, (fd, buf))| {
+                    let entry =
+                        opcode::Read::new(types::Fd((*fd)?), buf.as_mut_ptr(), READ_BUF_LEN)
+                            .build();
+                    Some((i, entry))
+                });
+            outcome = submit_all(ring, reads, &mut read);
+        }
+
+        if let Err(err) = outcome {
+            // Opens or reads still in flight may write here, so never free it
+            std::mem::forget(c_paths);
+            std::mem::forget(bufs);
+            for fd in fds.into_iter().flatten() {
+                // SAFETY: `fd` was opened by this batch and is closed only here
+                unsafe { libc::close(fd) };
+            }
+            return Err(err);
+        }
+
+        // Closes already submitted still run if this fails, so none is retried
+        let closes = fds
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fd)| Some((i, opcode::Close::new(types::Fd((*fd)?)).build())));
+        submit_all(ring, closes, &mut vec![0; paths.len()])?;
+
+        Ok(read
+            .into_iter()
+            .zip(&bufs)
+            .map(|(res, buf)| {
+                let len = usize::try_from(res).ok()?;
+                String::from_utf8(buf[..len].to_vec()).ok()
+            })
+            .collect())
+    }
+
+    /// Submit `entries` (tagged with their batch index) and wait for all of
+    /// them, storing each result at its index in `results`
+    ///
+    /// On error, entries may still be in flight: the ring must not be used
+    /// again and the memory they point to must not be freed.
+    fn submit_all(
+        ring: &mut IoUring,
+        entries: impl Iterator<Item = (usize, squeue::Entry)>,
+        results: &mut [i32],
+    ) -> io::Result<()> {
+        let mut entries = entries.peekable();
+
+        while entries.peek().is_some() {
+            let mut pending = 0;
+            while let Some((i, entry)) = entries.peek() {
+                // SAFETY: every pointer in the entry (path or buffer) is owned by
+                // `read_batch`, which keeps it alive until the entry completes
+                // and leaks it when the batch fails.
+                if unsafe { ring.submission().push(&entry.clone().user_data(*i as u64)) }.is_err() {
+                    // Queue full: submit this chunk first
+                    break;
+                }
+                entries.next();
+                pending += 1;
+            }
+            if pending == 0 {
+                return Err(io::Error::other("submission queue full"));
+            }
+            wait_for(ring, pending, results)?;
+        }
+
+        Ok(())
+    }
+
+    /// Submit what is queued and reap `pending` completions into `results`
+    fn wait_for(ring: &mut IoUring, mut pending: usize, results: &mut [i32]) -> io::Result<()> {
+        while pending > 0 {
+            match ring.submit_and_wait(pending) {
+                // A signal (e.g. a terminal resize) only cut the wait short
+                Err(err) if err.kind() != io::ErrorKind::Interrupted => return Err(err),
+                _ => {}
+            }
+            for cqe in ring.completion() {
+                pending = pending.saturating_sub(1);
+                if let Some(slot) = usize::try_from(cqe.user_data())
+                    .ok()
+                    .and_then(|i| results.get_mut(i))
+                {
+                    *slot = cqe.result();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_std_reader_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("present"), "42\n").unwrap();
+
+        let values = StdReader.read_all(&[dir.path().join("present"), dir.path().join("absent")]);
+        assert_eq!(values, vec![Some("42\n".to_string()), None]);
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    fn test_uring_reader_matches_std() {
+        let dir = tempfile::tempdir().unwrap();
+        // More files than the ring holds, to exercise chunked submission
+        let paths: Vec<PathBuf> = (0..150)
+            .map(|i| {
+                let path = dir.path().join(format!("counter_{i}"));
+                if i % 7 != 0 {
+                    std::fs::write(&path, format!("{}\n", i * 1_000_003)).unwrap();
+                }
+                path
+            })
+            .collect();
+
+        assert_eq!(
+            uring::UringReader.read_all(&paths),
+            StdReader.read_all(&paths)
+        );
+    }
+}
@@ -1,31 +1,249 @@
-use crate::types::{AdapterInfo, PortCounters, PortInfo, PortState};
+mod bond;
+mod cable;
+mod counters;
+mod vport;
 
-const MLX5_DATA_MULTIPLIER: u64 = 4; // mlx5 reports in 32-bit words
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-pub(crate) fn discover_adapters() -> Vec<AdapterInfo> {
-    let mut adapters: Vec<AdapterInfo> = Vec::new();
+use crate::types::{AdapterInfo, LinkLayer, PhysState, PortCounters, PortInfo, PortState};
+pub(crate) use cable::{read_link_details, LinkDetails};
+pub(crate) use counters::{default_reader, CounterReader};
+pub(crate) use vport::{parse_vport_counters, VportCounters};
 
-    let path =
-        std::env::var("INFINIBAND_PATH").unwrap_or_else(|_| "/sys/class/infiniband/".to_string());
+pub(crate) const MLX5_DATA_MULTIPLIER: u64 = 4; // mlx5 reports in 32-bit words
+
+/// Counter files read for every port, in `PortCounters` field order
+const COUNTER_FILES: [&str; 12] = [
+    "port_rcv_data",
+    "port_xmit_data",
+    "port_rcv_packets",
+    "port_xmit_packets",
+    "port_rcv_errors",
+    "port_xmit_discards",
+    "port_rcv_constraint_errors",
+    "link_error_recovery",
+    "link_downed",
+    "symbol_error",
+    "local_link_integrity_errors",
+    "port_xmit_wait",
+];
+
+/// Adapters whose RX and TX counters are read swapped (`--swap-rxtx`), for
+/// drivers and firmware that report `port_rcv_*` and `port_xmit_*` the
+/// wrong way round
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct SwapRxTx {
+    /// Swap on every adapter
+    pub(crate) all: bool,
+    /// Swap on these adapters only (`--swap-rxtx-adapter`)
+    pub(crate) adapters: Vec<String>,
+}
+
+impl SwapRxTx {
+    fn applies_to(&self, adapter: &str) -> bool {
+        self.all || self.adapters.iter().any(|name| name == adapter)
+    }
+}
+
+/// Joins a duplicate adapter's name to its occurrence number
+const DUPLICATE_MARKER: char = '#';
+
+/// sysfs root scanned when `INFINIBAND_PATH` is not set
+pub(crate) const DEFAULT_INFINIBAND_PATH: &str = "/sys/class/infiniband/";
+
+/// How long the last discovery pass spent reading sysfs, and what it skipped
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ReadTiming {
+    /// Time spent reading each adapter, in adapter order
+    pub(crate) per_adapter: Vec<(String, Duration)>,
+    /// Wall-clock time of the whole pass
+    pub(crate) total: Duration,
+    /// Entries under `<adapter>/ports` ignored because they are not port
+    /// numbers, as `adapter/ports/entry`
+    pub(crate) skipped_ports: Vec<String>,
+    /// Adapters renamed because another one already had their name
+    pub(crate) renamed_adapters: Vec<String>,
+}
+
+pub(crate) fn discover_adapters(swap: &SwapRxTx) -> Vec<AdapterInfo> {
+    discover_adapters_timed(swap).0
+}
+
+/// Discover adapters and report how long reading them took
+pub(crate) fn discover_adapters_timed(swap: &SwapRxTx) -> (Vec<AdapterInfo>, ReadTiming) {
+    read_adapters(
+        std::path::Path::new(&infiniband_path()),
+        true,
+        default_reader(),
+        swap,
+    )
+}
+
+/// Whether the sysfs root exists but cannot be read by this user
+pub(crate) fn permission_denied() -> bool {
+    std::fs::read_dir(infiniband_path())
+        .is_err_and(|err| err.kind() == std::io::ErrorKind::PermissionDenied)
+}
+
+/// Directories worth watching for changes: the sysfs root (hotplug) plus
+/// every port directory and its counters
+pub(crate) fn watch_paths(adapters: &[AdapterInfo]) -> Vec<PathBuf> {
+    let root = PathBuf::from(infiniband_path());
+    let mut paths = vec![root.clone()];
+    for adapter in adapters {
+        for port in &adapter.ports {
+            let port_path = root
+                .join(&adapter.name)
+                .join("ports")
+                .join(port.port_number.to_string());
+            paths.push(port_path.join("counters"));
+            paths.push(port_path);
+        }
+    }
+    paths
+}
+
+/// Every file in a port's `counters` and `hw_counters` directories with its
+/// raw contents, as `dir/file` → value, sorted by name
+pub(crate) fn read_raw_counters(adapter: &str, port: u16) -> Vec<(String, String)> {
+    read_counter_dirs(
+        &PathBuf::from(infiniband_path())
+            .join(adapter)
+            .join("ports")
+            .join(port.to_string()),
+    )
+}
+
+/// Read the counter directories below `port_path` without filtering or
+/// parsing anything; unreadable files get a placeholder value
+fn read_counter_dirs(port_path: &std::path::Path) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for dir in ["counters", "hw_counters"] {
+        let Ok(files) = std::fs::read_dir(port_path.join(dir)) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let value = std::fs::read(file.path()).map_or_else(
+                |err| format!("<unreadable: {err}>"),
+                |bytes| String::from_utf8_lossy(&bytes).trim_end().to_string(),
+            );
+            entries.push((
+                format!("{dir}/{}", file.file_name().to_string_lossy()),
+                value,
+            ));
+        }
+    }
+    entries.sort();
+    entries
+}
+
+fn infiniband_path() -> String {
+    std::env::var("INFINIBAND_PATH").unwrap_or_else(|_| DEFAULT_INFINIBAND_PATH.to_string())
+}
+
+/// Read every adapter below `path`, optionally one thread per adapter
+///
+/// Nodes with many HCAs spend most of a refresh opening tiny counter files,
+/// so reading adapters concurrently hides most of that latency.
+fn read_adapters(
+    path: &std::path::Path,
+    parallel: bool,
+    reader: &dyn CounterReader,
+    swap: &SwapRxTx,
+) -> (Vec<AdapterInfo>, ReadTiming) {
+    let start = Instant::now();
 
     let Ok(entries) = std::fs::read_dir(path) else {
-        return adapters;
+        return (Vec::new(), ReadTiming::default());
+    };
+
+    let adapter_dirs: Vec<(String, std::path::PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            Some((name, entry.path()))
+        })
+        .collect();
+
+    let timed_read = |name: String, path: std::path::PathBuf| {
+        let adapter_start = Instant::now();
+        let swap = swap.applies_to(&name);
+        let (adapter, skipped) = create_adapter_info(name, &path, reader, swap);
+        (adapter, adapter_start.elapsed(), skipped)
+    };
+
+    let mut results: Vec<(AdapterInfo, Duration, Vec<String>)> =
+        if parallel && adapter_dirs.len() > 1 {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = adapter_dirs
+                    .into_iter()
+                    .map(|(name, path)| scope.spawn(move || timed_read(name, path)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .filter_map(|handle| handle.join().ok())
+                    .collect()
+            })
+        } else {
+            adapter_dirs
+                .into_iter()
+                .map(|(name, path)| timed_read(name, path))
+                .collect()
+        };
+
+    // Thread completion order is arbitrary; sorting keeps output deterministic
+    results.sort_by(|a, b| natural_cmp(&a.0.name, &b.0.name));
+
+    let mut timing = ReadTiming {
+        per_adapter: results
+            .iter()
+            .map(|(adapter, elapsed, _)| (adapter.name.clone(), *elapsed))
+            .collect(),
+        total: start.elapsed(),
+        skipped_ports: results
+            .iter_mut()
+            .flat_map(|(_, _, skipped)| std::mem::take(skipped))
+            .collect(),
+        renamed_adapters: Vec::new(),
     };
+    let mut adapters: Vec<AdapterInfo> =
+        results.into_iter().map(|(adapter, _, _)| adapter).collect();
+    timing.renamed_adapters = disambiguate_names(&mut adapters);
 
-    for entry in entries.flatten() {
-        if let Some(adapter_name) = entry
-            .file_name()
-            .to_str()
-            .map(std::string::ToString::to_string)
-        {
-            let adapter = create_adapter_info(adapter_name, &entry.path());
-            adapters.push(adapter);
+    (adapters, timing)
+}
+
+/// Give adapters that share a name distinct ones: the first keeps it and
+/// later ones become `name#2`, `name#3`, ...
+///
+/// Metrics and history are keyed by adapter name and port, so same-named
+/// adapters (symlink quirks, namespaces) would otherwise mix their data.
+/// Returns the new names of the renamed adapters.
+pub(crate) fn disambiguate_names(adapters: &mut [AdapterInfo]) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut renamed = Vec::new();
+    for adapter in adapters {
+        let count = seen.entry(adapter.name.clone()).or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            adapter.name = format!("{}{DUPLICATE_MARKER}{count}", adapter.name);
+            renamed.push(adapter.name.clone());
         }
     }
+    renamed
+}
 
-    adapters.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+/// Name an adapter was discovered under, if it was renamed as a duplicate
+pub(crate) fn duplicated_name(name: &str) -> Option<&str> {
+    let (original, count) = name.rsplit_once(DUPLICATE_MARKER)?;
+    count.parse::<usize>().ok().map(|_| original)
+}
 
-    adapters
+/// Drop adapters that expose no ports (virtual or misconfigured devices)
+pub(crate) fn retain_with_ports(adapters: &mut Vec<AdapterInfo>) {
+    adapters.retain(|adapter| !adapter.ports.is_empty());
 }
 
 /// Natural sort comparison that handles numeric suffixes correctly
@@ -51,97 +269,177 @@ fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
     }
 }
 
-fn create_adapter_info(adapter_name: String, adapter_path: &std::path::Path) -> AdapterInfo {
+/// Read one adapter, along with the `ports` entries skipped as not being
+/// port numbers (some drivers expose extra entries there)
+fn create_adapter_info(
+    adapter_name: String,
+    adapter_path: &std::path::Path,
+    reader: &dyn CounterReader,
+    swap_rxtx: bool,
+) -> (AdapterInfo, Vec<String>) {
     let mut ports: Vec<PortInfo> = Vec::new();
+    let mut skipped = Vec::new();
     let ports_path = adapter_path.join("ports");
 
     if ports_path.exists() {
         if let Ok(ports_entries) = std::fs::read_dir(ports_path) {
             for port_entry in ports_entries.flatten() {
-                if let Some(port_name) = port_entry.file_name().to_str() {
-                    if let Ok(port_number) = port_name.parse::<u16>() {
-                        let port_info = create_port_info(port_number, adapter_path);
-                        ports.push(port_info);
-                    }
+                let port_name = port_entry.file_name();
+                let port_name = port_name.to_string_lossy();
+                if let Ok(port_number) = port_name.parse::<u16>() {
+                    let port_info = create_port_info(port_number, adapter_path, reader, swap_rxtx);
+                    ports.push(port_info);
+                } else {
+                    skipped.push(format!("{adapter_name}/ports/{port_name}"));
                 }
             }
         }
     }
+    skipped.sort();
 
-    AdapterInfo {
+    let mut adapter = AdapterInfo {
         name: adapter_name,
         ports,
+        bond_members: Vec::new(),
+        fw_ver: read_identity(&adapter_path.join("fw_ver")),
+        board_id: read_identity(&adapter_path.join("board_id")),
+        hca_type: read_identity(&adapter_path.join("hca_type")),
+    };
+    if crate::warnings::is_bond(&adapter) {
+        adapter.bond_members = bond::read_bond_members(adapter_path);
     }
+    (adapter, skipped)
 }
 
-fn create_port_info(port_number: u16, adapter_path: &std::path::Path) -> PortInfo {
+/// Read a one-line identity file such as `fw_ver`; only some drivers have them
+fn read_identity(path: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn create_port_info(
+    port_number: u16,
+    adapter_path: &std::path::Path,
+    reader: &dyn CounterReader,
+    swap_rxtx: bool,
+) -> PortInfo {
     let port_path = adapter_path.join("ports").join(port_number.to_string());
     let state = read_port_state(&port_path);
+    let phys_state = read_phys_state(&port_path);
+    let link_layer = std::fs::read_to_string(port_path.join("link_layer"))
+        .unwrap_or_default()
+        .parse::<LinkLayer>()
+        .unwrap_or(LinkLayer::Unknown);
     let rate = read_port_rate(&port_path);
-    let counters = read_port_counters(&port_path);
+    let gid = read_port_gid(&port_path);
+    let lid = read_lid(&port_path.join("lid"));
+    let sm_lid = read_lid(&port_path.join("sm_lid"));
+    let counters = read_port_counters(&port_path, reader, swap_rxtx);
 
     PortInfo {
         port_number,
         state,
+        phys_state,
+        link_layer,
         rate,
+        gid,
+        lid,
+        sm_lid,
         counters,
+        sysfs_path: Some(port_path),
     }
 }
 
+/// Read a LID file such as `lid` or `sm_lid`, which hold hex like `0x0002`
+fn read_lid(path: &std::path::Path) -> Option<u16> {
+    let text = std::fs::read_to_string(path).ok()?;
+    u16::try_from(parse_counter_text(&text)?).ok()
+}
+
+fn read_port_gid(port_path: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(port_path.join("gids").join("0"))
+        .ok()
+        .map(|gid| gid.trim().to_string())
+        .filter(|gid| !gid.is_empty())
+}
+
 fn read_port_state(port_path: &std::path::Path) -> PortState {
-    let state_path = port_path.join("state");
-    let raw_state = std::fs::read_to_string(state_path)
-        .unwrap_or_default()
-        .trim()
-        .to_string();
+    read_state_name(&port_path.join("state"))
+        .parse::<PortState>()
+        .unwrap_or(PortState::Unknown)
+}
+
+fn read_phys_state(port_path: &std::path::Path) -> PhysState {
+    read_state_name(&port_path.join("phys_state"))
+        .parse::<PhysState>()
+        .unwrap_or(PhysState::Unknown)
+}
+
+/// Name part of a sysfs state file such as `4: ACTIVE` or `5: LinkUp`
+fn read_state_name(path: &std::path::Path) -> String {
+    let raw_state = std::fs::read_to_string(path).unwrap_or_default();
 
     // Handle format like "4: ACTIVE" or just "ACTIVE"
-    let state_str = if let Some(colon_pos) = raw_state.find(':') {
-        raw_state[colon_pos + 1..].trim()
-    } else {
-        raw_state.as_str()
+    let state_str = match raw_state.find(':') {
+        Some(colon_pos) => &raw_state[colon_pos + 1..],
+        None => raw_state.as_str(),
     };
-
-    state_str.parse::<PortState>().unwrap_or(PortState::Unknown)
+    state_str.trim().to_string()
 }
 
 fn read_port_rate(port_path: &std::path::Path) -> String {
-    let rate_path = port_path.join("rate");
-    let raw_rate = std::fs::read_to_string(rate_path)
+    // Kept verbatim, width and generation included (e.g. "400 Gb/sec (4X NDR)");
+    // the table trims it for display
+    std::fs::read_to_string(port_path.join("rate"))
         .unwrap_or_default()
         .trim()
-        .to_string();
-
-    // Just keeping the raw rate for now to prevent cluttering the UI
-    // I know already that people will complain about this - sorry
-    if let Some(paren_pos) = raw_rate.find('(') {
-        raw_rate[..paren_pos].trim().to_string()
-    } else {
-        raw_rate
-    }
+        .to_string()
 }
 
-fn read_port_counters(port_path: &std::path::Path) -> PortCounters {
+/// Read a port's counters; `swap_rxtx` exchanges the RX and TX data and
+/// packet counters for drivers that report them the wrong way round
+fn read_port_counters(
+    port_path: &std::path::Path,
+    reader: &dyn CounterReader,
+    swap_rxtx: bool,
+) -> PortCounters {
     let counters_path = port_path.join("counters");
-    let mut counters = PortCounters::default();
-
-    if counters_path.exists() {
-        counters.rx_bytes = read_counter_value(&counters_path, "port_rcv_data");
-        counters.tx_bytes = read_counter_value(&counters_path, "port_xmit_data");
-        counters.rx_packets = read_counter_value(&counters_path, "port_rcv_packets");
-        counters.tx_packets = read_counter_value(&counters_path, "port_xmit_packets");
-        counters.rx_errors = read_counter_value(&counters_path, "port_rcv_errors");
-        counters.tx_errors = read_counter_value(&counters_path, "port_xmit_discards");
-        counters.rx_dropped = read_counter_value(&counters_path, "port_rcv_constraint_errors");
+    if !counters_path.exists() {
+        return PortCounters::default();
     }
 
-    counters
+    let paths: Vec<PathBuf> = COUNTER_FILES
+        .iter()
+        .map(|filename| counters_path.join(filename))
+        .collect();
+    let values: Vec<u64> = reader
+        .read_all(&paths)
+        .iter()
+        .zip(COUNTER_FILES)
+        .map(|(content, filename)| parse_counter_value(content.as_deref(), filename))
+        .collect();
+
+    let (rx, tx) = if swap_rxtx { (1, 0) } else { (0, 1) };
+    PortCounters {
+        rx_bytes: values[rx],
+        tx_bytes: values[tx],
+        rx_packets: values[rx + 2],
+        tx_packets: values[tx + 2],
+        rx_errors: values[4],
+        tx_discards: values[5],
+        rx_dropped: values[6],
+        link_error_recovery: values[7],
+        link_downed: values[8],
+        symbol_errors: values[9],
+        local_link_integrity_errors: values[10],
+        xmit_wait: values[11],
+    }
 }
 
-fn read_counter_value(counters_path: &std::path::Path, filename: &str) -> u64 {
-    let value = std::fs::read_to_string(counters_path.join(filename))
-        .map(|content| content.trim().parse().unwrap_or(0))
-        .unwrap_or(0);
+fn parse_counter_value(content: Option<&str>, filename: &str) -> u64 {
+    let value = content.and_then(parse_counter_text).unwrap_or(0);
 
     if filename == "port_rcv_data" || filename == "port_xmit_data" {
         value * MLX5_DATA_MULTIPLIER
@@ -149,3 +447,420 @@ fn read_counter_value(counters_path: &std::path::Path, filename: &str) -> u64 {
         value
     }
 }
+
+/// Parse a counter file's contents, decimal or `0x`-prefixed hex
+///
+/// Most counters are decimal, but some extended `hw_counters` print hex;
+/// a plain decimal parse would silently read those as missing.
+pub(crate) fn parse_counter_text(content: &str) -> Option<u64> {
+    let content = content.trim();
+    match content
+        .strip_prefix("0x")
+        .or_else(|| content.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => content.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_port(root: &std::path::Path, adapter: &str, port: u16, state: &str) {
+        let port_path = root.join(adapter).join("ports").join(port.to_string());
+        fs::create_dir_all(port_path.join("counters")).unwrap();
+        fs::write(port_path.join("state"), state).unwrap();
+        fs::write(port_path.join("rate"), "100 Gb/sec (4X EDR)").unwrap();
+        fs::write(port_path.join("counters").join("port_rcv_data"), "10").unwrap();
+        fs::create_dir_all(port_path.join("gids")).unwrap();
+        fs::write(
+            port_path.join("gids").join("0"),
+            "fe80:0000:0000:0000:0002:c903:00f1:2345\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_adapter_without_ports_dir() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        fs::write(root.path().join("mlx5_0/fw_ver"), "28.39.1002\n").unwrap();
+        fs::write(root.path().join("mlx5_0/hca_type"), "MT4129\n").unwrap();
+        fs::create_dir_all(root.path().join("virt_0")).unwrap();
+
+        let (mut adapters, _) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        assert_eq!(adapters.len(), 2);
+
+        let virt = adapters.iter().find(|a| a.name == "virt_0").unwrap();
+        assert!(virt.ports.is_empty());
+        assert_eq!(virt.fw_ver, None);
+
+        retain_with_ports(&mut adapters);
+        assert_eq!(adapters.len(), 1);
+        assert_eq!(adapters[0].name, "mlx5_0");
+        assert_eq!(adapters[0].ports[0].state, PortState::Active);
+        assert_eq!(adapters[0].ports[0].counters.rx_bytes, 40);
+        assert_eq!(adapters[0].fw_ver.as_deref(), Some("28.39.1002"));
+        assert_eq!(adapters[0].hca_type.as_deref(), Some("MT4129"));
+        assert_eq!(adapters[0].board_id, None);
+        assert_eq!(
+            adapters[0].ports[0].gid.as_deref(),
+            Some("fe80:0000:0000:0000:0002:c903:00f1:2345")
+        );
+    }
+
+    #[test]
+    fn test_phys_state_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        for port in 2..=4 {
+            write_port(root.path(), "mlx5_0", port, "1: DOWN");
+        }
+        let ports = root.path().join("mlx5_0").join("ports");
+        fs::write(ports.join("1").join("phys_state"), "5: LinkUp\n").unwrap();
+        fs::write(ports.join("2").join("phys_state"), "2: Polling\n").unwrap();
+        fs::write(ports.join("3").join("phys_state"), "Disabled\n").unwrap();
+
+        // Port 4 has no phys_state file
+
+        let (adapters, _) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let mut phys: Vec<(u16, PhysState)> = adapters[0]
+            .ports
+            .iter()
+            .map(|p| (p.port_number, p.phys_state))
+            .collect();
+        phys.sort_by_key(|(port, _)| *port);
+        assert_eq!(
+            phys,
+            vec![
+                (1, PhysState::LinkUp),
+                (2, PhysState::Polling),
+                (3, PhysState::Disabled),
+                (4, PhysState::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_counters_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        let counters_path = root.path().join("mlx5_0/ports/1/counters");
+        for (file, value) in [
+            ("link_error_recovery", "3\n"),
+            ("link_downed", "1\n"),
+            ("symbol_error", "12\n"),
+            ("local_link_integrity_errors", "2\n"),
+            ("port_xmit_wait", "40000\n"),
+        ] {
+            fs::write(counters_path.join(file), value).unwrap();
+        }
+
+        let (adapters, _) = read_adapters(
+            root.path(),
+            false,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let counters = &adapters[0].ports[0].counters;
+        // Event counts, so no data multiplier
+        assert_eq!(counters.link_error_recovery, 3);
+        assert_eq!(counters.link_downed, 1);
+        assert_eq!(counters.symbol_errors, 12);
+        assert_eq!(counters.local_link_integrity_errors, 2);
+        assert_eq!(counters.xmit_wait, 40_000);
+    }
+
+    #[test]
+    fn test_swap_rxtx_exchanges_directions() {
+        let root = tempfile::tempdir().unwrap();
+        for adapter in ["mlx5_0", "mlx5_1"] {
+            write_port(root.path(), adapter, 1, "4: ACTIVE");
+            let counters_path = root.path().join(adapter).join("ports/1/counters");
+            for (file, value) in [
+                ("port_rcv_data", "10"),
+                ("port_xmit_data", "25"),
+                ("port_rcv_packets", "3"),
+                ("port_xmit_packets", "7"),
+                ("port_rcv_errors", "1"),
+            ] {
+                fs::write(counters_path.join(file), value).unwrap();
+            }
+        }
+
+        let read = |swap: &SwapRxTx| {
+            let (adapters, _) = read_adapters(root.path(), false, &counters::StdReader, swap);
+            adapters
+                .iter()
+                .map(|a| {
+                    let c = &a.ports[0].counters;
+                    (
+                        c.rx_bytes,
+                        c.tx_bytes,
+                        c.rx_packets,
+                        c.tx_packets,
+                        c.rx_errors,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let straight = (40, 100, 3, 7, 1);
+        let swapped = (100, 40, 7, 3, 1);
+
+        assert_eq!(read(&SwapRxTx::default()), vec![straight, straight]);
+        let all = SwapRxTx {
+            all: true,
+            ..SwapRxTx::default()
+        };
+        assert_eq!(read(&all), vec![swapped, swapped]);
+        let one = SwapRxTx {
+            adapters: vec!["mlx5_1".to_string()],
+            ..SwapRxTx::default()
+        };
+        assert_eq!(read(&one), vec![straight, swapped]);
+    }
+
+    #[test]
+    fn test_errors_and_discards_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        let counters_path = root.path().join("mlx5_0/ports/1/counters");
+        fs::write(counters_path.join("port_rcv_errors"), "7\n").unwrap();
+        fs::write(counters_path.join("port_xmit_discards"), "300\n").unwrap();
+        fs::write(counters_path.join("port_rcv_constraint_errors"), "1\n").unwrap();
+
+        let (adapters, _) = read_adapters(
+            root.path(),
+            false,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let counters = &adapters[0].ports[0].counters;
+        assert_eq!(counters.rx_errors, 7);
+        assert_eq!(counters.tx_discards, 300);
+        assert_eq!(counters.rx_dropped, 1);
+    }
+
+    #[test]
+    fn test_link_layer_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        for port in 1..=3 {
+            write_port(root.path(), "mlx5_0", port, "4: ACTIVE");
+        }
+        let ports = root.path().join("mlx5_0").join("ports");
+        fs::write(ports.join("1").join("link_layer"), "InfiniBand\n").unwrap();
+        fs::write(ports.join("1").join("lid"), "0x5\n").unwrap();
+        // RoCE: Ethernet with no LID files at all
+        fs::write(ports.join("2").join("link_layer"), "Ethernet\n").unwrap();
+
+        // Port 3 has no link_layer file
+
+        let (adapters, _) = read_adapters(
+            root.path(),
+            false,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let mut layers: Vec<(u16, LinkLayer, Option<u16>)> = adapters[0]
+            .ports
+            .iter()
+            .map(|p| (p.port_number, p.link_layer, p.lid))
+            .collect();
+        layers.sort_by_key(|(port, _, _)| *port);
+        assert_eq!(
+            layers,
+            vec![
+                (1, LinkLayer::InfiniBand, Some(5)),
+                (2, LinkLayer::Ethernet, None),
+                (3, LinkLayer::Unknown, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lids_from_sysfs() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        let port = root.path().join("mlx5_0/ports/1");
+        fs::write(port.join("lid"), "0x002a\n").unwrap();
+        fs::write(port.join("sm_lid"), "0x1\n").unwrap();
+
+        let read = |root: &std::path::Path| {
+            let (adapters, _) =
+                read_adapters(root, false, &counters::StdReader, &SwapRxTx::default());
+            let port = &adapters[0].ports[0];
+            (port.lid, port.sm_lid)
+        };
+        assert_eq!(read(root.path()), (Some(42), Some(1)));
+
+        // Unparsable, out of range or missing files leave the LID unknown
+        fs::write(port.join("lid"), "garbage\n").unwrap();
+        fs::write(port.join("sm_lid"), "0x10000\n").unwrap();
+        assert_eq!(read(root.path()), (None, None));
+        fs::remove_file(port.join("lid")).unwrap();
+        assert_eq!(read(root.path()).0, None);
+    }
+
+    #[test]
+    fn test_port_keeps_its_sysfs_path() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+
+        let (adapters, _) = read_adapters(
+            root.path(),
+            false,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        assert_eq!(
+            adapters[0].ports[0].sysfs_path.as_deref(),
+            Some(root.path().join("mlx5_0").join("ports").join("1").as_path())
+        );
+    }
+
+    #[test]
+    fn test_non_numeric_port_entries_are_reported() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        fs::create_dir_all(root.path().join("mlx5_0/ports/mgmt")).unwrap();
+        fs::write(root.path().join("mlx5_0/ports/README"), "").unwrap();
+
+        let (adapters, timing) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        assert_eq!(adapters[0].ports.len(), 1);
+        assert_eq!(adapters[0].ports[0].port_number, 1);
+        assert_eq!(
+            timing.skipped_ports,
+            vec!["mlx5_0/ports/README", "mlx5_0/ports/mgmt"]
+        );
+    }
+
+    #[test]
+    fn test_raw_counter_dump_reads_every_file() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        let port_path = root.path().join("mlx5_0/ports/1");
+        fs::write(port_path.join("counters/link_downed"), "3\n").unwrap();
+        fs::create_dir_all(port_path.join("hw_counters")).unwrap();
+        fs::write(port_path.join("hw_counters/out_of_buffer"), "0x1f\n").unwrap();
+
+        assert_eq!(
+            read_counter_dirs(&port_path),
+            vec![
+                ("counters/link_downed".to_string(), "3".to_string()),
+                ("counters/port_rcv_data".to_string(), "10".to_string()),
+                ("hw_counters/out_of_buffer".to_string(), "0x1f".to_string()),
+            ]
+        );
+        assert!(read_counter_dirs(&root.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let root = tempfile::tempdir().unwrap();
+        for adapter in 0..12 {
+            for port in 1..=2 {
+                write_port(root.path(), &format!("mlx5_{adapter}"), port, "4: ACTIVE");
+            }
+        }
+
+        let (sequential, _) = read_adapters(
+            root.path(),
+            false,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let (parallel, _) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+
+        assert_eq!(sequential.len(), 12);
+        assert_eq!(sequential, parallel);
+        assert_eq!(parallel[2].name, "mlx5_2");
+        assert_eq!(parallel[10].name, "mlx5_10");
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    fn test_uring_discovery_matches_std() {
+        let root = tempfile::tempdir().unwrap();
+        for adapter in 0..4 {
+            write_port(root.path(), &format!("mlx5_{adapter}"), 1, "4: ACTIVE");
+            write_port(root.path(), &format!("mlx5_{adapter}"), 2, "1: DOWN");
+        }
+
+        let (with_std, _) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+        let (with_uring, _) =
+            read_adapters(root.path(), true, default_reader(), &SwapRxTx::default());
+
+        assert_eq!(default_reader().name(), "io_uring");
+        assert_eq!(with_std, with_uring);
+        assert_eq!(with_uring[0].ports[0].counters.rx_bytes, 40);
+    }
+
+    #[test]
+    fn test_read_timing_populated() {
+        let root = tempfile::tempdir().unwrap();
+        write_port(root.path(), "mlx5_0", 1, "4: ACTIVE");
+        write_port(root.path(), "mlx5_1", 1, "1: DOWN");
+
+        let (adapters, timing) = read_adapters(
+            root.path(),
+            true,
+            &counters::StdReader,
+            &SwapRxTx::default(),
+        );
+
+        let names: Vec<&str> = timing.per_adapter.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["mlx5_0", "mlx5_1"]);
+        assert_eq!(adapters.len(), timing.per_adapter.len());
+        assert!(timing.total > Duration::ZERO);
+        assert!(timing.per_adapter.iter().all(|(_, d)| *d <= timing.total));
+    }
+
+    #[test]
+    fn test_parse_counter_text() {
+        assert_eq!(parse_counter_text("0x1a2b\n"), Some(0x1a2b));
+        assert_eq!(parse_counter_text("0X1A2B"), Some(0x1a2b));
+        assert_eq!(parse_counter_text("6699\n"), Some(6699));
+        assert_eq!(parse_counter_text("0xzz"), None);
+        assert_eq!(parse_counter_text(""), None);
+
+        // The data multiplier still applies to hex data counters
+        assert_eq!(parse_counter_value(Some("0x10"), "port_rcv_data"), 64);
+        assert_eq!(parse_counter_value(Some("0x10"), "port_rcv_errors"), 16);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("mlx5_2", "mlx5_10"), std::cmp::Ordering::Less);
+        assert_eq!(
+            natural_cmp("mlx5_10", "mlx5_2"),
+            std::cmp::Ordering::Greater
+        );
+    }
+}
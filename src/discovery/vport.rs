@@ -0,0 +1,107 @@
+//! Per-vport traffic on SR-IOV ports
+//!
+//! With SR-IOV enabled, mlx5 adds `hw_counters` such as
+//! `rx_vport_<name>_bytes` and `tx_vport_<name>_packets`, one set per vport.
+//! The names vary by firmware and VF count, so they are matched by pattern
+//! rather than listed.
+
+use std::collections::BTreeMap;
+
+/// Traffic counted for one vport
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VportCounters {
+    pub(crate) rx_bytes: u64,
+    pub(crate) tx_bytes: u64,
+    pub(crate) rx_packets: u64,
+    pub(crate) tx_packets: u64,
+}
+
+/// Group the vport counters among a port's raw counter files by vport name
+///
+/// Takes `dir/file` → value entries as read for the "Raw" tab. Ports without
+/// vport counters yield an empty map; unparsable values are skipped.
+pub(crate) fn parse_vport_counters(
+    entries: &[(String, String)],
+) -> BTreeMap<String, VportCounters> {
+    let mut vports: BTreeMap<String, VportCounters> = BTreeMap::new();
+    for (name, value) in entries {
+        let Some(file) = name.strip_prefix("hw_counters/") else {
+            continue;
+        };
+        let Some((rx, rest)) = file
+            .strip_prefix("rx_vport_")
+            .map(|rest| (true, rest))
+            .or_else(|| file.strip_prefix("tx_vport_").map(|rest| (false, rest)))
+        else {
+            continue;
+        };
+        let Some((vport, bytes)) = rest
+            .strip_suffix("_bytes")
+            .map(|vport| (vport, true))
+            .or_else(|| rest.strip_suffix("_packets").map(|vport| (vport, false)))
+        else {
+            continue;
+        };
+        let Some(value) = super::parse_counter_text(value) else {
+            continue;
+        };
+        if vport.is_empty() {
+            continue;
+        }
+
+        let counters = vports.entry(vport.to_string()).or_default();
+        let field = match (rx, bytes) {
+            (true, true) => &mut counters.rx_bytes,
+            (false, true) => &mut counters.tx_bytes,
+            (true, false) => &mut counters.rx_packets,
+            (false, false) => &mut counters.tx_packets,
+        };
+        *field = value;
+    }
+    vports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vport_counters_fixture() {
+        let entries: Vec<(String, String)> = [
+            ("counters/port_rcv_data", "1000"),
+            ("hw_counters/out_of_buffer", "0"),
+            ("hw_counters/rx_vport_vf0_bytes", "4096"),
+            ("hw_counters/rx_vport_vf0_packets", "4"),
+            ("hw_counters/tx_vport_vf0_bytes", "2048"),
+            ("hw_counters/tx_vport_vf0_packets", "0x2"),
+            ("hw_counters/rx_vport_rdma_unicast_bytes", "123"),
+            (
+                "hw_counters/tx_vport_vf1_bytes",
+                "<unreadable: Permission denied>",
+            ),
+            ("hw_counters/rx_vport__bytes", "9"),
+        ]
+        .iter()
+        .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+        .collect();
+
+        let vports = parse_vport_counters(&entries);
+        assert_eq!(
+            vports.keys().collect::<Vec<_>>(),
+            vec!["rdma_unicast", "vf0"]
+        );
+        assert_eq!(
+            vports["vf0"],
+            VportCounters {
+                rx_bytes: 4096,
+                tx_bytes: 2048,
+                rx_packets: 4,
+                tx_packets: 2,
+            }
+        );
+        assert_eq!(vports["rdma_unicast"].rx_bytes, 123);
+        assert_eq!(vports["rdma_unicast"].tx_bytes, 0);
+
+        assert!(parse_vport_counters(&entries[..2]).is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+//! `ibtop doctor` - environment diagnostics
+//!
+//! Prints what ibtop sees on this host so "why is ibtop behaving oddly"
+//! questions can be answered from a single paste.
+
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+
+use crate::discovery::{self, ReadTiming, SwapRxTx};
+use crate::types::AdapterInfo;
+use crate::warnings;
+
+/// A recognized environment variable and the value ibtop falls back to when unset
+struct EnvVar {
+    name: &'static str,
+    default: Option<&'static str>,
+    description: &'static str,
+}
+
+/// Every environment variable ibtop reacts to
+const RECOGNIZED_ENV_VARS: &[EnvVar] = &[
+    EnvVar {
+        name: "INFINIBAND_PATH",
+        default: Some(discovery::DEFAULT_INFINIBAND_PATH),
+        description: "sysfs root to scan",
+    },
+    EnvVar {
+        name: "IBTOP_FAKE_DATA",
+        default: None,
+        description: "use simulated adapters",
+    },
+    EnvVar {
+        name: "IBTOP_DEMO",
+        default: None,
+        description: "simulate when no adapters are found",
+    },
+    EnvVar {
+        name: "NO_COLOR",
+        default: None,
+        description: "disable colored output",
+    },
+];
+
+/// One line of the environment summary
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnvEntry {
+    pub name: &'static str,
+    /// Raw value, if the variable is set
+    pub value: Option<String>,
+    /// Value ibtop actually uses
+    pub effective: String,
+    pub description: &'static str,
+}
+
+/// Build the environment summary using `lookup` to read variables
+pub fn env_summary(lookup: impl Fn(&str) -> Option<String>) -> Vec<EnvEntry> {
+    RECOGNIZED_ENV_VARS
+        .iter()
+        .map(|var| {
+            let value = lookup(var.name);
+            let effective = match (&value, var.default) {
+                (Some(v), _) => v.clone(),
+                (None, Some(default)) => format!("{default} (default)"),
+                (None, None) => "unset".to_string(),
+            };
+            EnvEntry {
+                name: var.name,
+                value,
+                effective,
+                description: var.description,
+            }
+        })
+        .collect()
+}
+
+/// Lifetime traffic below which a lopsided port is not worth a hint
+const ONE_SIDED_MIN_BYTES: u64 = 1 << 30;
+
+/// How many times more one direction must have carried than the other
+const ONE_SIDED_RATIO: u64 = 100;
+
+/// Hints for ports whose lifetime traffic runs almost all one way
+///
+/// Few nodes only ever send or only receive, so this usually means the
+/// driver reports RX and TX swapped; a pure storage client or source is
+/// the exception, hence a hint rather than a warning.
+fn one_sided_hints(adapters: &[AdapterInfo]) -> Vec<String> {
+    let mut hints = Vec::new();
+    for adapter in adapters {
+        for port in &adapter.ports {
+            let (rx, tx) = (port.counters.rx_bytes, port.counters.tx_bytes);
+            if rx + tx < ONE_SIDED_MIN_BYTES {
+                continue;
+            }
+            let (more, ratio) = if rx > tx {
+                ("received", rx / tx.max(1))
+            } else {
+                ("sent", tx / rx.max(1))
+            };
+            if ratio >= ONE_SIDED_RATIO {
+                hints.push(format!(
+                    "{} port {}: {more} {ratio}x more than the other way; unless this \
+                     node only {more} data, RX and TX may be swapped (try \
+                     --swap-rxtx-adapter {})",
+                    adapter.name, port.port_number, adapter.name
+                ));
+            }
+        }
+    }
+    hints
+}
+
+/// Render the full doctor report
+fn build_report(env: &[EnvEntry], adapters: &[AdapterInfo], timing: &ReadTiming) -> String {
+    let mut report = String::new();
+
+    let _ = writeln!(report, "ibtop {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report);
+
+    let _ = writeln!(report, "Adapters: {}", adapters.len());
+    for adapter in adapters {
+        let _ = writeln!(report, "  {} ({} ports)", adapter.name, adapter.ports.len());
+    }
+    let _ = writeln!(report);
+
+    let warnings = warnings::collect(adapters);
+    if !warnings.is_empty() {
+        let _ = writeln!(report, "Warnings:");
+        for warning in &warnings {
+            let _ = writeln!(report, "  {}: {}", warning.adapter, warning.message);
+        }
+        let _ = writeln!(report);
+    }
+
+    let hints = one_sided_hints(adapters);
+    if !hints.is_empty() {
+        let _ = writeln!(report, "Hints:");
+        for hint in &hints {
+            let _ = writeln!(report, "  {hint}");
+        }
+        let _ = writeln!(report);
+    }
+
+    let _ = writeln!(
+        report,
+        "Counter reader: {}",
+        discovery::default_reader().name()
+    );
+    let _ = writeln!(report, "Counter read latency: {:?} total", timing.total);
+    for (name, elapsed) in &timing.per_adapter {
+        let _ = writeln!(report, "  {name}  {elapsed:?}");
+    }
+    let _ = writeln!(report);
+
+    if !timing.skipped_ports.is_empty() {
+        let _ = writeln!(report, "Skipped port entries (not port numbers):");
+        for entry in &timing.skipped_ports {
+            let _ = writeln!(report, "  {entry}");
+        }
+        let _ = writeln!(report);
+    }
+
+    if !timing.renamed_adapters.is_empty() {
+        let _ = writeln!(
+            report,
+            "Adapters renamed because their name was already taken:"
+        );
+        for name in &timing.renamed_adapters {
+            let _ = writeln!(report, "  {name}");
+        }
+        let _ = writeln!(report);
+    }
+
+    let _ = writeln!(report, "Environment:");
+    let name_width = env.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    let value_width = env.iter().map(|e| e.effective.len()).max().unwrap_or(0);
+    for entry in env {
+        let _ = writeln!(
+            report,
+            "  {:<name_width$}  {:<value_width$}  ({})",
+            entry.name, entry.effective, entry.description
+        );
+    }
+
+    report
+}
+
+/// Print the doctor report to stdout
+pub fn run() -> io::Result<()> {
+    let env = env_summary(|name| std::env::var(name).ok());
+    // Counters as the driver reports them, so the swap hint is not masked
+    let (adapters, timing) = discovery::discover_adapters_timed(&SwapRxTx::default());
+    io::stdout().write_all(build_report(&env, &adapters, &timing).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_summary_controlled_environment() {
+        let summary = env_summary(|name| match name {
+            "IBTOP_DEMO" => Some("1".to_string()),
+            _ => None,
+        });
+
+        assert_eq!(summary.len(), RECOGNIZED_ENV_VARS.len());
+
+        let path = summary
+            .iter()
+            .find(|e| e.name == "INFINIBAND_PATH")
+            .unwrap();
+        assert_eq!(path.value, None);
+        assert_eq!(
+            path.effective,
+            format!("{} (default)", discovery::DEFAULT_INFINIBAND_PATH)
+        );
+
+        let demo = summary.iter().find(|e| e.name == "IBTOP_DEMO").unwrap();
+        assert_eq!(demo.value.as_deref(), Some("1"));
+        assert_eq!(demo.effective, "1");
+
+        let fake = summary
+            .iter()
+            .find(|e| e.name == "IBTOP_FAKE_DATA")
+            .unwrap();
+        assert_eq!(fake.effective, "unset");
+    }
+
+    #[test]
+    fn test_report_lists_every_var() {
+        let report = build_report(&env_summary(|_| None), &[], &ReadTiming::default());
+        for var in RECOGNIZED_ENV_VARS {
+            assert!(report.contains(var.name));
+        }
+    }
+
+    #[test]
+    fn test_one_sided_traffic_hint() {
+        use crate::types::{PortCounters, PortInfo};
+        let adapter = |rx_bytes, tx_bytes| AdapterInfo {
+            name: "mlx5_0".to_string(),
+            ports: vec![PortInfo {
+                port_number: 1,
+                counters: PortCounters {
+                    rx_bytes,
+                    tx_bytes,
+                    ..PortCounters::default()
+                },
+                ..PortInfo::default()
+            }],
+            ..AdapterInfo::default()
+        };
+
+        // Balanced, and lopsided but too little to judge: no hint
+        assert!(one_sided_hints(&[adapter(40 << 30, 30 << 30)]).is_empty());
+        assert!(one_sided_hints(&[adapter(1 << 20, 0)]).is_empty());
+
+        let hints = one_sided_hints(&[adapter(0, 300 << 30)]);
+        assert_eq!(hints.len(), 1);
+        assert!(hints[0].starts_with("mlx5_0 port 1: sent"));
+        assert!(hints[0].contains("--swap-rxtx-adapter mlx5_0"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Expected port states and rates, checked on every refresh
+//!
+//! `--expect mlx5_0:1=ACTIVE@200G` declares how a port should look; ports
+//! that fall short are flagged in the table and explained in the detail
+//! view, so ibtop doubles as a quick conformance check after maintenance.
+
+use crate::types::{PortInfo, PortState};
+use crate::ui::{parse_rate_bps, port_rate_bps};
+
+/// How one port (or every port of an adapter) is expected to look
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expectation {
+    pub adapter: String,
+    /// `None` matches every port of the adapter (`mlx5_0:*`)
+    pub port: Option<u16>,
+    pub state: Option<PortState>,
+    /// Lowest acceptable link rate, in bits per second
+    pub min_rate_bps: Option<f64>,
+}
+
+impl Expectation {
+    /// Parse `adapter:port=STATE@rate`, where either `STATE` or `@rate` may
+    /// be left out and `port` may be `*`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = |why: &str| format!("invalid expectation '{spec}': {why}");
+
+        let (target, wanted) = spec
+            .split_once('=')
+            .ok_or_else(|| invalid("expected adapter:port=STATE@rate"))?;
+        let (adapter, port) = target
+            .rsplit_once(':')
+            .ok_or_else(|| invalid("expected adapter:port before '='"))?;
+        if adapter.is_empty() {
+            return Err(invalid("empty adapter name"));
+        }
+        let port = match port {
+            "*" => None,
+            port => Some(port.parse().map_err(|_| invalid("port is not a number"))?),
+        };
+
+        let (state, rate) = match wanted.split_once('@') {
+            Some((state, rate)) => (state, Some(rate)),
+            None => (wanted, None),
+        };
+        let state = match state.trim().to_ascii_uppercase().as_str() {
+            "" => None,
+            "ACTIVE" => Some(PortState::Active),
+            "DOWN" => Some(PortState::Down),
+            "INIT" => Some(PortState::Init),
+            "ARMED" => Some(PortState::Armed),
+            other => return Err(invalid(&format!("unknown state '{other}'"))),
+        };
+        let min_rate_bps = rate
+            .map(|rate| parse_rate_bps(rate).ok_or_else(|| invalid("unparsable rate")))
+            .transpose()?;
+        if state.is_none() && min_rate_bps.is_none() {
+            return Err(invalid("nothing expected"));
+        }
+
+        Ok(Self {
+            adapter: adapter.to_string(),
+            port,
+            state,
+            min_rate_bps,
+        })
+    }
+
+    /// Ways `port` falls short of this expectation; empty when it conforms
+    pub fn shortfalls(&self, port: &PortInfo) -> Vec<String> {
+        let mut shortfalls = Vec::new();
+        if let Some(state) = self.state {
+            if port.state != state {
+                shortfalls.push(format!("{state} expected, port is {}", port.state));
+            }
+        }
+        if let Some(min) = self.min_rate_bps {
+            match port_rate_bps(port) {
+                Some(rate) if rate >= min => {}
+                Some(rate) => shortfalls.push(format!(
+                    "at least {} Gb/s expected, link runs at {} Gb/s",
+                    min / 1e9,
+                    rate / 1e9
+                )),
+                None => shortfalls.push(format!(
+                    "at least {} Gb/s expected, link rate unknown",
+                    min / 1e9
+                )),
+            }
+        }
+        shortfalls
+    }
+}
+
+/// Every expectation given, looked up per port
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Expectations {
+    entries: Vec<Expectation>,
+}
+
+impl Expectations {
+    pub fn new(entries: Vec<Expectation>) -> Self {
+        Self { entries }
+    }
+
+    /// The expectation for a port; one naming the port wins over `adapter:*`
+    pub fn expectation_for(&self, adapter: &str, port: u16) -> Option<&Expectation> {
+        let for_adapter = || self.entries.iter().filter(move |e| e.adapter == adapter);
+        for_adapter()
+            .find(|e| e.port == Some(port))
+            .or_else(|| for_adapter().find(|e| e.port.is_none()))
+    }
+
+    /// Ways the port falls short of its expectation, if it has one
+    pub fn check(&self, adapter: &str, port: &PortInfo) -> Vec<String> {
+        self.expectation_for(adapter, port.port_number)
+            .map(|expectation| expectation.shortfalls(port))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(port_number: u16, state: PortState, rate: &str) -> PortInfo {
+        PortInfo {
+            port_number,
+            state,
+            rate: rate.to_string(),
+            ..PortInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_expectation() {
+        let expectation = Expectation::parse("mlx5_0:1=ACTIVE@200G").unwrap();
+        assert_eq!(expectation.adapter, "mlx5_0");
+        assert_eq!(expectation.port, Some(1));
+        assert_eq!(expectation.state, Some(PortState::Active));
+        assert_eq!(expectation.min_rate_bps, Some(200e9));
+
+        let any_port = Expectation::parse("mlx5_1:*=@100 Gb/sec").unwrap();
+        assert_eq!(any_port.port, None);
+        assert_eq!(any_port.state, None);
+        assert_eq!(any_port.min_rate_bps, Some(100e9));
+
+        for bad in [
+            "mlx5_0:1",
+            "mlx5_0=ACTIVE",
+            ":1=ACTIVE",
+            "mlx5_0:x=ACTIVE",
+            "mlx5_0:1=UP",
+            "mlx5_0:1=",
+            "mlx5_0:1=@fast",
+        ] {
+            assert!(Expectation::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_expectation_matching() {
+        let expectations = Expectations::new(vec![
+            Expectation::parse("mlx5_0:*=ACTIVE").unwrap(),
+            Expectation::parse("mlx5_0:2=DOWN").unwrap(),
+        ]);
+        assert_eq!(
+            expectations.expectation_for("mlx5_0", 1).unwrap().state,
+            Some(PortState::Active)
+        );
+        // The port-specific entry wins over the wildcard
+        assert_eq!(
+            expectations.expectation_for("mlx5_0", 2).unwrap().state,
+            Some(PortState::Down)
+        );
+        assert_eq!(expectations.expectation_for("mlx5_1", 1), None);
+    }
+
+    #[test]
+    fn test_shortfalls() {
+        let expectations =
+            Expectations::new(vec![Expectation::parse("mlx5_0:1=ACTIVE@200G").unwrap()]);
+
+        let good = port(1, PortState::Active, "200 Gb/sec (4X HDR)");
+        assert!(expectations.check("mlx5_0", &good).is_empty());
+        let faster = port(1, PortState::Active, "400 Gb/sec (4X NDR)");
+        assert!(expectations.check("mlx5_0", &faster).is_empty());
+
+        let slow = port(1, PortState::Active, "100 Gb/sec (4X EDR)");
+        assert_eq!(
+            expectations.check("mlx5_0", &slow),
+            vec!["at least 200 Gb/s expected, link runs at 100 Gb/s"]
+        );
+
+        let down = port(1, PortState::Down, "");
+        assert_eq!(
+            expectations.check("mlx5_0", &down),
+            vec![
+                "ACTIVE expected, port is DOWN",
+                "at least 200 Gb/s expected, link rate unknown",
+            ]
+        );
+
+        // Ports without an expectation always conform
+        assert!(expectations.check("mlx5_1", &down).is_empty());
+    }
+}
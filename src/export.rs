@@ -0,0 +1,721 @@
+//! Helpers shared by the non-interactive output modes
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::history::PortHistory;
+use crate::metrics::{MetricsCollector, PortMetrics};
+use crate::types::{
+    AdapterInfo, FlatPort, IbtopOutput, PortCounters, PortHistorySamples, PortInfo, PortRates,
+    PortState,
+};
+use crate::ui::RateDisplay;
+
+/// Window sampled for `--with-history` when `--export-avg` is not given
+pub const HISTORY_EXPORT_WINDOW: Duration = Duration::from_secs(5);
+
+/// Sample `collect` repeatedly for `window` and average each port's rates
+///
+/// Instantaneous rates from two samples are noisy; averaging over a short
+/// window gives steadier numbers for sparse scrape intervals. The samples
+/// also land in `metrics`' history.
+pub fn average_rates(
+    metrics: &mut MetricsCollector,
+    mut collect: impl FnMut() -> Vec<AdapterInfo>,
+    window: Duration,
+    interval: Duration,
+) -> Vec<PortRates> {
+    let mut samples: BTreeMap<(String, u16), Vec<PortMetrics>> = BTreeMap::new();
+
+    let start = Instant::now();
+    metrics.update(&collect());
+
+    // Always take at least one rate sample, even for windows shorter than the interval
+    loop {
+        thread::sleep(interval);
+        let adapters = collect();
+        metrics.update(&adapters);
+
+        for adapter in &adapters {
+            for port in &adapter.ports {
+                if let Some(m) = metrics.get_metrics(&adapter.name, port.port_number) {
+                    samples
+                        .entry((adapter.name.clone(), port.port_number))
+                        .or_default()
+                        .push(m.clone());
+                }
+            }
+        }
+
+        if start.elapsed() >= window {
+            break;
+        }
+    }
+
+    samples
+        .into_iter()
+        .map(|((adapter, port_number), port_samples)| {
+            to_port_rates(adapter, port_number, &PortMetrics::average(&port_samples))
+        })
+        .collect()
+}
+
+/// Recorded history of every port in `adapters`, for `--with-history`
+pub fn history_samples(
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+) -> Vec<PortHistorySamples> {
+    adapters
+        .iter()
+        .flat_map(|adapter| {
+            adapter.ports.iter().filter_map(|port| {
+                metrics
+                    .get_history(&adapter.name, port.port_number)
+                    .map(|history| to_history_samples(&adapter.name, port.port_number, history))
+            })
+        })
+        .collect()
+}
+
+fn to_history_samples(
+    adapter: &str,
+    port_number: u16,
+    history: &PortHistory,
+) -> PortHistorySamples {
+    PortHistorySamples {
+        adapter: adapter.to_string(),
+        port_number,
+        timestamps: history.timestamps.to_vec(),
+        rx_bytes_per_sec: history.rx_bytes_per_sec.to_vec(),
+        tx_bytes_per_sec: history.tx_bytes_per_sec.to_vec(),
+        rx_packets_per_sec: history.rx_packets_per_sec.to_vec(),
+        tx_packets_per_sec: history.tx_packets_per_sec.to_vec(),
+        error_rate: history.error_rate.to_vec(),
+    }
+}
+
+/// One line per adapter with its port states, names aligned, for `--list`
+pub fn to_list(adapters: &[AdapterInfo]) -> String {
+    let width = adapters.iter().map(|a| a.name.len()).max().unwrap_or(0);
+    let mut list = String::new();
+    for adapter in adapters {
+        let ports: Vec<String> = adapter
+            .ports
+            .iter()
+            .map(|port| format!("port {} {}", port.port_number, port.state))
+            .collect();
+        let ports = if ports.is_empty() {
+            "no ports".to_string()
+        } else {
+            ports.join(", ")
+        };
+        let _ = writeln!(list, "{:<width$}  {ports}", adapter.name);
+    }
+    list
+}
+
+/// One line per port stamped with `timestamp`, for each `--watch` refresh,
+/// e.g. `12:00:01 mlx5_0:1  ACTIVE  rx   5.4GB/s  tx   4.5GB/s  err 0.0/s`
+///
+/// Ports not sampled twice yet show "--" for their rates.
+pub fn to_watch_lines(
+    timestamp: &str,
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    rates: RateDisplay,
+) -> String {
+    let ports: Vec<(String, &PortInfo, Option<&PortMetrics>)> = adapters
+        .iter()
+        .flat_map(|adapter| {
+            adapter.ports.iter().map(|port| {
+                (
+                    format!("{}:{}", adapter.name, port.port_number),
+                    port,
+                    metrics.get_metrics(&adapter.name, port.port_number),
+                )
+            })
+        })
+        .collect();
+    let width = ports.iter().map(|(name, ..)| name.len()).max().unwrap_or(0);
+
+    let mut lines = String::new();
+    for (name, port, port_metrics) in ports {
+        let state = port.state.to_string();
+        let _ = match port_metrics {
+            Some(m) => writeln!(
+                lines,
+                "{timestamp} {name:<width$}  {state:<6}  rx {:>9}  tx {:>9}  err {:.1}/s",
+                rates.format(m.rx_bytes_per_sec),
+                rates.format(m.tx_bytes_per_sec),
+                m.error_rate
+            ),
+            None => writeln!(
+                lines,
+                "{timestamp} {name:<width$}  {state:<6}  rx {:>9}  tx {:>9}  err --",
+                "--", "--"
+            ),
+        };
+    }
+    lines
+}
+
+/// One record per port, for `--json-flat`
+pub fn flatten_ports(output: &IbtopOutput) -> Vec<FlatPort> {
+    output
+        .adapters
+        .iter()
+        .flat_map(|adapter| {
+            adapter.ports.iter().map(|port| FlatPort {
+                hostname: output.hostname.clone(),
+                adapter: adapter.name.clone(),
+                port: port.port_number,
+                state: port.state,
+                rate: port.rate.clone(),
+                counters: port.counters.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Columns of `--csv` output, in order; `tx_errors` holds the transmit
+/// discards, under the column name published before they were told apart
+const CSV_HEADER: &str = "adapter,port,state,rate,rx_bytes,tx_bytes,rx_packets,tx_packets,rx_errors,tx_errors,rx_dropped";
+
+/// One CSV row per port after a header line, for `--csv`
+pub fn to_csv(output: &IbtopOutput) -> String {
+    let mut csv = format!("{CSV_HEADER}\n");
+    for port in flatten_ports(output) {
+        let c = &port.counters;
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&port.adapter),
+            port.port,
+            port.state,
+            csv_field(&port.rate),
+            c.rx_bytes,
+            c.tx_bytes,
+            c.rx_packets,
+            c.tx_packets,
+            c.rx_errors,
+            c.tx_discards,
+            c.rx_dropped,
+        );
+    }
+    csv
+}
+
+/// Quote a CSV field if it holds a separator, quote or line break
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Port counters exported by `--prometheus`: metric suffix, help text, value
+type CounterMetric = (&'static str, &'static str, fn(&PortCounters) -> u64);
+
+const PROMETHEUS_COUNTERS: &[CounterMetric] = &[
+    ("rx_bytes_total", "Bytes received", |c| c.rx_bytes),
+    ("tx_bytes_total", "Bytes transmitted", |c| c.tx_bytes),
+    ("rx_packets_total", "Packets received", |c| c.rx_packets),
+    ("tx_packets_total", "Packets transmitted", |c| c.tx_packets),
+    ("rx_errors_total", "Receive errors", |c| c.rx_errors),
+    // The published name, kept for existing dashboards
+    ("tx_errors_total", "Transmit discards (congestion)", |c| {
+        c.tx_discards
+    }),
+    ("rx_dropped_total", "Received packets dropped", |c| {
+        c.rx_dropped
+    }),
+    ("link_error_recovery_total", "Link error recoveries", |c| {
+        c.link_error_recovery
+    }),
+    ("link_downed_total", "Link-downed events", |c| c.link_downed),
+    ("symbol_errors_total", "Symbol errors", |c| c.symbol_errors),
+    (
+        "local_link_integrity_errors_total",
+        "Local link integrity errors",
+        |c| c.local_link_integrity_errors,
+    ),
+    (
+        "xmit_wait_ticks_total",
+        "Ticks spent waiting for credits to transmit (congestion)",
+        |c| c.xmit_wait,
+    ),
+];
+
+/// Port counters and state in Prometheus text exposition format, for
+/// `--prometheus` (e.g. for `node_exporter`'s textfile collector)
+pub fn to_prometheus(output: &IbtopOutput) -> String {
+    let ports = flatten_ports(output);
+    let labels: Vec<String> = ports
+        .iter()
+        .map(|p| {
+            format!(
+                "adapter=\"{}\",port=\"{}\"",
+                prometheus_label(&p.adapter),
+                p.port
+            )
+        })
+        .collect();
+
+    let mut text = String::new();
+    for (suffix, help, value) in PROMETHEUS_COUNTERS {
+        let _ = writeln!(text, "# HELP ibtop_port_{suffix} {help} on the port");
+        let _ = writeln!(text, "# TYPE ibtop_port_{suffix} counter");
+        for (port, labels) in ports.iter().zip(&labels) {
+            let _ = writeln!(
+                text,
+                "ibtop_port_{suffix}{{{labels}}} {}",
+                value(&port.counters)
+            );
+        }
+    }
+
+    let _ = writeln!(
+        text,
+        "# HELP ibtop_port_state Whether the port is ACTIVE (1) or not (0)"
+    );
+    let _ = writeln!(text, "# TYPE ibtop_port_state gauge");
+    for (port, labels) in ports.iter().zip(&labels) {
+        let active = u8::from(port.state == PortState::Active);
+        let _ = writeln!(text, "ibtop_port_state{{{labels}}} {active}");
+    }
+    text
+}
+
+/// Escape a Prometheus label value (backslash, double quote and newline)
+fn prometheus_label(value: &str) -> Cow<'_, str> {
+    if value.contains(['\\', '"', '\n']) {
+        Cow::Owned(
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Drop ports that are not ACTIVE or have never counted anything, and
+/// adapters left without ports, for `--active-only`
+pub fn retain_active(output: &mut IbtopOutput) {
+    for adapter in &mut output.adapters {
+        adapter.ports.retain(|port| {
+            port.state == PortState::Active && port.counters != PortCounters::default()
+        });
+    }
+    output.adapters.retain(|adapter| !adapter.ports.is_empty());
+
+    let adapters = &output.adapters;
+    let kept = |adapter: &str, port_number: u16| {
+        adapters
+            .iter()
+            .any(|a| a.name == adapter && a.ports.iter().any(|p| p.port_number == port_number))
+    };
+    output
+        .rates
+        .retain(|rate| kept(&rate.adapter, rate.port_number));
+    output
+        .history
+        .retain(|samples| kept(&samples.adapter, samples.port_number));
+}
+
+/// Mark ports whose averaged error rate is above `threshold` errors/s
+pub fn mark_error_alerts(rates: &mut [PortRates], threshold: f64) {
+    for rate in rates {
+        rate.alert = rate.error_rate > threshold;
+    }
+}
+
+fn to_port_rates(adapter: String, port_number: u16, metrics: &PortMetrics) -> PortRates {
+    PortRates {
+        adapter,
+        port_number,
+        rx_bytes_per_sec: metrics.rx_bytes_per_sec,
+        tx_bytes_per_sec: metrics.tx_bytes_per_sec,
+        rx_packets_per_sec: metrics.rx_packets_per_sec,
+        tx_packets_per_sec: metrics.tx_packets_per_sec,
+        error_rate: metrics.error_rate,
+        discard_rate: metrics.discard_rate,
+        alert: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_alerts_in_json() {
+        let rate = |error_rate| PortRates {
+            adapter: "mlx5_0".to_string(),
+            port_number: 1,
+            error_rate,
+            ..PortRates::default()
+        };
+        let mut rates = vec![rate(0.0), rate(0.5), rate(3.0)];
+        mark_error_alerts(&mut rates, 0.5);
+        let alerts: Vec<bool> = rates.iter().map(|r| r.alert).collect();
+        assert_eq!(alerts, [false, false, true]);
+
+        // Present only where set, so snapshots without a threshold are unchanged
+        assert!(serde_json::to_string(&rates[2])
+            .unwrap()
+            .contains(r#""alert":true"#));
+        assert!(!serde_json::to_string(&rates[1]).unwrap().contains("alert"));
+    }
+
+    #[test]
+    fn test_flat_json_shape() {
+        let port = |port_number, rx_bytes| PortInfo {
+            port_number,
+            state: PortState::Active,
+            rate: "100 Gb/sec (4X EDR)".to_string(),
+            counters: PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            },
+            ..PortInfo::default()
+        };
+        let output = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![
+                AdapterInfo {
+                    name: "mlx5_0".to_string(),
+                    ports: vec![port(1, 10), port(2, 20)],
+                    ..AdapterInfo::default()
+                },
+                AdapterInfo {
+                    name: "mlx5_1".to_string(),
+                    ports: vec![port(1, 30)],
+                    ..AdapterInfo::default()
+                },
+            ],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+
+        let json = serde_json::to_value(flatten_ports(&output)).unwrap();
+        let records = json.as_array().unwrap();
+        assert_eq!(records.len(), 3);
+
+        let expected_keys = [
+            "adapter",
+            "hostname",
+            "link_downed",
+            "link_error_recovery",
+            "local_link_integrity_errors",
+            "port",
+            "rate",
+            "rx_bytes",
+            "rx_dropped",
+            "rx_errors",
+            "rx_packets",
+            "state",
+            "symbol_errors",
+            "tx_bytes",
+            "tx_discards",
+            "tx_packets",
+            "xmit_wait",
+        ];
+        for record in records {
+            let mut keys: Vec<&str> = record
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            keys.sort_unstable();
+            assert_eq!(keys, expected_keys);
+            // Every value is a scalar, so the array loads straight into a table
+            assert!(record
+                .as_object()
+                .unwrap()
+                .values()
+                .all(|v| !v.is_object() && !v.is_array()));
+        }
+
+        assert_eq!(records[2]["adapter"], "mlx5_1");
+        assert_eq!(records[2]["port"], 1);
+        assert_eq!(records[2]["rx_bytes"], 30);
+        assert_eq!(records[2]["hostname"], "node01");
+        assert_eq!(records[2]["state"], "Active");
+    }
+
+    #[test]
+    fn test_list_lines() {
+        let port = |port_number, state| PortInfo {
+            port_number,
+            state,
+            ..PortInfo::default()
+        };
+        let adapters = vec![
+            AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![port(1, PortState::Active), port(2, PortState::Down)],
+                ..AdapterInfo::default()
+            },
+            AdapterInfo {
+                name: "mlx5_bond0".to_string(),
+                ports: vec![port(1, PortState::Armed)],
+                ..AdapterInfo::default()
+            },
+            AdapterInfo {
+                name: "mlx4_0".to_string(),
+                ..AdapterInfo::default()
+            },
+        ];
+
+        assert_eq!(
+            to_list(&adapters),
+            "mlx5_0      port 1 ACTIVE, port 2 DOWN\n\
+             mlx5_bond0  port 1 ARMED\n\
+             mlx4_0      no ports\n"
+        );
+        assert_eq!(to_list(&[]), "");
+    }
+
+    #[test]
+    fn test_watch_lines() {
+        let sample = |rx_bytes| {
+            vec![AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![
+                    PortInfo {
+                        port_number: 1,
+                        state: PortState::Active,
+                        counters: PortCounters {
+                            rx_bytes,
+                            ..PortCounters::default()
+                        },
+                        ..PortInfo::default()
+                    },
+                    PortInfo {
+                        port_number: 12,
+                        state: PortState::Down,
+                        ..PortInfo::default()
+                    },
+                ],
+                ..AdapterInfo::default()
+            }]
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+
+        // Before a second sample there is nothing to rate
+        let text = to_watch_lines("12:00:00", &sample(0), &metrics, RateDisplay::default());
+        assert_eq!(
+            text,
+            "12:00:00 mlx5_0:1   ACTIVE  rx        --  tx        --  err --\n\
+             12:00:00 mlx5_0:12  DOWN    rx        --  tx        --  err --\n"
+        );
+
+        thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(1 << 30));
+        let text = to_watch_lines(
+            "12:00:01",
+            &sample(1 << 30),
+            &metrics,
+            RateDisplay::default(),
+        );
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("12:00:01 mlx5_0:1   ACTIVE  rx "));
+        assert!(lines[0].contains("GB/s") && lines[0].ends_with("err 0.0/s"));
+        assert!(lines[1].contains("tx   0.00B/s"));
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let output = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![
+                    PortInfo {
+                        port_number: 1,
+                        state: PortState::Active,
+                        rate: "100 Gb/sec (4X EDR)".to_string(),
+                        counters: PortCounters {
+                            rx_bytes: 10,
+                            tx_bytes: 20,
+                            rx_dropped: 7,
+                            ..PortCounters::default()
+                        },
+                        ..PortInfo::default()
+                    },
+                    PortInfo {
+                        port_number: 2,
+                        state: PortState::Down,
+                        rate: "10, maybe".to_string(),
+                        ..PortInfo::default()
+                    },
+                ],
+                ..AdapterInfo::default()
+            }],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+
+        let csv = to_csv(&output);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        // Published column names stay put for existing consumers
+        assert_eq!(
+            lines[0],
+            "adapter,port,state,rate,rx_bytes,tx_bytes,rx_packets,tx_packets,rx_errors,tx_errors,rx_dropped"
+        );
+        assert_eq!(
+            lines[1],
+            "mlx5_0,1,ACTIVE,100 Gb/sec (4X EDR),10,20,0,0,0,0,7"
+        );
+        assert_eq!(lines[2], "mlx5_0,2,DOWN,\"10, maybe\",0,0,0,0,0,0,0");
+        // Every row has a value for each header column
+        assert_eq!(lines[1].split(',').count(), CSV_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn test_prometheus_exposition() {
+        let output = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![
+                    PortInfo {
+                        port_number: 1,
+                        state: PortState::Active,
+                        counters: PortCounters {
+                            rx_bytes: 123,
+                            tx_discards: 4,
+                            ..PortCounters::default()
+                        },
+                        ..PortInfo::default()
+                    },
+                    PortInfo {
+                        port_number: 2,
+                        state: PortState::Down,
+                        ..PortInfo::default()
+                    },
+                ],
+                ..AdapterInfo::default()
+            }],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+
+        let text = to_prometheus(&output);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines.contains(&r#"ibtop_port_rx_bytes_total{adapter="mlx5_0",port="1"} 123"#));
+        assert!(lines.contains(&r#"ibtop_port_tx_errors_total{adapter="mlx5_0",port="1"} 4"#));
+        assert!(lines.contains(&r#"ibtop_port_state{adapter="mlx5_0",port="1"} 1"#));
+        assert!(lines.contains(&r#"ibtop_port_state{adapter="mlx5_0",port="2"} 0"#));
+        assert!(lines.contains(&"# TYPE ibtop_port_rx_packets_total counter"));
+        assert!(lines.contains(&"# TYPE ibtop_port_state gauge"));
+
+        // Every metric family is announced before its samples
+        let families = PROMETHEUS_COUNTERS.len() + 1;
+        assert_eq!(
+            lines.iter().filter(|l| l.starts_with("# HELP")).count(),
+            families
+        );
+        assert_eq!(lines.len(), families * (2 + 2));
+
+        assert_eq!(prometheus_label(r#"a"b\c"#), r#"a\"b\\c"#);
+    }
+
+    #[test]
+    fn test_active_only_drops_down_and_idle_ports() {
+        let port = |port_number, state, rx_bytes| PortInfo {
+            port_number,
+            state,
+            counters: PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            },
+            ..PortInfo::default()
+        };
+        let rates = |adapter: &str, port_number| PortRates {
+            adapter: adapter.to_string(),
+            port_number,
+            ..PortRates::default()
+        };
+        let mut output = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![
+                AdapterInfo {
+                    name: "mlx5_0".to_string(),
+                    ports: vec![
+                        port(1, PortState::Active, 10),
+                        port(2, PortState::Down, 20),
+                        port(3, PortState::Active, 0),
+                    ],
+                    ..AdapterInfo::default()
+                },
+                AdapterInfo {
+                    name: "mlx5_1".to_string(),
+                    ports: vec![port(1, PortState::Down, 0)],
+                    ..AdapterInfo::default()
+                },
+            ],
+            rates: vec![rates("mlx5_0", 1), rates("mlx5_0", 2), rates("mlx5_1", 1)],
+            history: vec![PortHistorySamples {
+                adapter: "mlx5_1".to_string(),
+                port_number: 1,
+                ..PortHistorySamples::default()
+            }],
+        };
+
+        retain_active(&mut output);
+
+        assert_eq!(output.adapters.len(), 1);
+        assert_eq!(output.adapters[0].name, "mlx5_0");
+        let ports: Vec<u16> = output.adapters[0]
+            .ports
+            .iter()
+            .map(|p| p.port_number)
+            .collect();
+        assert_eq!(ports, vec![1]);
+        assert_eq!(output.rates, vec![rates("mlx5_0", 1)]);
+        assert!(output.history.is_empty());
+    }
+
+    #[test]
+    fn test_history_export_is_chronological() {
+        // Five samples into three slots: the buffer has wrapped
+        let mut history = PortHistory::with_capacity(3);
+        for i in 1..=5 {
+            let i = f64::from(i);
+            history.record(
+                i * 100.0,
+                i * 10.0,
+                i * 2.0,
+                i,
+                i / 10.0,
+                Duration::from_secs(1),
+            );
+        }
+
+        let samples = to_history_samples("mlx5_0", 1, &history);
+        assert_eq!(samples.rx_bytes_per_sec, vec![300.0, 400.0, 500.0]);
+        assert_eq!(samples.tx_bytes_per_sec, vec![30.0, 40.0, 50.0]);
+        assert_eq!(samples.rx_packets_per_sec, vec![6.0, 8.0, 10.0]);
+        assert_eq!(samples.tx_packets_per_sec, vec![3.0, 4.0, 5.0]);
+        assert_eq!(samples.error_rate, vec![0.3, 0.4, 0.5]);
+        assert_eq!(samples.timestamps.len(), 3);
+        assert!(samples.timestamps.windows(2).all(|w| w[0] <= w[1]));
+
+        let json = serde_json::to_value(&samples).unwrap();
+        assert_eq!(
+            json["rx_bytes_per_sec"],
+            serde_json::json!([300.0, 400.0, 500.0])
+        );
+    }
+}
@@ -9,12 +9,47 @@
 #![allow(clippy::cast_sign_loss)] // Values are always positive
 
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
 
 /// Default history length (number of samples)
 pub const DEFAULT_HISTORY_SIZE: usize = 120; // 30 seconds at 4 samples/sec
 
+/// Most samples kept per series, however long `--history-seconds` asks for
+pub const MAX_HISTORY_SIZE: usize = 100_000;
+
+/// Samples needed to cover `window` when sampling every `interval`,
+/// between one and `MAX_HISTORY_SIZE`
+pub fn capacity_for(window: Duration, interval: Duration) -> usize {
+    let samples = (window.as_secs_f64() / interval.as_secs_f64()).ceil();
+    if samples.is_finite() {
+        (samples as usize).clamp(1, MAX_HISTORY_SIZE)
+    } else {
+        MAX_HISTORY_SIZE
+    }
+}
+
+/// Bumped whenever the layout of `--history-file` changes; files written by
+/// another version are ignored
+const HISTORY_FILE_VERSION: u32 = 1;
+
 /// Ring buffer for storing historical values
-#[derive(Debug, Clone)]
+///
+/// Serialized as its capacity and its values oldest first, so loading
+/// rebuilds the write position by pushing them back in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    try_from = "SavedRing<T>",
+    into = "SavedRing<T>",
+    bound(
+        serialize = "T: Serialize + Clone + Default",
+        deserialize = "T: Deserialize<'de> + Clone + Default"
+    )
+)]
 pub struct RingBuffer<T: Clone + Default> {
     data: Vec<T>,
     capacity: usize,
@@ -24,7 +59,10 @@ pub struct RingBuffer<T: Clone + Default> {
 
 impl<T: Clone + Default> RingBuffer<T> {
     /// Create a new ring buffer with the specified capacity
+    ///
+    /// A capacity of zero is raised to one, so `push` always has a slot.
     pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
         Self {
             data: vec![T::default(); capacity],
             capacity,
@@ -95,20 +133,70 @@ impl<T: Clone + Default> RingBuffer<T> {
         self.len = 0;
     }
 
+    /// The same values in a buffer of another capacity, keeping the newest
+    pub fn resized(&self, capacity: usize) -> Self {
+        let mut ring = RingBuffer::new(capacity);
+        for value in self.to_vec() {
+            ring.push(value);
+        }
+        ring
+    }
+
     /// Get capacity
     pub fn capacity(&self) -> usize {
         self.capacity
     }
 }
 
+/// On-disk form of a `RingBuffer`
+#[derive(Serialize, Deserialize)]
+struct SavedRing<T> {
+    capacity: usize,
+    values: Vec<T>,
+}
+
+/// The file is not trusted: a capacity past `MAX_HISTORY_SIZE` is cut down
+/// rather than allocated, and more values than the capacity are an error
+impl<T: Clone + Default> TryFrom<SavedRing<T>> for RingBuffer<T> {
+    type Error = String;
+
+    fn try_from(saved: SavedRing<T>) -> Result<Self, Self::Error> {
+        if saved.values.len() > saved.capacity {
+            return Err(format!(
+                "{} values saved for a capacity of {}",
+                saved.values.len(),
+                saved.capacity
+            ));
+        }
+        let mut ring = RingBuffer::new(saved.capacity.min(MAX_HISTORY_SIZE));
+        for value in saved.values {
+            ring.push(value);
+        }
+        Ok(ring)
+    }
+}
+
+impl<T: Clone + Default> From<RingBuffer<T>> for SavedRing<T> {
+    fn from(ring: RingBuffer<T>) -> Self {
+        SavedRing {
+            capacity: ring.capacity,
+            values: ring.to_vec(),
+        }
+    }
+}
+
 /// Historical metrics for a single port
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortHistory {
     pub rx_bytes_per_sec: RingBuffer<f64>,
     pub tx_bytes_per_sec: RingBuffer<f64>,
     pub rx_packets_per_sec: RingBuffer<f64>,
     pub tx_packets_per_sec: RingBuffer<f64>,
     pub error_rate: RingBuffer<f64>,
+    /// When each sample was recorded, in seconds since the Unix epoch
+    pub timestamps: RingBuffer<f64>,
+    /// Seconds each sample's rates were measured over
+    pub intervals: RingBuffer<f64>,
 }
 
 impl PortHistory {
@@ -125,35 +213,78 @@ impl PortHistory {
             rx_packets_per_sec: RingBuffer::new(capacity),
             tx_packets_per_sec: RingBuffer::new(capacity),
             error_rate: RingBuffer::new(capacity),
+            timestamps: RingBuffer::new(capacity),
+            intervals: RingBuffer::new(capacity),
         }
     }
 
-    /// Record a new data point
-    pub fn record(&mut self, rx_bps: f64, tx_bps: f64, rx_pps: f64, tx_pps: f64, errors: f64) {
+    /// The same samples in buffers of another capacity
+    fn resized(&self, capacity: usize) -> Self {
+        Self {
+            rx_bytes_per_sec: self.rx_bytes_per_sec.resized(capacity),
+            tx_bytes_per_sec: self.tx_bytes_per_sec.resized(capacity),
+            rx_packets_per_sec: self.rx_packets_per_sec.resized(capacity),
+            tx_packets_per_sec: self.tx_packets_per_sec.resized(capacity),
+            error_rate: self.error_rate.resized(capacity),
+            timestamps: self.timestamps.resized(capacity),
+            intervals: self.intervals.resized(capacity),
+        }
+    }
+
+    /// Record a new data point, with rates measured over `interval`
+    pub fn record(
+        &mut self,
+        rx_bps: f64,
+        tx_bps: f64,
+        rx_pps: f64,
+        tx_pps: f64,
+        errors: f64,
+        interval: Duration,
+    ) {
         self.rx_bytes_per_sec.push(rx_bps);
         self.tx_bytes_per_sec.push(tx_bps);
         self.rx_packets_per_sec.push(rx_pps);
         self.tx_packets_per_sec.push(tx_pps);
         self.error_rate.push(errors);
+        self.timestamps.push(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |d| d.as_secs_f64()),
+        );
+        self.intervals.push(interval.as_secs_f64());
     }
 
     /// Get sparkline data for RX throughput (last N samples, normalized to 0-1)
-    pub fn rx_sparkline_data(&self, samples: usize) -> Vec<u64> {
-        normalize_for_sparkline(self.rx_bytes_per_sec.last_n(samples))
+    ///
+    /// `clip` is the optional spike percentile, see `sparkline_scale`.
+    pub fn rx_sparkline_data(&self, samples: usize, clip: Option<f64>) -> Vec<u64> {
+        let rx: Vec<f64> = self.rx_bytes_per_sec.last_n(samples).copied().collect();
+        normalize_for_sparkline(&rx, sparkline_scale(&rx, clip))
     }
 
     /// Get sparkline data for TX throughput (last N samples, normalized to 0-1)
-    pub fn tx_sparkline_data(&self, samples: usize) -> Vec<u64> {
-        normalize_for_sparkline(self.tx_bytes_per_sec.last_n(samples))
+    pub fn tx_sparkline_data(&self, samples: usize, clip: Option<f64>) -> Vec<u64> {
+        let tx: Vec<f64> = self.tx_bytes_per_sec.last_n(samples).copied().collect();
+        normalize_for_sparkline(&tx, sparkline_scale(&tx, clip))
     }
 
     /// Get combined RX+TX sparkline data
+    #[allow(dead_code)] // The table also needs the scale, see `combined_sparkline`
     pub fn combined_sparkline_data(&self, samples: usize) -> Vec<u64> {
+        self.combined_sparkline(samples, None).0
+    }
+
+    /// Combined RX+TX sparkline data and the rate it is scaled to
+    ///
+    /// A full-height bar means this many bytes per second, whether that is
+    /// 1 KB/s or 100 GB/s: the window max, or with `clip` its percentile.
+    pub fn combined_sparkline(&self, samples: usize, clip: Option<f64>) -> (Vec<u64>, f64) {
         let rx: Vec<f64> = self.rx_bytes_per_sec.last_n(samples).copied().collect();
         let tx: Vec<f64> = self.tx_bytes_per_sec.last_n(samples).copied().collect();
 
         let combined: Vec<f64> = rx.iter().zip(tx.iter()).map(|(r, t)| r + t).collect();
-        normalize_for_sparkline(combined.iter())
+        let scale = sparkline_scale(&combined, clip);
+        (normalize_for_sparkline(&combined, scale), scale)
     }
 
     /// Get the peak throughput observed
@@ -180,6 +311,47 @@ impl PortHistory {
         let tx_sum: f64 = self.tx_bytes_per_sec.iter().sum();
         (rx_sum + tx_sum) / self.rx_bytes_per_sec.len() as f64
     }
+
+    /// Peak over average throughput: ~1.0 for steady traffic, high for
+    /// spiky traffic; `None` while the average is zero
+    pub fn burstiness(&self) -> Option<f64> {
+        let avg = self.avg_throughput();
+        (avg > 0.0).then(|| self.peak_throughput() / avg)
+    }
+
+    /// Seconds the samples reach back, each counted for the interval it was
+    /// measured over
+    pub fn span_secs(&self) -> f64 {
+        self.intervals.iter().sum()
+    }
+
+    /// Bytes (RX + TX) moved during the last `window`
+    ///
+    /// Each sample counts for the interval it was measured over, so a
+    /// refresh rate changed with `+`/`-` midway still adds up to the window;
+    /// the oldest sample in it counts in part.
+    /// Returns the total and whether the history covers the whole window;
+    /// when it doesn't, the total is a lower bound.
+    pub fn bytes_over(&self, window: Duration) -> (f64, bool) {
+        let mut remaining = window.as_secs_f64();
+        let mut bytes = 0.0;
+        let newest_first = self
+            .rx_bytes_per_sec
+            .to_vec()
+            .into_iter()
+            .zip(self.tx_bytes_per_sec.to_vec())
+            .zip(self.intervals.to_vec())
+            .rev();
+        for ((rx, tx), secs) in newest_first {
+            if remaining <= 0.0 {
+                break;
+            }
+            let counted = secs.min(remaining);
+            bytes += (rx + tx) * counted;
+            remaining -= counted;
+        }
+        (bytes, remaining <= 0.0)
+    }
 }
 
 impl Default for PortHistory {
@@ -188,26 +360,67 @@ impl Default for PortHistory {
     }
 }
 
-/// Normalize values for sparkline display (0-7 range for 8-level sparkline)
-fn normalize_for_sparkline<'a>(values: impl Iterator<Item = &'a f64>) -> Vec<u64> {
-    let values: Vec<f64> = values.copied().collect();
-    if values.is_empty() {
-        return vec![];
-    }
+/// Exponential moving average of `values`, oldest first; `alpha` in (0, 1]
+/// is the weight of each new value, so 1.0 returns the values unchanged
+pub fn ema(values: impl IntoIterator<Item = f64>, alpha: f64) -> Vec<f64> {
+    let mut average = None;
+    values
+        .into_iter()
+        .map(|value| {
+            let next = average.map_or(value, |avg: f64| alpha * value + (1.0 - alpha) * avg);
+            average = Some(next);
+            next
+        })
+        .collect()
+}
 
+/// Rate a full sparkline bar stands for: the max of `values`, or with
+/// `clip` their `clip`th percentile (nearest rank)
+///
+/// One sample far above the rest, such as a spike from a counter reset that
+/// slipped through, would otherwise flatten every other bar. Samples above
+/// the percentile are drawn full height. Where the percentile is zero (an
+/// idle port with one burst) the max is kept, so the burst still shows.
+fn sparkline_scale(values: &[f64], clip: Option<f64>) -> f64 {
     let max = values.iter().copied().fold(0.0_f64, f64::max);
-    if max <= 0.0 {
+    let Some(percentile) = clip.filter(|_| !values.is_empty()) else {
+        return max;
+    };
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    let cap = sorted[rank.clamp(1, sorted.len()) - 1];
+    if cap > 0.0 {
+        cap
+    } else {
+        max
+    }
+}
+
+/// Normalize values against `scale` for sparkline display (0-7 range for
+/// 8-level sparkline); values above it are drawn full height
+fn normalize_for_sparkline(values: &[f64], scale: f64) -> Vec<u64> {
+    if scale <= 0.0 {
         return vec![0; values.len()];
     }
 
     values
         .iter()
-        .map(|v| ((v / max) * 7.0).round() as u64)
+        .map(|v| ((v.min(scale) / scale) * 7.0).round() as u64)
         .collect()
 }
 
+/// Key identifying a port in the metrics and history maps
+///
+/// Adapter names are unique once discovery has run, which renames any
+/// duplicates (see `discovery::disambiguate_names`).
+pub fn port_key(adapter: &str, port: u16) -> String {
+    format!("{adapter}:{port}")
+}
+
 /// Collection of all port histories
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct HistoryCollector {
     histories: HashMap<String, PortHistory>,
     capacity: usize,
@@ -229,7 +442,7 @@ impl HistoryCollector {
 
     /// Get or create history for a port
     pub fn get_or_create(&mut self, adapter: &str, port: u16) -> &mut PortHistory {
-        let key = format!("{adapter}:{port}");
+        let key = port_key(adapter, port);
         self.histories
             .entry(key)
             .or_insert_with(|| PortHistory::with_capacity(self.capacity))
@@ -237,7 +450,7 @@ impl HistoryCollector {
 
     /// Get history for a port (read-only)
     pub fn get(&self, adapter: &str, port: u16) -> Option<&PortHistory> {
-        let key = format!("{adapter}:{port}");
+        let key = port_key(adapter, port);
         self.histories.get(&key)
     }
 
@@ -252,16 +465,22 @@ impl HistoryCollector {
         rx_pps: f64,
         tx_pps: f64,
         errors: f64,
+        interval: Duration,
     ) {
         self.get_or_create(adapter, port)
-            .record(rx_bps, tx_bps, rx_pps, tx_pps, errors);
+            .record(rx_bps, tx_bps, rx_pps, tx_pps, errors, interval);
+    }
+
+    /// Forget every port's history, keeping the capacity
+    pub fn clear(&mut self) {
+        self.histories.clear();
     }
 
     /// Remove stale entries for ports that no longer exist
     pub fn retain_ports(&mut self, active_ports: &[(String, u16)]) {
         let active_keys: std::collections::HashSet<String> = active_ports
             .iter()
-            .map(|(adapter, port)| format!("{adapter}:{port}"))
+            .map(|(adapter, port)| port_key(adapter, *port))
             .collect();
 
         self.histories.retain(|key, _| active_keys.contains(key));
@@ -276,6 +495,65 @@ impl HistoryCollector {
     pub fn port_count(&self) -> usize {
         self.histories.len()
     }
+
+    /// Write every port's history to `path` (`--history-file`)
+    ///
+    /// The history goes to a temporary file next to `path`, flushed and
+    /// synced before it replaces `path`, so a shutdown never leaves a file
+    /// with only part of the samples.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let file = HistoryFile {
+            version: HISTORY_FILE_VERSION,
+            histories: &self.histories,
+        };
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        serde_json::to_writer(&mut writer, &file)?;
+        writer.flush()?;
+        writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        std::fs::rename(&temp_path, path)
+    }
+
+    /// History written by `save`, in buffers of `capacity` samples
+    ///
+    /// `None` when the file is missing, unreadable or from another version,
+    /// so a stale file means starting empty rather than failing.
+    pub fn load(path: &Path, capacity: usize) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let file: HistoryFile<HashMap<String, PortHistory>> =
+            serde_json::from_str(&contents).ok()?;
+        if file.version != HISTORY_FILE_VERSION {
+            return None;
+        }
+        let histories = file
+            .histories
+            .into_iter()
+            .map(|(key, history)| (key, history.resized(capacity)))
+            .collect();
+        Some(Self {
+            histories,
+            capacity,
+        })
+    }
+}
+
+impl Default for HistoryCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Layout of `--history-file`
+#[derive(Serialize, Deserialize)]
+struct HistoryFile<H> {
+    version: u32,
+    histories: H,
 }
 
 #[cfg(test)]
@@ -315,6 +593,154 @@ mod tests {
         assert_eq!(values, vec![2, 3, 4]);
     }
 
+    #[test]
+    fn test_saved_history_is_complete_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        std::fs::write(&path, "an older, longer file that must not leak through").unwrap();
+
+        let mut collector = HistoryCollector::with_capacity(500);
+        for port in 1..=4 {
+            for i in 0..500 {
+                collector.record(
+                    "mlx5_0",
+                    port,
+                    f64::from(i),
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    Duration::from_secs(1),
+                );
+            }
+        }
+        collector.save(&path).unwrap();
+        drop(collector);
+
+        // Read back raw, as the next run would: every sample, nothing else
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let file: HistoryFile<HashMap<String, PortHistory>> =
+            serde_json::from_str(&contents).unwrap();
+        assert_eq!(file.histories.len(), 4);
+        for history in file.histories.values() {
+            assert_eq!(history.rx_bytes_per_sec.len(), 500);
+            assert_eq!(history.rx_bytes_per_sec.last(), Some(&499.0));
+        }
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1, "the temporary file is renamed away");
+    }
+
+    #[test]
+    fn test_history_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+
+        let mut collector = HistoryCollector::with_capacity(3);
+        for i in 1..=5 {
+            let value = f64::from(i);
+            collector.record(
+                "mlx5_0",
+                1,
+                value,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                Duration::from_secs(1),
+            );
+        }
+        collector.save(&path).unwrap();
+
+        // The wrapped buffer comes back oldest first and keeps wrapping
+        let mut loaded = HistoryCollector::load(&path, 3).unwrap();
+        let history = loaded.get_or_create("mlx5_0", 1);
+        assert_eq!(history.rx_bytes_per_sec.to_vec(), vec![3.0, 4.0, 5.0]);
+        history.record(6.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        assert_eq!(history.rx_bytes_per_sec.to_vec(), vec![4.0, 5.0, 6.0]);
+        assert_eq!(history.rx_bytes_per_sec.last(), Some(&6.0));
+
+        // A smaller capacity keeps the newest samples
+        let loaded = HistoryCollector::load(&path, 2).unwrap();
+        let history = loaded.get("mlx5_0", 1).unwrap();
+        assert_eq!(history.rx_bytes_per_sec.to_vec(), vec![4.0, 5.0]);
+        assert_eq!(history.timestamps.len(), 2);
+
+        // Missing files and other versions start empty
+        assert!(HistoryCollector::load(&dir.path().join("nope.json"), 3).is_none());
+        std::fs::write(&path, r#"{"version":0,"histories":{}}"#).unwrap();
+        assert!(HistoryCollector::load(&path, 3).is_none());
+        std::fs::write(&path, "not json").unwrap();
+        assert!(HistoryCollector::load(&path, 3).is_none());
+    }
+
+    #[test]
+    fn test_history_file_capacity_is_not_trusted() {
+        let ring = |text: &str| serde_json::from_str::<RingBuffer<f64>>(text);
+
+        // Allocating this many slots would abort
+        let huge = ring(r#"{"capacity":1000000000000000,"values":[1.0,2.0]}"#).unwrap();
+        assert_eq!(huge.capacity(), MAX_HISTORY_SIZE);
+        assert_eq!(huge.to_vec(), vec![1.0, 2.0]);
+
+        assert!(ring(r#"{"capacity":1,"values":[1.0,2.0]}"#).is_err());
+    }
+
+    #[test]
+    fn test_capacity_for_window() {
+        let ms = Duration::from_millis;
+        assert_eq!(capacity_for(Duration::from_secs(30), ms(250)), 120);
+        assert_eq!(capacity_for(Duration::from_mins(10), ms(250)), 2_400);
+        // Partial samples round up, and there is always at least one
+        assert_eq!(capacity_for(ms(1_100), ms(500)), 3);
+        assert_eq!(capacity_for(ms(1), Duration::from_secs(2)), 1);
+        // Absurd windows are capped rather than allocated
+        assert_eq!(
+            capacity_for(Duration::from_hours(24 * 365), ms(1)),
+            MAX_HISTORY_SIZE
+        );
+        assert_eq!(
+            capacity_for(Duration::MAX, Duration::from_nanos(1)),
+            MAX_HISTORY_SIZE
+        );
+    }
+
+    #[test]
+    fn test_ema_smooths_without_touching_raw() {
+        let mut history = PortHistory::with_capacity(4);
+        for rx in [100.0, 0.0, 100.0, 0.0] {
+            history.record(rx, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        let smoothed = ema(history.rx_bytes_per_sec.iter().copied(), 0.5);
+        assert_eq!(smoothed, vec![100.0, 50.0, 75.0, 37.5]);
+        assert_eq!(
+            history.rx_bytes_per_sec.to_vec(),
+            vec![100.0, 0.0, 100.0, 0.0]
+        );
+
+        assert_eq!(ema([3.0, 9.0], 1.0), vec![3.0, 9.0]);
+        assert!(ema(Vec::new(), 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_ring_buffer_zero_capacity() {
+        let mut buf: RingBuffer<i32> = RingBuffer::new(0);
+        assert_eq!(buf.capacity(), 1);
+        assert!(buf.last().is_none());
+
+        buf.push(1);
+        buf.push(2);
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf.last(), Some(&2));
+        assert_eq!(buf.to_vec(), vec![2]);
+
+        // Zero-sized histories built on top of it are just as safe
+        let mut history = HistoryCollector::with_capacity(0);
+        let port = history.get_or_create("mlx5_0", 1);
+        port.record(1000.0, 500.0, 10.0, 5.0, 0.0, Duration::from_secs(1));
+        port.record(2000.0, 1000.0, 20.0, 10.0, 0.0, Duration::from_secs(1));
+        assert_eq!(port.rx_bytes_per_sec.to_vec(), vec![2000.0]);
+    }
+
     #[test]
     fn test_ring_buffer_last() {
         let mut buf: RingBuffer<i32> = RingBuffer::new(5);
@@ -348,8 +774,8 @@ mod tests {
     fn test_port_history_record() {
         let mut history = PortHistory::with_capacity(10);
 
-        history.record(1000.0, 500.0, 10.0, 5.0, 0.0);
-        history.record(2000.0, 1000.0, 20.0, 10.0, 0.1);
+        history.record(1000.0, 500.0, 10.0, 5.0, 0.0, Duration::from_secs(1));
+        history.record(2000.0, 1000.0, 20.0, 10.0, 0.1, Duration::from_secs(1));
 
         assert_eq!(history.rx_bytes_per_sec.len(), 2);
         assert_eq!(history.tx_bytes_per_sec.len(), 2);
@@ -357,8 +783,8 @@ mod tests {
 
     #[test]
     fn test_normalize_for_sparkline() {
-        let values = vec![0.0, 50.0, 100.0, 25.0, 75.0];
-        let normalized = normalize_for_sparkline(values.iter());
+        let values = [0.0, 50.0, 100.0, 25.0, 75.0];
+        let normalized = normalize_for_sparkline(&values, sparkline_scale(&values, None));
 
         assert_eq!(normalized.len(), 5);
         assert_eq!(normalized[0], 0); // 0%
@@ -368,23 +794,76 @@ mod tests {
     #[test]
     fn test_normalize_empty() {
         let values: Vec<f64> = vec![];
-        let normalized = normalize_for_sparkline(values.iter());
+        let normalized = normalize_for_sparkline(&values, sparkline_scale(&values, Some(95.0)));
         assert!(normalized.is_empty());
     }
 
     #[test]
     fn test_normalize_all_zero() {
-        let values = vec![0.0, 0.0, 0.0];
-        let normalized = normalize_for_sparkline(values.iter());
+        let values = [0.0, 0.0, 0.0];
+        let normalized = normalize_for_sparkline(&values, sparkline_scale(&values, None));
         assert_eq!(normalized, vec![0, 0, 0]);
     }
 
+    #[test]
+    fn test_clipped_spike_no_longer_flattens_sparkline() {
+        let mut history = PortHistory::new();
+        for i in 0..39 {
+            history.record(
+                f64::from(i % 4 + 1) * 1e9,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                Duration::from_secs(1),
+            );
+        }
+        // A reset slipping through reads as petabytes per second
+        history.record(1e18, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+
+        let (plain, scale) = history.combined_sparkline(40, None);
+        assert!((scale - 1e18).abs() < 1.0);
+        assert!(plain[..39].iter().all(|&bar| bar == 0));
+
+        let (clipped, scale) = history.combined_sparkline(40, Some(95.0));
+        assert!((scale - 4e9).abs() < 1.0);
+        assert_eq!(&clipped[..4], &[2, 4, 5, 7]);
+        assert_eq!(clipped[39], 7);
+        assert_eq!(history.rx_sparkline_data(40, Some(95.0)), clipped);
+
+        // An idle port's one burst is not clipped away
+        let mut idle = PortHistory::new();
+        for _ in 0..39 {
+            idle.record(0.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        idle.record(5e9, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        assert_eq!(idle.rx_sparkline_data(40, Some(95.0))[39], 7);
+    }
+
     #[test]
     fn test_history_collector_basic() {
         let mut collector = HistoryCollector::new();
 
-        collector.record("mlx5_0", 1, 1000.0, 500.0, 10.0, 5.0, 0.0);
-        collector.record("mlx5_0", 2, 2000.0, 1000.0, 20.0, 10.0, 0.1);
+        collector.record(
+            "mlx5_0",
+            1,
+            1000.0,
+            500.0,
+            10.0,
+            5.0,
+            0.0,
+            Duration::from_secs(1),
+        );
+        collector.record(
+            "mlx5_0",
+            2,
+            2000.0,
+            1000.0,
+            20.0,
+            10.0,
+            0.1,
+            Duration::from_secs(1),
+        );
 
         assert_eq!(collector.port_count(), 2);
         assert!(collector.get("mlx5_0", 1).is_some());
@@ -396,9 +875,36 @@ mod tests {
     fn test_history_collector_retain() {
         let mut collector = HistoryCollector::new();
 
-        collector.record("mlx5_0", 1, 1000.0, 500.0, 10.0, 5.0, 0.0);
-        collector.record("mlx5_0", 2, 2000.0, 1000.0, 20.0, 10.0, 0.1);
-        collector.record("mlx5_1", 1, 3000.0, 1500.0, 30.0, 15.0, 0.0);
+        collector.record(
+            "mlx5_0",
+            1,
+            1000.0,
+            500.0,
+            10.0,
+            5.0,
+            0.0,
+            Duration::from_secs(1),
+        );
+        collector.record(
+            "mlx5_0",
+            2,
+            2000.0,
+            1000.0,
+            20.0,
+            10.0,
+            0.1,
+            Duration::from_secs(1),
+        );
+        collector.record(
+            "mlx5_1",
+            1,
+            3000.0,
+            1500.0,
+            30.0,
+            15.0,
+            0.0,
+            Duration::from_secs(1),
+        );
 
         assert_eq!(collector.port_count(), 3);
 
@@ -411,26 +917,127 @@ mod tests {
         assert!(collector.get("mlx5_1", 1).is_some());
     }
 
+    #[test]
+    fn test_combined_sparkline_scale_is_window_max() {
+        let mut history = PortHistory::new();
+        history.record(9000.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1)); // Outside the window below
+        history.record(100.0, 50.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        history.record(400.0, 300.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        history.record(200.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+
+        let (data, scale) = history.combined_sparkline(3, None);
+        assert!((scale - 700.0).abs() < f64::EPSILON);
+        assert_eq!(data, vec![2, 7, 2]);
+        assert_eq!(data, history.combined_sparkline_data(3));
+
+        assert!(PortHistory::new().combined_sparkline(3, None).1.abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_port_history_peak_throughput() {
         let mut history = PortHistory::with_capacity(10);
 
-        history.record(1000.0, 500.0, 10.0, 5.0, 0.0);
-        history.record(2000.0, 1500.0, 20.0, 10.0, 0.0);
-        history.record(500.0, 250.0, 5.0, 2.0, 0.0);
+        history.record(1000.0, 500.0, 10.0, 5.0, 0.0, Duration::from_secs(1));
+        history.record(2000.0, 1500.0, 20.0, 10.0, 0.0, Duration::from_secs(1));
+        history.record(500.0, 250.0, 5.0, 2.0, 0.0, Duration::from_secs(1));
 
         // Peak is 2000 + 1500 = 3500
         assert!((history.peak_throughput() - 3500.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_port_history_bytes_over_windows() {
+        let second = Duration::from_secs(1);
+        let mut history = PortHistory::with_capacity(4_000);
+        // 90 samples, one per second: 1 KB/s RX, then 2 KB/s RX + 1 KB/s TX for the last 30
+        for i in 0..90 {
+            if i < 60 {
+                history.record(1_000.0, 0.0, 0.0, 0.0, 0.0, second);
+            } else {
+                history.record(2_000.0, 1_000.0, 0.0, 0.0, 0.0, second);
+            }
+        }
+
+        assert_eq!(history.bytes_over(second), (3_000.0, true));
+        assert_eq!(
+            history.bytes_over(Duration::from_mins(1)),
+            (30.0 * 1_000.0 + 30.0 * 3_000.0, true)
+        );
+        // Only 90s recorded: a lower bound over what is available
+        assert_eq!(
+            history.bytes_over(Duration::from_hours(1)),
+            (60.0 * 1_000.0 + 30.0 * 3_000.0, false)
+        );
+
+        assert_eq!(PortHistory::new().bytes_over(second), (0.0, false));
+    }
+
+    #[test]
+    fn test_bytes_over_follows_interval_changes() {
+        // 10 s of 1 KB/s sampled every 2 s, then 4 KB/s every 250 ms for 2 s
+        let mut history = PortHistory::with_capacity(100);
+        for _ in 0..5 {
+            history.record(1_000.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(2));
+        }
+        for _ in 0..8 {
+            history.record(4_000.0, 0.0, 0.0, 0.0, 0.0, Duration::from_millis(250));
+        }
+
+        assert_eq!(history.bytes_over(Duration::from_secs(1)), (4_000.0, true));
+        // 2 s of fast samples, then 3 s of the slow ones (one of them in part)
+        assert_eq!(
+            history.bytes_over(Duration::from_secs(5)),
+            (8_000.0 + 3_000.0, true)
+        );
+        assert_eq!(
+            history.bytes_over(Duration::from_mins(1)),
+            (8_000.0 + 10_000.0, false)
+        );
+    }
+
+    #[test]
+    fn test_span_sums_sample_intervals() {
+        let mut history = PortHistory::with_capacity(100);
+        assert!(history.span_secs().abs() < f64::EPSILON);
+        for _ in 0..5 {
+            history.record(0.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(2));
+        }
+        for _ in 0..8 {
+            history.record(0.0, 0.0, 0.0, 0.0, 0.0, Duration::from_millis(250));
+        }
+        assert!((history.span_secs() - 12.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_port_history_avg_throughput() {
         let mut history = PortHistory::with_capacity(10);
 
-        history.record(1000.0, 500.0, 10.0, 5.0, 0.0);
-        history.record(2000.0, 1000.0, 20.0, 10.0, 0.0);
+        history.record(1000.0, 500.0, 10.0, 5.0, 0.0, Duration::from_secs(1));
+        history.record(2000.0, 1000.0, 20.0, 10.0, 0.0, Duration::from_secs(1));
 
         // Avg is ((1000+500) + (2000+1000)) / 2 = 4500 / 2 = 2250
         assert!((history.avg_throughput() - 2250.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_port_history_burstiness() {
+        let mut steady = PortHistory::with_capacity(10);
+        for _ in 0..4 {
+            steady.record(1000.0, 1000.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        assert!((steady.burstiness().unwrap() - 1.0).abs() < 1e-9);
+
+        // One 4000 B/s spike among idle samples: peak 4000, avg 1000
+        let mut spiky = PortHistory::with_capacity(10);
+        for rx in [0.0, 0.0, 0.0, 4000.0] {
+            spiky.record(rx, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        assert!((spiky.burstiness().unwrap() - 4.0).abs() < 1e-9);
+
+        // Idle or empty history has no meaningful ratio
+        let mut idle = PortHistory::with_capacity(10);
+        assert_eq!(idle.burstiness(), None);
+        idle.record(0.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        assert_eq!(idle.burstiness(), None);
+    }
 }
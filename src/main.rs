@@ -1,9 +1,21 @@
+mod alerts;
+mod baseline;
+mod cli;
+mod config;
+mod degrade;
 mod discovery;
+mod doctor;
+mod expectations;
+mod export;
 mod history;
+mod merge;
 mod metrics;
+mod serve;
 mod simulation;
 mod types;
 mod ui;
+mod warnings;
+mod watch;
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
@@ -11,12 +23,21 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
 use std::env;
-use std::io;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 const UI_REFRESH_INTERVAL_MS: u64 = 33;
 const METRICS_UPDATE_INTERVAL_MS: u64 = 250;
+/// With `--refresh-on-change`, refresh at least this often: most sysfs
+/// counters never raise change notifications
+const ON_CHANGE_FALLBACK_INTERVAL_MS: u64 = 5_000;
+/// `--watch` sleeps in steps this long, checking for a signal in between
+const WATCH_SLEEP_STEP: Duration = Duration::from_millis(100);
 
 fn get_hostname() -> String {
     hostname::get().map_or_else(
@@ -25,24 +46,12 @@ fn get_hostname() -> String {
     )
 }
 
-fn main() -> Result<(), io::Error> {
-    let args: Vec<String> = env::args().collect();
-    let json_mode = args.contains(&String::from("--json"));
-
-    if json_mode {
-        run_json_mode()
-    } else {
-        run_interactive_mode()
-    }
-}
-
-fn run_json_mode() -> Result<(), io::Error> {
-    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
-
-    let adapters = if use_fake_data {
+/// Discover adapters (or simulate them), hiding port-less adapters unless requested
+fn load_adapters(use_fake_data: bool, options: &cli::Options) -> Vec<types::AdapterInfo> {
+    let mut adapters = if use_fake_data {
         simulation::generate_fake_adapters()
     } else {
-        let real_adapters = discovery::discover_adapters();
+        let real_adapters = discovery::discover_adapters(&options.swap_rxtx);
         if real_adapters.is_empty() && std::env::var("IBTOP_DEMO").is_ok() {
             simulation::generate_fake_adapters()
         } else {
@@ -50,24 +59,324 @@ fn run_json_mode() -> Result<(), io::Error> {
         }
     };
 
-    let output = types::IbtopOutput {
+    if !options.show_empty {
+        discovery::retain_with_ports(&mut adapters);
+    }
+
+    adapters
+}
+
+/// How a run ended; every exit code ibtop uses is chosen here
+///
+/// Keep in sync with the "Exit status" section of `cli::USAGE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    IoError,
+    UsageError,
+    NoAdapters,
+    PermissionDenied,
+}
+
+impl Outcome {
+    /// Classify a batch run from what discovery returned
+    fn from_discovery(adapter_count: usize, permission_denied: bool) -> Self {
+        match (adapter_count, permission_denied) {
+            (0, true) => Outcome::PermissionDenied,
+            (0, false) => Outcome::NoAdapters,
+            _ => Outcome::Success,
+        }
+    }
+
+    const fn exit_code(self) -> u8 {
+        match self {
+            Outcome::Success => 0,
+            Outcome::IoError => 1,
+            Outcome::UsageError => 2,
+            Outcome::NoAdapters => 3,
+            Outcome::PermissionDenied => 4,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let outcome = match cli::Options::parse(env::args().skip(1)) {
+        Ok(mut options) => match apply_config(&mut options) {
+            Ok(()) => run(&options).unwrap_or_else(|err| {
+                eprintln!("ibtop: {err}");
+                Outcome::IoError
+            }),
+            Err(err) => {
+                eprintln!("ibtop: {err}");
+                Outcome::UsageError
+            }
+        },
+        Err(err) => {
+            eprintln!("ibtop: {err}\n\n{}", cli::USAGE);
+            Outcome::UsageError
+        }
+    };
+
+    ExitCode::from(outcome.exit_code())
+}
+
+/// Fill in what the command line left unset from the config file
+fn apply_config(options: &mut cli::Options) -> Result<(), String> {
+    // A broken config file must not hide the help that explains it
+    if options.mode != cli::Mode::Help {
+        config::load()?.apply(options);
+    }
+    Ok(())
+}
+
+fn run(options: &cli::Options) -> Result<Outcome, io::Error> {
+    match options.mode {
+        cli::Mode::Help => print!("{}", cli::USAGE),
+        cli::Mode::Doctor => doctor::run()?,
+        cli::Mode::Json | cli::Mode::JsonFlat | cli::Mode::Csv | cli::Mode::Prometheus => {
+            return run_json_mode(options)
+        }
+        cli::Mode::List => return Ok(run_list_mode(options)),
+        cli::Mode::Merge => return Ok(run_merge_mode(options)),
+        cli::Mode::Watch => return run_watch_mode(options),
+        cli::Mode::Serve { addr } => serve::serve(addr, || scrape_prometheus(options))?,
+        cli::Mode::RenderOnce { width, height } => render_once(options, width, height)?,
+        cli::Mode::Interactive => run_interactive_mode(options)?,
+    }
+
+    Ok(Outcome::Success)
+}
+
+/// Fresh port counters in Prometheus format, for one `--serve` scrape
+fn scrape_prometheus(options: &cli::Options) -> String {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    let mut output = types::IbtopOutput {
+        hostname: get_hostname(),
+        adapters: load_adapters(use_fake_data, options),
+        rates: Vec::new(),
+        history: Vec::new(),
+    };
+    if options.active_only {
+        export::retain_active(&mut output);
+    }
+    export::to_prometheus(&output)
+}
+
+/// Print the adapters and their port states; nothing is sampled
+fn run_list_mode(options: &cli::Options) -> Outcome {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    let adapters = load_adapters(use_fake_data, options);
+    print!("{}", export::to_list(&adapters));
+
+    Outcome::from_discovery(
+        adapters.len(),
+        !use_fake_data && discovery::permission_denied(),
+    )
+}
+
+/// Print the combined summary of the `--merge` snapshots
+fn run_merge_mode(options: &cli::Options) -> Outcome {
+    let (snapshots, skipped) = merge::load(&merge::expand(&options.merge));
+    for reason in &skipped {
+        eprintln!("ibtop: skipping {reason}");
+    }
+    print!("{}", merge::summary(&snapshots));
+
+    // Nothing to add up is reported like a node without adapters
+    Outcome::from_discovery(snapshots.len(), false)
+}
+
+/// Print a timestamped line per port every interval until a signal arrives
+///
+/// Output goes straight to stdout, no raw mode or alternate screen, so it
+/// can be `tee`d to a log; a closed pipe ends the watch like Ctrl-C.
+fn run_watch_mode(options: &cli::Options) -> Result<Outcome, io::Error> {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    let interval = poll_interval(options);
+    let rates = options
+        .divide_by
+        .and_then(ui::RateDisplay::per_consumer)
+        .unwrap_or_default();
+    let mut metrics = metrics::MetricsCollector::with_history_capacity(history_capacity(options));
+    metrics.set_smoothing(options.smoothing);
+
+    let adapters = load_adapters(use_fake_data, options);
+    let outcome = Outcome::from_discovery(
+        adapters.len(),
+        !use_fake_data && discovery::permission_denied(),
+    );
+    if adapters.is_empty() {
+        return Ok(outcome);
+    }
+    metrics.update(&adapters);
+
+    let shutdown = shutdown_flag()?;
+    let mut stdout = io::stdout();
+    while !shutdown.load(Ordering::Relaxed) {
+        // In short steps, so a signal ends the watch without waiting out
+        // a long interval
+        let started = Instant::now();
+        while !shutdown.load(Ordering::Relaxed) && started.elapsed() < interval {
+            std::thread::sleep(WATCH_SLEEP_STEP.min(interval.saturating_sub(started.elapsed())));
+        }
+        if shutdown.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let adapters = load_adapters(use_fake_data, options);
+        metrics.update(&adapters);
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+        let lines = export::to_watch_lines(&timestamp.to_string(), &adapters, &metrics, rates);
+        match stdout
+            .write_all(lines.as_bytes())
+            .and_then(|()| stdout.flush())
+        {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+            result => result?,
+        }
+    }
+
+    Ok(outcome)
+}
+
+fn run_json_mode(options: &cli::Options) -> Result<Outcome, io::Error> {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    // --with-history and --error-threshold need a window to sample, even
+    // without --export-avg
+    let window = options
+        .export_avg
+        .or((options.with_history || options.error_threshold.is_some())
+            .then_some(export::HISTORY_EXPORT_WINDOW));
+    let mut metrics = metrics::MetricsCollector::with_history_capacity(history_capacity(options));
+    let mut rates = window.map_or_else(Vec::new, |window| {
+        export::average_rates(
+            &mut metrics,
+            || load_adapters(use_fake_data, options),
+            window,
+            poll_interval(options),
+        )
+    });
+    if let Some(threshold) = options.error_threshold {
+        export::mark_error_alerts(&mut rates, threshold);
+    }
+    let adapters = load_adapters(use_fake_data, options);
+    // The exit status reflects discovery, not what filtering left over
+    let adapter_count = adapters.len();
+
+    let history = if options.with_history {
+        export::history_samples(&adapters, &metrics)
+    } else {
+        Vec::new()
+    };
+
+    let mut output = types::IbtopOutput {
         hostname: get_hostname(),
         adapters,
+        rates,
+        history,
     };
-    let json_output = serde_json::to_string_pretty(&output)?;
-    println!("{json_output}");
+    if options.active_only {
+        export::retain_active(&mut output);
+    }
+    match options.mode {
+        cli::Mode::JsonFlat => println!(
+            "{}",
+            serde_json::to_string_pretty(&export::flatten_ports(&output))?
+        ),
+        cli::Mode::Csv => print!("{}", export::to_csv(&output)),
+        cli::Mode::Prometheus => print!("{}", export::to_prometheus(&output)),
+        _ => println!("{}", serde_json::to_string_pretty(&output)?),
+    }
+
+    Ok(Outcome::from_discovery(
+        adapter_count,
+        !use_fake_data && discovery::permission_denied(),
+    ))
+}
+
+/// Capture a baseline from `adapters`, writing it out if `--baseline-save` was given
+///
+/// A failed write is reported like a failed export; the baseline still
+/// applies for the rest of the session.
+fn capture_baseline(
+    adapters: &[types::AdapterInfo],
+    hostname: &str,
+    options: &cli::Options,
+    app_state: &mut ui::AppState,
+) {
+    if let Some(path) = &options.baseline_save {
+        let snapshot = types::IbtopOutput {
+            hostname: hostname.to_string(),
+            adapters: adapters.to_vec(),
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+        if let Err(err) = baseline::save(path, &snapshot) {
+            app_state.notify(format!("Baseline save failed: {err}"));
+        }
+    }
+    app_state.baseline = Some(baseline::Baseline::capture(adapters));
+}
+
+/// Write the table as shown to `ibtop-<host>-<time>.txt` and report where
+fn export_table(
+    adapters: &[types::AdapterInfo],
+    metrics: &metrics::MetricsCollector,
+    hostname: &str,
+    app_state: &mut ui::AppState,
+) {
+    let path = format!(
+        "ibtop-{hostname}-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let text = ui::plain_text_table(adapters, metrics, hostname, app_state);
+    match std::fs::write(&path, text) {
+        Ok(()) => app_state.notify(format!("Table written to {path}")),
+        Err(err) => app_state.notify(format!("Export failed: {err}")),
+    }
+}
+
+/// Print one frame of the TUI at a fixed size (`--render-once`)
+///
+/// Counters are sampled twice, one refresh interval apart, so the frame
+/// shows rates rather than an idle table.
+fn render_once(options: &cli::Options, width: u16, height: u16) -> io::Result<()> {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    let mut metrics = metrics::MetricsCollector::with_history_capacity(history_capacity(options));
+    metrics.set_smoothing(options.smoothing);
+    metrics.update(&load_adapters(use_fake_data, options));
+    std::thread::sleep(poll_interval(options));
+    let adapters = load_adapters(use_fake_data, options);
+    metrics.update(&adapters);
 
+    let mut app_state = initial_app_state(options);
+    let text = ui::render_to_text(
+        &adapters,
+        &metrics,
+        &get_hostname(),
+        &mut app_state,
+        width,
+        height,
+    )?;
+    print!("{text}");
     Ok(())
 }
 
-fn run_interactive_mode() -> Result<(), io::Error> {
+fn run_interactive_mode(options: &cli::Options) -> Result<(), io::Error> {
+    // Fail before taking over the terminal
+    let baseline = options
+        .baseline
+        .as_deref()
+        .map(baseline::Baseline::load)
+        .transpose()?;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = run_app(&mut terminal);
+    let res = run_app(&mut terminal, options, baseline);
 
     disable_raw_mode()?;
     execute!(
@@ -84,68 +393,390 @@ fn run_interactive_mode() -> Result<(), io::Error> {
     Ok(())
 }
 
-fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
-    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
-    let mut metrics = metrics::MetricsCollector::new();
+/// Read what the "Raw", "`VPorts`" and "Info" tabs show beyond the polled
+/// counters
+///
+/// Raw counter files, which the "`VPorts`" tab also breaks down, are re-read
+/// on refresh or when the port changed; they span whole directories, so only
+/// while one of those tabs is on screen. Link details
+/// rarely change and may come from a slow module EEPROM, so they are read
+/// once per port.
+fn refresh_port_files(app_state: &mut ui::AppState, use_fake_data: bool, refresh_due: bool) {
+    if let Some(port) = app_state.link_details_wanted() {
+        let details = if use_fake_data {
+            discovery::LinkDetails::default()
+        } else {
+            discovery::read_link_details(&port.0, port.1)
+        };
+        app_state.set_link_details(port, details);
+    }
+
+    let Some(port) = app_state.raw_counters_port() else {
+        return;
+    };
+    if refresh_due || app_state.needs_raw_counters(&port) {
+        let entries = if use_fake_data {
+            Vec::new()
+        } else {
+            discovery::read_raw_counters(&port.0, port.1)
+        };
+        app_state.set_raw_counters(port, entries);
+    }
+}
+
+/// Longest gap between refreshes the main loop means to leave, which
+/// `--interval-jitter` measures late samples against
+///
+/// Watching for changes, quiet counters are only re-read at the fallback.
+fn expected_interval(app_state: &ui::AppState, watching: bool) -> Duration {
+    if watching {
+        app_state
+            .refresh_interval
+            .max(Duration::from_millis(ON_CHANGE_FALLBACK_INTERVAL_MS))
+    } else {
+        app_state.refresh_interval
+    }
+}
+
+/// How often counters are read, `--interval` or the default
+fn poll_interval(options: &cli::Options) -> Duration {
+    options
+        .interval
+        .unwrap_or(Duration::from_millis(METRICS_UPDATE_INTERVAL_MS))
+}
+
+/// Samples of history kept per port, covering `--history-seconds`
+fn history_capacity(options: &cli::Options) -> usize {
+    options
+        .history_window
+        .map_or(history::DEFAULT_HISTORY_SIZE, |window| {
+            history::capacity_for(window, poll_interval(options))
+        })
+}
+
+/// UI state configured by the command-line options
+fn initial_app_state(options: &cli::Options) -> ui::AppState {
     let mut app_state = ui::AppState::new();
+    app_state.refresh_interval = poll_interval(options);
+    app_state.split_sparkline = options.split_sparkline;
+    if let Some(key) = options.sort_key {
+        app_state.sort_key = key;
+    }
+    app_state.sparkline_clip = options.sparkline_clip;
+    app_state.show_sparkline = !options.no_sparkline;
+    app_state.wrap_navigation = options.wrap;
+    app_state.expectations = expectations::Expectations::new(options.expect.clone());
+    app_state.adaptive_refresh = options.adaptive_refresh;
+    app_state.time_unit = options.time_unit;
+    app_state.alert_total = options.alert_total;
+    app_state.title.clone_from(&options.title);
+    app_state.theme = options
+        .theme
+        .unwrap_or_else(|| ui::Theme::default_for(env::var_os("NO_COLOR")));
+    app_state.error_threshold = options.error_threshold;
+    if let Some(columns) = &options.columns {
+        app_state.columns.clone_from(columns);
+    }
+    if let Some(counters) = &options.error_counters {
+        app_state.error_counters.clone_from(counters);
+    }
+    app_state.counter_notation = options.counter_notation.unwrap_or_default();
+    if let Some(rates) = options.divide_by.and_then(ui::RateDisplay::per_consumer) {
+        app_state.rate_display = rates;
+    }
+    // Validated while parsing options
+    app_state.highlight = options
+        .highlight
+        .as_deref()
+        .and_then(|pattern| regex::Regex::new(pattern).ok());
+    app_state
+}
+
+/// Keys while an adapter filter is typed after `/`
+fn handle_filter_key(app_state: &mut ui::AppState, code: KeyCode) {
+    match code {
+        KeyCode::Char(c) => app_state.filter.push(c),
+        KeyCode::Backspace => {
+            app_state.filter.pop();
+        }
+        KeyCode::Enter => app_state.commit_filter(),
+        KeyCode::Esc => app_state.clear_filter(),
+        _ => {}
+    }
+}
+
+/// Keys that only change what the UI shows
+fn handle_view_key(app_state: &mut ui::AppState, code: KeyCode) {
+    match code {
+        // Navigation
+        KeyCode::Char('j') | KeyCode::Down => app_state.select_next(),
+        KeyCode::Char('k') | KeyCode::Up => app_state.select_prev(),
+
+        // Detail view
+        KeyCode::Enter => app_state.toggle_detail(),
+        KeyCode::Tab if app_state.detail_expanded => app_state.next_tab(),
+        KeyCode::BackTab if app_state.detail_expanded => app_state.prev_tab(),
+        KeyCode::Char('>') if app_state.detail_expanded => app_state.grow_detail(),
+        KeyCode::Char('<') if app_state.detail_expanded => app_state.shrink_detail(),
+
+        KeyCode::PageDown if app_state.detail_expanded => app_state.scroll_raw(10),
+        KeyCode::PageUp if app_state.detail_expanded => app_state.scroll_raw(-10),
+
+        KeyCode::Char('w') if app_state.detail_expanded => {
+            app_state.toggle_wall_clock();
+        }
+        KeyCode::Char('l') if app_state.detail_expanded => {
+            app_state.toggle_line_rate_band();
+        }
+        KeyCode::Char('C') if app_state.detail_expanded => {
+            app_state.toggle_carousel(Instant::now());
+        }
+        KeyCode::Char('t') if app_state.detail_expanded => app_state.toggle_elapsed(),
+        KeyCode::Char('i') if app_state.detail_expanded => app_state.toggle_interval_delta(),
+        KeyCode::Char('m') if app_state.detail_expanded => app_state.toggle_smooth_chart(),
+        KeyCode::Char('v') if app_state.detail_expanded => app_state.toggle_mirror_chart(),
+
+        // Sparkline mode
+        KeyCode::Char('x') => app_state.toggle_split_sparkline(),
+        KeyCode::Char('h') => app_state.toggle_sparkline(),
+
+        // Busiest adapters first
+        KeyCode::Char('o') => app_state.toggle_sort_adapters(),
+
+        // Collapse DOWN ports
+        KeyCode::Char('d') => app_state.toggle_collapse_down(),
+
+        // Port sort key, and the summary strip
+        KeyCode::Char('s') => app_state.cycle_sort_key(),
+        KeyCode::Char('S') => app_state.toggle_summary(),
+
+        // Alert history
+        KeyCode::Char('a') => app_state.toggle_alerts(),
+
+        // Freeze the display
+        KeyCode::Char(' ') => app_state.toggle_paused(),
+
+        // Adapter filter
+        KeyCode::Char('/') => app_state.start_filter(),
+
+        // Refresh interval
+        KeyCode::Char('+' | '=') => app_state.slower_refresh(),
+        KeyCode::Char('-') => app_state.faster_refresh(),
+
+        _ => {}
+    }
+}
+
+/// Set once SIGINT, SIGTERM or SIGHUP arrives
+///
+/// A signal then ends the session like `q`, so the history file is still
+/// saved and the terminal restored.
+fn shutdown_flag() -> io::Result<Arc<AtomicBool>> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM, SIGHUP] {
+        signal_hook::flag::register(signal, Arc::clone(&shutdown))?;
+    }
+    Ok(shutdown)
+}
+
+/// Metrics collector set up from the options, with history restored from
+/// `--history-file`; a missing or unreadable file starts empty
+fn new_metrics(options: &cli::Options) -> metrics::MetricsCollector {
+    let mut metrics = metrics::MetricsCollector::with_history_capacity(history_capacity(options));
+    metrics.set_smoothing(options.smoothing);
+    if let Some(history) = options
+        .history_file
+        .as_deref()
+        .and_then(|path| history::HistoryCollector::load(path, history_capacity(options)))
+    {
+        metrics.history = history;
+    }
+    metrics
+}
+
+fn run_app<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    options: &cli::Options,
+    baseline: Option<baseline::Baseline>,
+) -> io::Result<()> {
+    let use_fake_data = std::env::var("IBTOP_FAKE_DATA").is_ok();
+    let mut metrics = new_metrics(options);
+    let mut alert_monitor = alerts::AlertMonitor::with_total_limit(options.alert_total);
+    let mut governor = (!options.no_auto_degrade).then(degrade::FrameGovernor::default);
+    let mut app_state = initial_app_state(options);
+    app_state.baseline = baseline;
     let hostname = get_hostname();
 
     let ui_refresh_duration = Duration::from_millis(UI_REFRESH_INTERVAL_MS);
-    let metrics_update_interval = Duration::from_millis(METRICS_UPDATE_INTERVAL_MS);
+    let on_change_fallback = Duration::from_millis(ON_CHANGE_FALLBACK_INTERVAL_MS);
+    // `None` means plain polling every refresh interval
+    let mut watcher = if options.refresh_on_change && !use_fake_data {
+        watch::ChangeWatcher::new()
+    } else {
+        None
+    };
+    let mut change_pending = true;
+    // `r` refreshes on the next pass, even while paused
+    let mut force_refresh = false;
 
+    let shutdown = shutdown_flag()?;
     let mut last_metrics_update = Instant::now();
     let mut adapters = Vec::new();
+    // `--baseline-save` captures as soon as the first counters are read,
+    // unless `--baseline` already supplied one
+    let mut baseline_pending = options.baseline_save.is_some() && app_state.baseline.is_none();
 
-    loop {
+    while !shutdown.load(Ordering::Relaxed) {
         let now = Instant::now();
 
-        if now.duration_since(last_metrics_update) >= metrics_update_interval {
-            adapters = if use_fake_data {
-                simulation::generate_fake_adapters()
-            } else {
-                let real_adapters = discovery::discover_adapters();
-                if real_adapters.is_empty() && std::env::var("IBTOP_DEMO").is_ok() {
-                    simulation::generate_fake_adapters()
-                } else {
-                    real_adapters
-                }
-            };
+        let since_update = now.duration_since(last_metrics_update);
+        let refresh_due = match &mut watcher {
+            Some(watcher) => {
+                change_pending |= watcher.changed();
+                since_update >= app_state.refresh_interval
+                    && (change_pending || since_update >= on_change_fallback)
+            }
+            None => since_update >= app_state.refresh_interval,
+        };
 
+        // Paused, the last counters stay on screen until `r` or unpausing
+        let refresh = (refresh_due && !app_state.paused) || force_refresh;
+        if refresh {
+            force_refresh = false;
+            adapters = load_adapters(use_fake_data, options);
+
+            // The interval may have changed since the last refresh
+            metrics.set_interval_guard(
+                options
+                    .interval_jitter
+                    .then(|| expected_interval(&app_state, watcher.is_some())),
+            );
             metrics.update(&adapters);
+            app_state.adapt_refresh(&adapters, &metrics);
+            app_state.mark_updated(now);
+            alert_monitor.observe(&adapters, &metrics, &mut app_state.alerts);
             last_metrics_update = now;
+            change_pending = false;
+            if let Some(watcher) = &mut watcher {
+                watcher.watch(discovery::watch_paths(&adapters));
+            }
+
+            if baseline_pending {
+                capture_baseline(&adapters, &hostname, options, &mut app_state);
+                baseline_pending = false;
+            }
         }
 
+        app_state.advance_carousel(now);
+        refresh_port_files(&mut app_state, use_fake_data, refresh);
         terminal.draw(|f| ui::draw(f, &adapters, &metrics, &hostname, &mut app_state))?;
+        if let Some(decision) = governor
+            .as_mut()
+            .and_then(|governor| governor.record(now.elapsed(), ui_refresh_duration))
+        {
+            app_state.set_degraded(decision == degrade::Decision::Degrade);
+        }
 
         let timeout = ui_refresh_duration.saturating_sub(now.elapsed());
         if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 match key.code {
                     // Quit
-                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        return Ok(())
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                    code if app_state.filter_editing => handle_filter_key(&mut app_state, code),
+                    // Esc drops an applied filter before it quits
+                    KeyCode::Esc if !app_state.filter.is_empty() => app_state.clear_filter(),
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+
+                    // Export the visible table
+                    KeyCode::Char('e') => {
+                        export_table(&adapters, &metrics, &hostname, &mut app_state);
+                    }
+
+                    // Baseline
+                    KeyCode::Char('b') => {
+                        capture_baseline(&adapters, &hostname, options, &mut app_state);
                     }
+                    KeyCode::Char('B') => app_state.baseline = None,
 
-                    // Navigation
-                    KeyCode::Char('j') | KeyCode::Down => app_state.select_next(),
-                    KeyCode::Char('k') | KeyCode::Up => app_state.select_prev(),
+                    // Zero (or re-zero) the trip meter
+                    KeyCode::Char('0') => metrics.set_reference(),
 
-                    // Detail view
-                    KeyCode::Enter => app_state.toggle_detail(),
-                    KeyCode::Tab if app_state.detail_expanded => app_state.next_tab(),
-                    KeyCode::BackTab if app_state.detail_expanded => app_state.prev_tab(),
+                    // Rates and history from now on, as if just started
+                    KeyCode::Char('z') => {
+                        metrics.reset();
+                        force_refresh = true;
+                        app_state.notify("Rates reset; measuring from now");
+                    }
 
                     // Force refresh
-                    KeyCode::Char('r') => {
-                        last_metrics_update = Instant::now()
-                            .checked_sub(metrics_update_interval)
-                            .unwrap_or_else(Instant::now);
-                    }
+                    KeyCode::Char('r') => force_refresh = true,
 
-                    _ => {}
+                    code => handle_view_key(&mut app_state, code),
                 }
             }
         }
     }
+
+    if let Some(path) = &options.history_file {
+        metrics.history.save(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_from_discovery() {
+        assert_eq!(Outcome::from_discovery(2, false), Outcome::Success);
+        // Adapters found through a fallback still count as success
+        assert_eq!(Outcome::from_discovery(2, true), Outcome::Success);
+        assert_eq!(Outcome::from_discovery(0, false), Outcome::NoAdapters);
+        assert_eq!(Outcome::from_discovery(0, true), Outcome::PermissionDenied);
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let outcomes = [
+            Outcome::Success,
+            Outcome::IoError,
+            Outcome::UsageError,
+            Outcome::NoAdapters,
+            Outcome::PermissionDenied,
+        ];
+        let mut codes: Vec<u8> = outcomes.iter().map(|o| o.exit_code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), outcomes.len());
+        assert_eq!(Outcome::Success.exit_code(), 0);
+    }
+
+    #[test]
+    fn test_failed_baseline_save_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cli::Options {
+            baseline_save: Some(dir.path().join("missing").join("baseline.json")),
+            ..cli::Options::default()
+        };
+        let adapters = simulation::generate_fake_adapters();
+        let mut app_state = ui::AppState::new();
+
+        capture_baseline(&adapters, "node01", &options, &mut app_state);
+        assert!(app_state.baseline.is_some());
+        let metrics = metrics::MetricsCollector::new();
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(120, 20)).unwrap();
+        terminal
+            .draw(|frame| ui::draw(frame, &adapters, &metrics, "node01", &mut app_state))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+        assert!(text.contains("Baseline save failed"), "{text}");
+    }
 }
@@ -0,0 +1,285 @@
+//! Combine `--json` snapshots from several nodes (`--merge`)
+//!
+//! Each file is one node's `ibtop --json` output. The summary adds up the
+//! lifetime RX/TX byte counters per HCA model and over every node, so a job
+//! spread across a cluster can be read as one total.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::types::IbtopOutput;
+use crate::ui::format_bytes;
+
+/// Shown for adapters whose snapshot has no `hca_type`
+const UNKNOWN_TYPE: &str = "unknown";
+
+/// Expand the `--merge` arguments into file paths
+///
+/// Arguments are usually expanded by the shell already; one that still holds
+/// `*` or `?` in its file name (quoted, say) is matched against its directory
+/// here. A pattern matching nothing is kept as is, so it is reported as
+/// unreadable rather than silently dropped.
+pub fn expand(patterns: &[String]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let name = path.file_name().map(|n| n.to_string_lossy());
+        let Some(name) = name.filter(|n| n.contains(['*', '?'])) else {
+            paths.push(path.to_path_buf());
+            continue;
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut matched: Vec<PathBuf> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| wildcard_matches(&name, &entry.file_name().to_string_lossy()))
+            .map(|entry| dir.join(entry.file_name()))
+            .collect();
+        if matched.is_empty() {
+            paths.push(path.to_path_buf());
+        } else {
+            matched.sort();
+            paths.append(&mut matched);
+        }
+    }
+    paths
+}
+
+/// Whether `name` matches a shell-style pattern with `*` and `?`
+fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let mut expr = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => expr.push_str(".*"),
+            '?' => expr.push('.'),
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+    Regex::new(&expr).is_ok_and(|re| re.is_match(name))
+}
+
+/// Read every snapshot that parses; the others are returned with the reason
+pub fn load(paths: &[PathBuf]) -> (Vec<IbtopOutput>, Vec<String>) {
+    let mut snapshots = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()));
+        match parsed {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(err) => skipped.push(format!("{}: {err}", path.display())),
+        }
+    }
+    (snapshots, skipped)
+}
+
+/// Byte totals over a group of adapters
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Totals {
+    adapters: usize,
+    ports: usize,
+    rx_bytes: u64,
+    tx_bytes: u64,
+}
+
+impl Totals {
+    fn add(&mut self, other: Totals) {
+        self.adapters += other.adapters;
+        self.ports += other.ports;
+        self.rx_bytes = self.rx_bytes.saturating_add(other.rx_bytes);
+        self.tx_bytes = self.tx_bytes.saturating_add(other.tx_bytes);
+    }
+}
+
+/// Totals per HCA model, ordered by model name
+fn totals_by_type(snapshots: &[IbtopOutput]) -> BTreeMap<&str, Totals> {
+    let mut by_type: BTreeMap<&str, Totals> = BTreeMap::new();
+    for adapter in snapshots.iter().flat_map(|s| &s.adapters) {
+        let hca_type = adapter.hca_type.as_deref().unwrap_or(UNKNOWN_TYPE);
+        by_type.entry(hca_type).or_default().add(Totals {
+            adapters: 1,
+            ports: adapter.ports.len(),
+            rx_bytes: adapter
+                .ports
+                .iter()
+                .map(|p| p.counters.rx_bytes)
+                .fold(0, u64::saturating_add),
+            tx_bytes: adapter
+                .ports
+                .iter()
+                .map(|p| p.counters.tx_bytes)
+                .fold(0, u64::saturating_add),
+        });
+    }
+    by_type
+}
+
+/// The combined summary printed by `--merge`
+pub fn summary(snapshots: &[IbtopOutput]) -> String {
+    let by_type = totals_by_type(snapshots);
+    let mut total = Totals::default();
+    for totals in by_type.values() {
+        total.add(*totals);
+    }
+
+    let width = by_type
+        .keys()
+        .map(|t| t.len())
+        .chain(["Type".len(), "Total".len()])
+        .max()
+        .unwrap_or(0);
+    let row = |label: &str, t: &Totals| {
+        format!(
+            "{label:<width$}  {:>8}  {:>5}  {:>10}  {:>10}",
+            t.adapters,
+            t.ports,
+            format_bytes(t.rx_bytes),
+            format_bytes(t.tx_bytes)
+        )
+    };
+
+    let mut text = String::new();
+    let _ = writeln!(text, "{} nodes", snapshots.len());
+    let _ = writeln!(
+        text,
+        "{:<width$}  {:>8}  {:>5}  {:>10}  {:>10}",
+        "Type", "Adapters", "Ports", "RX", "TX"
+    );
+    for (hca_type, totals) in &by_type {
+        let _ = writeln!(text, "{}", row(hca_type, totals));
+    }
+    let _ = writeln!(text, "{}", row("Total", &total));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AdapterInfo, PortCounters, PortInfo};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn snapshot(hostname: &str, adapters: &[(&str, Option<&str>, u64, u64)]) -> String {
+        let adapters = adapters
+            .iter()
+            .map(|&(name, hca_type, rx_bytes, tx_bytes)| AdapterInfo {
+                name: name.to_string(),
+                hca_type: hca_type.map(str::to_string),
+                ports: vec![PortInfo {
+                    port_number: 1,
+                    counters: PortCounters {
+                        rx_bytes,
+                        tx_bytes,
+                        ..PortCounters::default()
+                    },
+                    ..PortInfo::default()
+                }],
+                ..AdapterInfo::default()
+            })
+            .collect();
+        serde_json::to_string(&IbtopOutput {
+            hostname: hostname.to_string(),
+            adapters,
+            rates: Vec::new(),
+            history: Vec::new(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_merge_sums_per_type_and_skips_bad_files() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("node01.json"),
+            snapshot("node01", &[("mlx5_0", Some("MT4129"), 1 << 30, 2 << 30)]),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("node02.json"),
+            snapshot(
+                "node02",
+                &[
+                    ("mlx5_0", Some("MT4129"), 3 << 30, 4 << 30),
+                    ("mlx4_0", None, 1024, 2048),
+                ],
+            ),
+        )
+        .unwrap();
+        fs::write(dir.path().join("node03.json"), "not json").unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let pattern = dir.path().join("node*.json").display().to_string();
+        let paths = expand(&[pattern]);
+        assert_eq!(paths.len(), 3);
+
+        let (snapshots, skipped) = load(&paths);
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].contains("node03.json"));
+
+        let by_type = totals_by_type(&snapshots);
+        assert_eq!(
+            by_type["MT4129"],
+            Totals {
+                adapters: 2,
+                ports: 2,
+                rx_bytes: 4 << 30,
+                tx_bytes: 6 << 30,
+            }
+        );
+        assert_eq!(by_type[UNKNOWN_TYPE].rx_bytes, 1024);
+
+        let text = summary(&snapshots);
+        assert!(text.starts_with("2 nodes\n"));
+        assert!(text
+            .lines()
+            .any(|l| l.starts_with("MT4129") && l.contains("4.0GB")));
+        assert!(text.lines().last().unwrap().starts_with("Total"));
+    }
+
+    #[test]
+    fn test_port_byte_sums_saturate() {
+        let port = |rx_bytes| PortInfo {
+            counters: PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            },
+            ..PortInfo::default()
+        };
+        let output = IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![AdapterInfo {
+                ports: vec![port(u64::MAX - 1), port(10)],
+                ..AdapterInfo::default()
+            }],
+            rates: Vec::new(),
+            history: Vec::new(),
+        };
+        let by_type = totals_by_type(std::slice::from_ref(&output));
+        assert_eq!(by_type[UNKNOWN_TYPE].rx_bytes, u64::MAX);
+    }
+
+    #[test]
+    fn test_expand_keeps_plain_and_unmatched_paths() {
+        let args = vec!["a.json".to_string(), "/nonexistent/*.json".to_string()];
+        assert_eq!(
+            expand(&args),
+            vec![
+                PathBuf::from("a.json"),
+                PathBuf::from("/nonexistent/*.json")
+            ]
+        );
+        assert!(wildcard_matches("node??.json", "node01.json"));
+        assert!(!wildcard_matches("node?.json", "node01.json"));
+        assert!(!wildcard_matches("n.json", "nxjson"));
+    }
+}
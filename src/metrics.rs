@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
-use crate::history::HistoryCollector;
+use crate::discovery::MLX5_DATA_MULTIPLIER;
+use crate::history::{port_key, HistoryCollector};
 use crate::types::{AdapterInfo, PortCounters};
 
 #[derive(Debug, Clone)]
@@ -10,7 +11,63 @@ pub struct PortMetrics {
     pub tx_bytes_per_sec: f64,
     pub rx_packets_per_sec: f64,
     pub tx_packets_per_sec: f64,
+    /// Physical receive errors per second
     pub error_rate: f64,
+    /// Congestion discards on transmit per second
+    pub discard_rate: f64,
+    /// `port_xmit_wait` ticks per second spent waiting for credits
+    pub xmit_wait_rate: f64,
+}
+
+impl PortMetrics {
+    /// Exponentially blend a new sample into `self`, weighting the new sample by `factor`
+    fn blend(&self, new: &PortMetrics, factor: f64) -> PortMetrics {
+        let mix = |old: f64, new: f64| factor * new + (1.0 - factor) * old;
+        PortMetrics {
+            rx_bytes_per_sec: mix(self.rx_bytes_per_sec, new.rx_bytes_per_sec),
+            tx_bytes_per_sec: mix(self.tx_bytes_per_sec, new.tx_bytes_per_sec),
+            rx_packets_per_sec: mix(self.rx_packets_per_sec, new.rx_packets_per_sec),
+            tx_packets_per_sec: mix(self.tx_packets_per_sec, new.tx_packets_per_sec),
+            error_rate: mix(self.error_rate, new.error_rate),
+            discard_rate: mix(self.discard_rate, new.discard_rate),
+            xmit_wait_rate: mix(self.xmit_wait_rate, new.xmit_wait_rate),
+        }
+    }
+
+    /// Average size of received packets in bytes, `None` without packets
+    pub fn avg_rx_packet_size(&self) -> Option<f64> {
+        avg_packet_size(self.rx_bytes_per_sec, self.rx_packets_per_sec)
+    }
+
+    /// Average size of transmitted packets in bytes, `None` without packets
+    pub fn avg_tx_packet_size(&self) -> Option<f64> {
+        avg_packet_size(self.tx_bytes_per_sec, self.tx_packets_per_sec)
+    }
+
+    /// Mean of several samples (all zero for an empty slice)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average(samples: &[PortMetrics]) -> PortMetrics {
+        if samples.is_empty() {
+            return PortMetrics::default();
+        }
+        let n = samples.len() as f64;
+        let mean = |field: fn(&PortMetrics) -> f64| samples.iter().map(field).sum::<f64>() / n;
+        PortMetrics {
+            rx_bytes_per_sec: mean(|m| m.rx_bytes_per_sec),
+            tx_bytes_per_sec: mean(|m| m.tx_bytes_per_sec),
+            rx_packets_per_sec: mean(|m| m.rx_packets_per_sec),
+            tx_packets_per_sec: mean(|m| m.tx_packets_per_sec),
+            error_rate: mean(|m| m.error_rate),
+            discard_rate: mean(|m| m.discard_rate),
+            xmit_wait_rate: mean(|m| m.xmit_wait_rate),
+        }
+    }
+}
+
+/// Bytes per packet; rates below one packet per second are too few to
+/// average (and zero would divide by zero)
+fn avg_packet_size(bytes_per_sec: f64, packets_per_sec: f64) -> Option<f64> {
+    (packets_per_sec >= 1.0).then(|| bytes_per_sec / packets_per_sec)
 }
 
 impl Default for PortMetrics {
@@ -21,33 +78,93 @@ impl Default for PortMetrics {
             rx_packets_per_sec: 0.0,
             tx_packets_per_sec: 0.0,
             error_rate: 0.0,
+            discard_rate: 0.0,
+            xmit_wait_rate: 0.0,
         }
     }
 }
 
+/// What a port did since the trip meter was zeroed
+#[derive(Debug, Clone)]
+pub struct TripMeter {
+    pub counters: PortCounters,
+    /// Average rates over the whole trip
+    pub rates: PortMetrics,
+    pub elapsed: Duration,
+}
+
+/// With the interval guard on, samples taken more than this many target
+/// intervals after the previous one are discarded
+pub const MAX_DELTA_FACTOR: u32 = 3;
+
 #[derive(Debug)]
 pub struct MetricsCollector {
     previous_counters: HashMap<String, PortCounters>,
     current_metrics: HashMap<String, PortMetrics>,
+    /// Counts accrued between the last two samples
+    interval_deltas: HashMap<String, PortCounters>,
     last_collection: Option<Instant>,
+    /// Weight of the newest sample when smoothing (None = raw rates)
+    smoothing: Option<f64>,
+    /// Target interval between samples, when stalled samples are discarded
+    interval_guard: Option<Duration>,
+    /// Counters when the trip meter was zeroed, and when that was
+    reference_counters: HashMap<String, PortCounters>,
+    reference_time: Option<Instant>,
     pub history: HistoryCollector,
 }
 
 impl MetricsCollector {
+    #[allow(dead_code)] // The binary sizes history from the options
     pub fn new() -> Self {
+        Self::with_history_capacity(crate::history::DEFAULT_HISTORY_SIZE)
+    }
+
+    /// A collector keeping `capacity` samples of history per port
+    pub fn with_history_capacity(capacity: usize) -> Self {
         Self {
             previous_counters: HashMap::new(),
             current_metrics: HashMap::new(),
+            interval_deltas: HashMap::new(),
             last_collection: None,
-            history: HistoryCollector::new(),
+            smoothing: None,
+            interval_guard: None,
+            reference_counters: HashMap::new(),
+            reference_time: None,
+            history: HistoryCollector::with_capacity(capacity),
         }
     }
 
+    /// Enable exponential rate smoothing; `factor` in (0, 1] is the weight of the
+    /// newest sample, so 1.0 (or `None`) reports raw rates
+    pub fn set_smoothing(&mut self, factor: Option<f64>) {
+        self.smoothing = factor;
+    }
+
+    /// Discard samples taken more than `MAX_DELTA_FACTOR` times `target`
+    /// after the previous one (`--interval-jitter`); `None` keeps them all
+    ///
+    /// A stall of a few seconds would otherwise spread whatever moved during
+    /// it into one misleadingly flat rate. A discarded sample still becomes
+    /// the base for the next one, and the last good rates stay shown.
+    pub fn set_interval_guard(&mut self, target: Option<Duration>) {
+        self.interval_guard = target;
+    }
+
+    /// Whether a sample `time_delta` after the previous one is discarded
+    fn stalled(&self, time_delta: Duration) -> bool {
+        self.last_collection.is_some()
+            && self
+                .interval_guard
+                .is_some_and(|target| time_delta > target * MAX_DELTA_FACTOR)
+    }
+
     pub fn update(&mut self, adapters: &[AdapterInfo]) {
         let now = Instant::now();
         let time_delta = self
             .last_collection
             .map_or(Duration::from_secs(1), |last| now.duration_since(last));
+        let stalled = self.stalled(time_delta);
 
         // Track current port keys to clean up stale entries
         let mut current_port_keys = std::collections::HashSet::new();
@@ -55,12 +172,19 @@ impl MetricsCollector {
 
         for adapter in adapters {
             for port in &adapter.ports {
-                let port_key = format!("{}:{}", adapter.name, port.port_number);
-                current_port_keys.insert(port_key.clone());
+                let port_key = port_key(&adapter.name, port.port_number);
+                // A second port under the same key would mix its counters
+                // into the first one's rates; the first one keeps the key
+                if !current_port_keys.insert(port_key.clone()) {
+                    continue;
+                }
                 active_ports.push((adapter.name.clone(), port.port_number));
 
-                if let Some(prev_counters) = self.previous_counters.get(&port_key) {
-                    let metrics = Self::calculate_rates(prev_counters, &port.counters, time_delta);
+                let prev_counters = self.previous_counters.get(&port_key);
+                if let Some(prev_counters) = prev_counters.filter(|_| !stalled) {
+                    let raw = Self::calculate_rates(prev_counters, &port.counters, time_delta);
+                    let metrics =
+                        Self::smooth(self.current_metrics.get(&port_key), raw, self.smoothing);
 
                     // Record to history
                     self.history.record(
@@ -71,9 +195,14 @@ impl MetricsCollector {
                         metrics.rx_packets_per_sec,
                         metrics.tx_packets_per_sec,
                         metrics.error_rate,
+                        time_delta,
                     );
 
                     self.current_metrics.insert(port_key.clone(), metrics);
+                    self.interval_deltas.insert(
+                        port_key.clone(),
+                        counters_between(prev_counters, &port.counters),
+                    );
                 }
 
                 // Store current counters for next calculation
@@ -87,11 +216,27 @@ impl MetricsCollector {
             .retain(|key, _| current_port_keys.contains(key));
         self.current_metrics
             .retain(|key, _| current_port_keys.contains(key));
+        self.interval_deltas
+            .retain(|key, _| current_port_keys.contains(key));
+        self.reference_counters
+            .retain(|key, _| current_port_keys.contains(key));
         self.history.retain_ports(&active_ports);
 
         self.last_collection = Some(now);
     }
 
+    /// Blend a freshly computed rate with the previous one, if smoothing is enabled
+    fn smooth(
+        previous: Option<&PortMetrics>,
+        raw: PortMetrics,
+        smoothing: Option<f64>,
+    ) -> PortMetrics {
+        match (previous, smoothing) {
+            (Some(prev), Some(factor)) => prev.blend(&raw, factor),
+            _ => raw,
+        }
+    }
+
     #[allow(clippy::cast_precision_loss)]
     fn calculate_rates(
         prev: &PortCounters,
@@ -104,26 +249,68 @@ impl MetricsCollector {
             return PortMetrics::default();
         }
 
-        let rx_bytes_delta = current.rx_bytes.saturating_sub(prev.rx_bytes);
-        let tx_bytes_delta = current.tx_bytes.saturating_sub(prev.tx_bytes);
-        let rx_packets_delta = current.rx_packets.saturating_sub(prev.rx_packets);
-        let tx_packets_delta = current.tx_packets.saturating_sub(prev.tx_packets);
-
-        let prev_errors = prev.rx_errors + prev.tx_errors;
-        let current_errors = current.rx_errors + current.tx_errors;
-        let error_delta = current_errors.saturating_sub(prev_errors);
-
+        let delta = counters_between(prev, current);
+        // Physical errors and congestion discards point at different problems
         PortMetrics {
-            rx_bytes_per_sec: rx_bytes_delta as f64 / delta_seconds,
-            tx_bytes_per_sec: tx_bytes_delta as f64 / delta_seconds,
-            rx_packets_per_sec: rx_packets_delta as f64 / delta_seconds,
-            tx_packets_per_sec: tx_packets_delta as f64 / delta_seconds,
-            error_rate: error_delta as f64 / delta_seconds,
+            rx_bytes_per_sec: delta.rx_bytes as f64 / delta_seconds,
+            tx_bytes_per_sec: delta.tx_bytes as f64 / delta_seconds,
+            rx_packets_per_sec: delta.rx_packets as f64 / delta_seconds,
+            tx_packets_per_sec: delta.tx_packets as f64 / delta_seconds,
+            error_rate: delta.rx_errors as f64 / delta_seconds,
+            discard_rate: delta.tx_discards as f64 / delta_seconds,
+            xmit_wait_rate: delta.xmit_wait as f64 / delta_seconds,
         }
     }
 
+    /// Forget all counters, rates and history, so the next update starts
+    /// measuring afresh as if ibtop had just been launched
+    ///
+    /// The trip meter keeps its reference; it is the one thing asked to
+    /// count from an earlier moment.
+    pub fn reset(&mut self) {
+        self.previous_counters.clear();
+        self.current_metrics.clear();
+        self.interval_deltas.clear();
+        self.history.clear();
+        self.last_collection = None;
+    }
+
+    /// Zero the trip meter at the latest counters; calling it again restarts the trip
+    pub fn set_reference(&mut self) {
+        self.reference_counters = self.previous_counters.clone();
+        self.reference_time = self.last_collection;
+    }
+
+    /// Time covered by the trip meter, `None` while it has not been zeroed
+    pub fn reference_age(&self) -> Option<Duration> {
+        let start = self.reference_time?;
+        Some(self.last_collection?.duration_since(start))
+    }
+
+    /// Counters and average rates of a port since the trip meter was zeroed
+    ///
+    /// `None` until it is zeroed, and for ports that appeared afterwards.
+    pub fn trip(&self, adapter_name: &str, port_number: u16) -> Option<TripMeter> {
+        let port_key = port_key(adapter_name, port_number);
+        let reference = self.reference_counters.get(&port_key)?;
+        let current = self.previous_counters.get(&port_key)?;
+        let elapsed = self.reference_age()?;
+
+        Some(TripMeter {
+            counters: counters_between(reference, current),
+            rates: Self::calculate_rates(reference, current, elapsed),
+            elapsed,
+        })
+    }
+
+    /// Counts accrued between the last two samples, not divided by time
+    pub fn interval_delta(&self, adapter_name: &str, port_number: u16) -> Option<&PortCounters> {
+        self.interval_deltas
+            .get(&port_key(adapter_name, port_number))
+    }
+
     pub fn get_metrics(&self, adapter_name: &str, port_number: u16) -> Option<&PortMetrics> {
-        let port_key = format!("{adapter_name}:{port_number}");
+        let port_key = port_key(adapter_name, port_number);
         self.current_metrics.get(&port_key)
     }
 
@@ -136,3 +323,394 @@ impl MetricsCollector {
         self.history.get(adapter_name, port_number)
     }
 }
+
+/// Where a 32-bit counter wraps
+const COUNTER32_WRAP: u64 = 1 << 32;
+
+/// Where a 32-bit data counter wraps once scaled from words to bytes
+const DATA32_WRAP: u64 = COUNTER32_WRAP * MLX5_DATA_MULTIPLIER;
+
+/// How close below the 32-bit limit a counter must have been for a drop to
+/// count as a wrap, as a fraction of the range: more than one interval's
+/// worth of traffic is not plausible
+const WRAP_WINDOW_DIVISOR: u64 = 16;
+
+/// `later - earlier` for a counter that may be 32 bits wide
+///
+/// A drop from the top sixteenth of the 32-bit range is taken as the
+/// counter wrapping, and counts what it moved on both sides of the wrap.
+/// Any other drop (a reset, or a 64-bit counter going backwards) counts
+/// zero.
+fn counter_delta(earlier: u64, later: u64, wrap: u64) -> u64 {
+    if later >= earlier {
+        later - earlier
+    } else if earlier < wrap && wrap - earlier <= wrap / WRAP_WINDOW_DIVISOR {
+        (wrap - 1 - earlier) + later + 1
+    } else {
+        0
+    }
+}
+
+/// Field-wise `later - earlier`, across 32-bit wraps; a counter that was
+/// reset counts zero
+fn counters_between(earlier: &PortCounters, later: &PortCounters) -> PortCounters {
+    let delta = |field: fn(&PortCounters) -> u64| {
+        counter_delta(field(earlier), field(later), COUNTER32_WRAP)
+    };
+    let data_delta =
+        |field: fn(&PortCounters) -> u64| counter_delta(field(earlier), field(later), DATA32_WRAP);
+    PortCounters {
+        rx_bytes: data_delta(|c| c.rx_bytes),
+        tx_bytes: data_delta(|c| c.tx_bytes),
+        rx_packets: delta(|c| c.rx_packets),
+        tx_packets: delta(|c| c.tx_packets),
+        rx_errors: delta(|c| c.rx_errors),
+        tx_discards: delta(|c| c.tx_discards),
+        rx_dropped: delta(|c| c.rx_dropped),
+        link_error_recovery: delta(|c| c.link_error_recovery),
+        link_downed: delta(|c| c.link_downed),
+        symbol_errors: delta(|c| c.symbol_errors),
+        local_link_integrity_errors: delta(|c| c.local_link_integrity_errors),
+        xmit_wait: delta(|c| c.xmit_wait),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adapter `mlx5_0` with one port reading `counters`
+    fn adapter_with(counters: PortCounters) -> AdapterInfo {
+        AdapterInfo {
+            name: "mlx5_0".to_string(),
+            ports: vec![crate::types::PortInfo {
+                port_number: 1,
+                counters,
+                ..crate::types::PortInfo::default()
+            }],
+            ..AdapterInfo::default()
+        }
+    }
+
+    fn rx_sample(rate: f64) -> PortMetrics {
+        PortMetrics {
+            rx_bytes_per_sec: rate,
+            ..PortMetrics::default()
+        }
+    }
+
+    fn run(samples: &[f64], smoothing: Option<f64>) -> Vec<f64> {
+        let mut previous: Option<PortMetrics> = None;
+        samples
+            .iter()
+            .map(|&rate| {
+                let metrics =
+                    MetricsCollector::smooth(previous.as_ref(), rx_sample(rate), smoothing);
+                let value = metrics.rx_bytes_per_sec;
+                previous = Some(metrics);
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_smoothing_reduces_sawtooth() {
+        // A stretched delta produces a low sample followed by a high one
+        let noisy = [100.0, 100.0, 50.0, 150.0, 100.0, 40.0, 160.0, 100.0];
+
+        let raw = run(&noisy, None);
+        assert_eq!(raw, noisy);
+
+        let smoothed = run(&noisy, Some(0.3));
+        let spread = |v: &[f64]| {
+            v.iter().copied().fold(f64::MIN, f64::max) - v.iter().copied().fold(f64::MAX, f64::min)
+        };
+        assert!(spread(&smoothed) < spread(&raw) / 2.0);
+
+        // First sample has nothing to blend with
+        assert!((smoothed[0] - 100.0).abs() < f64::EPSILON);
+        // 0.3 * 50 + 0.7 * 100
+        assert!((smoothed[2] - 85.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_average_samples() {
+        let samples = [rx_sample(100.0), rx_sample(300.0), rx_sample(200.0)];
+        let avg = PortMetrics::average(&samples);
+        assert!((avg.rx_bytes_per_sec - 200.0).abs() < 1e-9);
+        assert!(avg.tx_bytes_per_sec.abs() < f64::EPSILON);
+
+        let empty = PortMetrics::average(&[]);
+        assert!(empty.rx_bytes_per_sec.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_errors_and_discards_are_separate_rates() {
+        let prev = PortCounters::default();
+        let current = PortCounters {
+            rx_errors: 2,
+            tx_discards: 10,
+            xmit_wait: 3_000,
+            ..PortCounters::default()
+        };
+        let rates = MetricsCollector::calculate_rates(&prev, &current, Duration::from_secs(2));
+        assert!((rates.error_rate - 1.0).abs() < 1e-9);
+        assert!((rates.discard_rate - 5.0).abs() < 1e-9);
+        assert!((rates.xmit_wait_rate - 1_500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_32_bit_wrap_counts_across_the_wrap() {
+        let max32 = u64::from(u32::MAX);
+        let prev = PortCounters {
+            rx_packets: max32 - 99,
+            symbol_errors: max32,
+            rx_bytes: DATA32_WRAP - 400,
+            // Already past 32 bits: a 64-bit counter
+            tx_packets: 10 * COUNTER32_WRAP,
+            ..PortCounters::default()
+        };
+        let current = PortCounters {
+            rx_packets: 50,
+            symbol_errors: 2,
+            rx_bytes: 600,
+            tx_packets: 10 * COUNTER32_WRAP + 1_000,
+            ..PortCounters::default()
+        };
+        let rates = MetricsCollector::calculate_rates(&prev, &current, Duration::from_secs(1));
+        // 100 up to the wrap, then 50 after it
+        assert!((rates.rx_packets_per_sec - 150.0).abs() < 1e-9);
+        assert!((rates.rx_bytes_per_sec - 1_000.0).abs() < 1e-9);
+        assert!((rates.tx_packets_per_sec - 1_000.0).abs() < 1e-9);
+        assert_eq!(counters_between(&prev, &current).symbol_errors, 3);
+
+        // A drop from low values, or of a 64-bit counter, is a reset
+        assert_eq!(counter_delta(5_000, 10, COUNTER32_WRAP), 0);
+        assert_eq!(counter_delta(20 * COUNTER32_WRAP, 10, COUNTER32_WRAP), 0);
+        assert_eq!(counter_delta(u64::MAX - 5, 10, COUNTER32_WRAP), 0);
+        // A reset from the upper half, not near the limit, is no wrap
+        assert_eq!(counter_delta(3 * COUNTER32_WRAP / 4, 10, COUNTER32_WRAP), 0);
+        assert_eq!(counter_delta(3 * DATA32_WRAP / 4, 10, DATA32_WRAP), 0);
+    }
+
+    #[test]
+    fn test_avg_packet_size() {
+        let m = PortMetrics {
+            rx_bytes_per_sec: 4_096_000.0,
+            rx_packets_per_sec: 1_000.0,
+            tx_bytes_per_sec: 500.0,
+            tx_packets_per_sec: 0.0,
+            ..PortMetrics::default()
+        };
+        assert_eq!(m.avg_rx_packet_size(), Some(4_096.0));
+        assert_eq!(m.avg_tx_packet_size(), None);
+        assert_eq!(PortMetrics::default().avg_rx_packet_size(), None);
+    }
+
+    #[test]
+    fn test_smoothing_factor_one_is_raw() {
+        let noisy = [10.0, 90.0, 20.0];
+        assert_eq!(run(&noisy, Some(1.0)), noisy);
+    }
+
+    #[test]
+    fn test_same_named_adapters_keep_distinct_histories() {
+        let adapter = |rx_bytes| {
+            adapter_with(PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            })
+        };
+        let sample = |first, second| {
+            let mut adapters = vec![adapter(first), adapter(second)];
+            let renamed = crate::discovery::disambiguate_names(&mut adapters);
+            assert_eq!(renamed, vec!["mlx5_0#2"]);
+            adapters
+        };
+
+        let mut collector = MetricsCollector::new();
+        collector.update(&sample(1_000, 50_000));
+        collector.update(&sample(2_000, 90_000));
+
+        let rx = |name| {
+            collector
+                .get_history(name, 1)
+                .unwrap()
+                .rx_bytes_per_sec
+                .to_vec()
+        };
+        let (first, second) = (rx("mlx5_0"), rx("mlx5_0#2"));
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert!(first[0] < second[0]);
+
+        // Left undisambiguated, the second adapter is ignored rather than
+        // mixed in; mixing would compare 2_000 against 50_000 and read zero
+        let mut collector = MetricsCollector::new();
+        collector.update(&[adapter(1_000), adapter(50_000)]);
+        collector.update(&[adapter(2_000), adapter(90_000)]);
+        assert!(collector.get_metrics("mlx5_0", 1).unwrap().rx_bytes_per_sec > 0.0);
+    }
+
+    #[test]
+    fn test_interval_delta_is_not_divided_by_time() {
+        let adapter = |rx_bytes, tx_packets| {
+            adapter_with(PortCounters {
+                rx_bytes,
+                tx_packets,
+                ..PortCounters::default()
+            })
+        };
+
+        let mut collector = MetricsCollector::new();
+        collector.update(&[adapter(1_000, 10)]);
+        assert!(collector.interval_delta("mlx5_0", 1).is_none());
+
+        // Whatever the time between samples, the delta is a plain count
+        collector.last_collection = collector
+            .last_collection
+            .and_then(|last| last.checked_sub(Duration::from_secs(4)));
+        collector.update(&[adapter(9_000, 30)]);
+        let delta = collector.interval_delta("mlx5_0", 1).unwrap();
+        assert_eq!(delta.rx_bytes, 8_000);
+        assert_eq!(delta.tx_packets, 20);
+        assert!(
+            (collector.get_metrics("mlx5_0", 1).unwrap().rx_bytes_per_sec - 2_000.0).abs() < 1.0
+        );
+
+        // Only the latest interval counts, and a reset reads as zero
+        collector.update(&[adapter(8_000, 35)]);
+        let delta = collector.interval_delta("mlx5_0", 1).unwrap();
+        assert_eq!(delta.rx_bytes, 0);
+        assert_eq!(delta.tx_packets, 5);
+
+        collector.update(&[]);
+        assert!(collector.interval_delta("mlx5_0", 1).is_none());
+    }
+
+    #[test]
+    fn test_interval_guard_discards_stalled_samples() {
+        let adapter = |rx_bytes| {
+            adapter_with(PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            })
+        };
+        let rewind = |collector: &mut MetricsCollector, by: Duration| {
+            collector.last_collection = collector
+                .last_collection
+                .and_then(|last| last.checked_sub(by));
+        };
+        let rx_rate = |collector: &MetricsCollector| {
+            collector.get_metrics("mlx5_0", 1).unwrap().rx_bytes_per_sec
+        };
+        let history_len = |collector: &MetricsCollector| {
+            collector
+                .get_history("mlx5_0", 1)
+                .map_or(0, |h| h.rx_bytes_per_sec.len())
+        };
+
+        let mut collector = MetricsCollector::new();
+        collector.set_interval_guard(Some(Duration::from_secs(1)));
+        collector.update(&[adapter(0)]);
+        rewind(&mut collector, Duration::from_secs(1));
+        collector.update(&[adapter(1_000)]);
+        assert!((rx_rate(&collector) - 1_000.0).abs() < 10.0);
+
+        // A 5 s stall: the burst is not smeared into a 5 s average
+        rewind(&mut collector, Duration::from_secs(5));
+        collector.update(&[adapter(51_000)]);
+        assert!((rx_rate(&collector) - 1_000.0).abs() < 10.0);
+        assert_eq!(history_len(&collector), 1);
+
+        // The next on-time sample is measured from the discarded one
+        rewind(&mut collector, Duration::from_secs(1));
+        collector.update(&[adapter(53_000)]);
+        assert!((rx_rate(&collector) - 2_000.0).abs() < 20.0);
+        assert_eq!(history_len(&collector), 2);
+
+        // Up to 3x the interval still counts
+        rewind(&mut collector, Duration::from_millis(2_900));
+        collector.update(&[adapter(58_800)]);
+        assert!((rx_rate(&collector) - 2_000.0).abs() < 20.0);
+        assert_eq!(history_len(&collector), 3);
+
+        // Without the guard, the stall is averaged in
+        let mut collector = MetricsCollector::new();
+        collector.update(&[adapter(0)]);
+        rewind(&mut collector, Duration::from_secs(5));
+        collector.update(&[adapter(50_000)]);
+        assert!((rx_rate(&collector) - 10_000.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_reset_rebaselines_rates() {
+        let adapter = |rx_bytes| {
+            adapter_with(PortCounters {
+                rx_bytes,
+                ..PortCounters::default()
+            })
+        };
+
+        let mut collector = MetricsCollector::new();
+        collector.update(&[adapter(0)]);
+        collector.update(&[adapter(1_000_000)]);
+        assert!(collector.get_metrics("mlx5_0", 1).is_some());
+        assert!(collector.get_history("mlx5_0", 1).is_some());
+
+        collector.reset();
+        assert!(collector.get_metrics("mlx5_0", 1).is_none());
+        assert!(collector.get_history("mlx5_0", 1).is_none());
+        assert!(collector.interval_delta("mlx5_0", 1).is_none());
+
+        // The first update after the reset is the new starting point
+        collector.update(&[adapter(5_000_000)]);
+        assert!(collector.get_metrics("mlx5_0", 1).is_none());
+        collector.update(&[adapter(5_000_300)]);
+        assert_eq!(collector.interval_delta("mlx5_0", 1).unwrap().rx_bytes, 300);
+        assert_eq!(
+            collector
+                .get_history("mlx5_0", 1)
+                .unwrap()
+                .rx_bytes_per_sec
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_trip_meter_counts_from_reference() {
+        let adapters = |rx_bytes, rx_errors| {
+            vec![adapter_with(PortCounters {
+                rx_bytes,
+                rx_errors,
+                ..PortCounters::default()
+            })]
+        };
+
+        let mut collector = MetricsCollector::new();
+        collector.update(&adapters(5_000, 7));
+        assert!(collector.trip("mlx5_0", 1).is_none());
+
+        collector.set_reference();
+        collector.update(&adapters(9_000, 9));
+        // Pretend the trip started two seconds before the latest sample
+        collector.reference_time = collector
+            .last_collection
+            .and_then(|last| last.checked_sub(Duration::from_secs(2)));
+
+        let trip = collector.trip("mlx5_0", 1).unwrap();
+        assert_eq!(trip.elapsed, Duration::from_secs(2));
+        assert_eq!(trip.counters.rx_bytes, 4_000);
+        assert_eq!(trip.counters.rx_errors, 2);
+        assert!((trip.rates.rx_bytes_per_sec - 2_000.0).abs() < 1e-9);
+        assert!((trip.rates.error_rate - 1.0).abs() < 1e-9);
+        assert!(collector.trip("mlx5_0", 2).is_none());
+
+        // Zeroing again restarts from the latest counters
+        collector.set_reference();
+        let trip = collector.trip("mlx5_0", 1).unwrap();
+        assert_eq!(trip.counters, PortCounters::default());
+        assert_eq!(trip.elapsed, Duration::ZERO);
+    }
+}
@@ -0,0 +1,258 @@
+//! Prometheus endpoint for `--serve`
+//!
+//! A deliberately small HTTP/1.1 server: every `GET /metrics` re-reads the
+//! counters and answers in the text exposition format. Each connection gets
+//! its own thread, so a slow scraper does not hold up the next one, up to
+//! `MAX_HANDLERS` at once; past that, connections are refused with a 503.
+//! Ctrl-C stops accepting and waits for scrapes in flight before returning.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+/// How often the accept loop checks whether to shut down
+const SHUTDOWN_POLL: Duration = Duration::from_millis(100);
+
+/// Longest a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most bytes of request line and headers read from a client
+const MAX_REQUEST_BYTES: u64 = 8192;
+
+/// Most connections handled at once; each scrape also reads every adapter
+/// on its own thread
+const MAX_HANDLERS: usize = 8;
+
+/// Content type of the Prometheus text exposition format
+const EXPOSITION_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Answer scrapes on `addr` with the text `scrape` returns, until Ctrl-C
+pub fn serve<F>(addr: SocketAddr, scrape: F) -> io::Result<()>
+where
+    F: Fn() -> String + Sync,
+{
+    let listener = TcpListener::bind(addr)?;
+    // Non-blocking, so the loop notices Ctrl-C between connections
+    listener.set_nonblocking(true)?;
+    let shutdown = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&shutdown))?;
+    }
+    eprintln!(
+        "ibtop: serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+
+    accept_loop(&listener, &shutdown, &scrape);
+    Ok(())
+}
+
+/// Hand each connection on the non-blocking `listener` to a thread of its
+/// own until `shutdown` is set, then wait for them
+fn accept_loop<F>(listener: &TcpListener, shutdown: &AtomicBool, scrape: &F)
+where
+    F: Fn() -> String + Sync,
+{
+    let active = &AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        while !shutdown.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) if active.load(Ordering::Acquire) >= MAX_HANDLERS => {
+                    // Errors only affect the refused client
+                    let _ = refuse(&stream);
+                }
+                Ok((stream, _)) => {
+                    active.fetch_add(1, Ordering::AcqRel);
+                    scope.spawn(move || {
+                        // A scraper that hangs up early only loses its own answer
+                        let _ = handle(&stream, scrape);
+                        active.fetch_sub(1, Ordering::AcqRel);
+                    });
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(SHUTDOWN_POLL);
+                }
+                Err(err) => eprintln!("ibtop: accepting a connection failed: {err}"),
+            }
+        }
+    });
+}
+
+/// Read one request from `stream` and write the response
+fn handle(stream: &TcpStream, scrape: &dyn Fn() -> String) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let request_line = read_request(stream)?;
+    let mut stream = stream;
+    stream.write_all(response(&request_line, scrape).as_bytes())?;
+    stream.flush()
+}
+
+/// Turn a connection away while `MAX_HANDLERS` are busy
+fn refuse(stream: &TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut stream = stream;
+    stream.write_all(
+        http_response(
+            "503 Service Unavailable",
+            "text/plain",
+            "Too many scrapes in flight\n",
+        )
+        .as_bytes(),
+    )
+}
+
+/// The request line, after reading the headers; at most `MAX_REQUEST_BYTES`
+/// are read, so an endless line cannot grow memory
+fn read_request(stream: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(stream).take(MAX_REQUEST_BYTES);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed, but are read so the client sees a clean close
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+    Ok(request_line)
+}
+
+/// The full HTTP response to a request line such as `GET /metrics HTTP/1.1`
+fn response(request_line: &str, scrape: &dyn Fn() -> String) -> String {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next();
+    let path = parts
+        .next()
+        .map(|target| target.split('?').next().unwrap_or(target));
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", EXPOSITION_CONTENT_TYPE, scrape()),
+        (Some("GET"), _) => (
+            "404 Not Found",
+            "text/plain",
+            "Metrics are served at /metrics\n".to_string(),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+    http_response(status, content_type, &body)
+}
+
+fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::to_prometheus;
+    use crate::types::{AdapterInfo, IbtopOutput, PortCounters, PortInfo, PortState};
+    use std::io::Read;
+
+    fn fixture() -> String {
+        to_prometheus(&IbtopOutput {
+            hostname: "node01".to_string(),
+            adapters: vec![AdapterInfo {
+                name: "mlx5_0".to_string(),
+                ports: vec![PortInfo {
+                    port_number: 1,
+                    state: PortState::Active,
+                    counters: PortCounters {
+                        rx_bytes: 123,
+                        ..PortCounters::default()
+                    },
+                    ..PortInfo::default()
+                }],
+                ..AdapterInfo::default()
+            }],
+            rates: Vec::new(),
+            history: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_metrics_response() {
+        let text = response("GET /metrics HTTP/1.1\r\n", &fixture);
+        let (head, body) = text.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        assert_eq!(body, fixture());
+        assert!(body
+            .lines()
+            .any(|l| l == r#"ibtop_port_rx_bytes_total{adapter="mlx5_0",port="1"} 123"#));
+
+        // Query strings are ignored; other paths and methods are refused
+        assert!(response("GET /metrics?x=1 HTTP/1.1", &fixture).starts_with("HTTP/1.1 200"));
+        assert!(response("GET / HTTP/1.1", &fixture).starts_with("HTTP/1.1 404"));
+        assert!(response("POST /metrics HTTP/1.1", &fixture).starts_with("HTTP/1.1 405"));
+        assert!(response("", &fixture).starts_with("HTTP/1.1 405"));
+    }
+
+    #[test]
+    fn test_handle_answers_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: node01\r\nAccept: */*\r\n\r\n")
+            .unwrap();
+
+        let (stream, _) = listener.accept().unwrap();
+        handle(&stream, &fixture).unwrap();
+        drop(stream);
+
+        let mut text = String::new();
+        client.read_to_string(&mut text).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.ends_with(&fixture()));
+    }
+
+    #[test]
+    fn test_request_reading_is_capped() {
+        // An endless request line stops at the cap instead of growing
+        let line = read_request(io::repeat(b'a')).unwrap();
+        assert_eq!(line.len() as u64, MAX_REQUEST_BYTES);
+
+        let request = "GET /metrics HTTP/1.1\r\nHost: node01\r\n\r\n";
+        assert_eq!(
+            read_request(request.as_bytes()).unwrap(),
+            "GET /metrics HTTP/1.1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_connections_past_the_limit_are_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| accept_loop(&listener, &shutdown, &fixture));
+
+            // Connections that never send a request keep their handlers busy
+            let idle: Vec<TcpStream> = (0..MAX_HANDLERS)
+                .map(|_| TcpStream::connect(addr).unwrap())
+                .collect();
+            let mut refused = TcpStream::connect(addr).unwrap();
+            let mut text = String::new();
+            refused.read_to_string(&mut text).unwrap();
+            assert!(text.starts_with("HTTP/1.1 503"), "{text}");
+
+            // Hanging up frees the handlers
+            drop(idle);
+            shutdown.store(true, Ordering::Relaxed);
+        });
+    }
+}
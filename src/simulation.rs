@@ -11,7 +11,7 @@
 #![allow(clippy::similar_names)] // rx/tx pairs are intentionally similar
 #![allow(clippy::cast_precision_loss)] // Acceptable for metrics
 
-use crate::types::{AdapterInfo, PortCounters, PortInfo, PortState};
+use crate::types::{AdapterInfo, LinkLayer, PhysState, PortCounters, PortInfo, PortState};
 use std::f64::consts::PI;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
@@ -146,8 +146,13 @@ struct PortCounterState {
     rx_packets: AtomicU64,
     tx_packets: AtomicU64,
     rx_errors: AtomicU64,
-    tx_errors: AtomicU64,
+    tx_discards: AtomicU64,
     rx_dropped: AtomicU64,
+    link_error_recovery: AtomicU64,
+    link_downed: AtomicU64,
+    symbol_errors: AtomicU64,
+    local_link_integrity_errors: AtomicU64,
+    xmit_wait: AtomicU64,
 }
 
 impl PortCounterState {
@@ -158,8 +163,13 @@ impl PortCounterState {
             rx_packets: AtomicU64::new(0),
             tx_packets: AtomicU64::new(0),
             rx_errors: AtomicU64::new(0),
-            tx_errors: AtomicU64::new(0),
+            tx_discards: AtomicU64::new(0),
             rx_dropped: AtomicU64::new(0),
+            link_error_recovery: AtomicU64::new(0),
+            link_downed: AtomicU64::new(0),
+            symbol_errors: AtomicU64::new(0),
+            local_link_integrity_errors: AtomicU64::new(0),
+            xmit_wait: AtomicU64::new(0),
         }
     }
 }
@@ -254,7 +264,29 @@ pub fn generate_fake_adapters() -> Vec<AdapterInfo> {
         let port_info = PortInfo {
             port_number: port_config.port_number,
             state: port_config.state,
+            // The simulated down port has no cable plugged in
+            phys_state: if port_config.state == PortState::Down {
+                PhysState::Polling
+            } else {
+                PhysState::LinkUp
+            },
+            // RoCE LAG devices bond Ethernet links
+            link_layer: if port_config.adapter_name.contains("bond") {
+                LinkLayer::Ethernet
+            } else {
+                LinkLayer::InfiniBand
+            },
             rate: port_config.rate.to_string(),
+            gid: Some(simulated_gid(idx)),
+            // The subnet manager sits at LID 1 and numbers ports from 2;
+            // ports that are down have not been assigned one
+            lid: Some(if port_config.state == PortState::Down {
+                0
+            } else {
+                simulated_lid(idx)
+            }),
+            sm_lid: Some(u16::from(port_config.state != PortState::Down)),
+            sysfs_path: None,
             counters,
         };
 
@@ -270,6 +302,10 @@ pub fn generate_fake_adapters() -> Vec<AdapterInfo> {
         .map(|(name, ports)| AdapterInfo {
             name: name.to_string(),
             ports,
+            bond_members: simulated_bond_members(name),
+            fw_ver: Some("28.39.1002".to_string()),
+            board_id: Some("MT_0000000838".to_string()),
+            hca_type: Some("MT4129".to_string()),
         })
         .collect();
 
@@ -277,6 +313,27 @@ pub fn generate_fake_adapters() -> Vec<AdapterInfo> {
     adapters
 }
 
+/// Two healthy member links for the simulated bond
+fn simulated_bond_members(adapter_name: &str) -> Vec<(String, PortState)> {
+    if !adapter_name.contains("bond") {
+        return Vec::new();
+    }
+    ["ens1f0np0", "ens1f1np1"]
+        .iter()
+        .map(|member| ((*member).to_string(), PortState::Active))
+        .collect()
+}
+
+/// Link-local GID in sysfs format, unique per simulated port
+fn simulated_gid(idx: usize) -> String {
+    format!("fe80:0000:0000:0000:0002:c903:00f1:{:04x}", 0x2340 + idx)
+}
+
+/// LID of a simulated port; the subnet manager holds LID 1
+fn simulated_lid(idx: usize) -> u16 {
+    u16::try_from(idx + 2).unwrap_or(u16::MAX)
+}
+
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_sign_loss)]
 fn generate_counters(idx: usize, config: &SimulatedPort, time_secs: f64) -> PortCounters {
@@ -300,7 +357,7 @@ fn generate_counters(idx: usize, config: &SimulatedPort, time_secs: f64) -> Port
     } else {
         0
     };
-    let tx_errors = if random_noise() < error_prob {
+    let tx_discards = if random_noise() < error_prob {
         (random_noise() * 2.0) as u64
     } else {
         0
@@ -311,6 +368,19 @@ fn generate_counters(idx: usize, config: &SimulatedPort, time_secs: f64) -> Port
         0
     };
 
+    // Link-level events: symbol errors as often as packet errors, and a rare
+    // recovery that now and then fails and takes the link down
+    let symbol_errors = u64::from(random_noise() < error_prob);
+    let link_error_recovery = u64::from(random_noise() < 0.002);
+    let link_downed = u64::from(link_error_recovery > 0 && random_noise() < 0.25);
+    let local_link_integrity_errors = u64::from(random_noise() < 0.001);
+    // Credit stalls: constant on a congested link, rare blips elsewhere
+    let xmit_wait = match config.pattern {
+        TrafficPattern::Congestion => (random_noise() * 200_000.0) as u64,
+        _ if random_noise() < 0.02 => (random_noise() * 1_000.0) as u64,
+        _ => 0,
+    };
+
     // Update cumulative counters
     let counter = &COUNTERS[idx];
     let total_rx = counter.rx_bytes.fetch_add(rx_bytes, Ordering::Relaxed) + rx_bytes;
@@ -318,7 +388,10 @@ fn generate_counters(idx: usize, config: &SimulatedPort, time_secs: f64) -> Port
     let total_rx_pkt = counter.rx_packets.fetch_add(rx_packets, Ordering::Relaxed) + rx_packets;
     let total_tx_pkt = counter.tx_packets.fetch_add(tx_packets, Ordering::Relaxed) + tx_packets;
     let total_rx_err = counter.rx_errors.fetch_add(rx_errors, Ordering::Relaxed) + rx_errors;
-    let total_tx_err = counter.tx_errors.fetch_add(tx_errors, Ordering::Relaxed) + tx_errors;
+    let total_tx_err = counter
+        .tx_discards
+        .fetch_add(tx_discards, Ordering::Relaxed)
+        + tx_discards;
     let total_dropped = counter.rx_dropped.fetch_add(rx_dropped, Ordering::Relaxed) + rx_dropped;
 
     PortCounters {
@@ -327,8 +400,25 @@ fn generate_counters(idx: usize, config: &SimulatedPort, time_secs: f64) -> Port
         rx_packets: total_rx_pkt,
         tx_packets: total_tx_pkt,
         rx_errors: total_rx_err,
-        tx_errors: total_tx_err,
+        tx_discards: total_tx_err,
         rx_dropped: total_dropped,
+        link_error_recovery: counter
+            .link_error_recovery
+            .fetch_add(link_error_recovery, Ordering::Relaxed)
+            + link_error_recovery,
+        link_downed: counter
+            .link_downed
+            .fetch_add(link_downed, Ordering::Relaxed)
+            + link_downed,
+        symbol_errors: counter
+            .symbol_errors
+            .fetch_add(symbol_errors, Ordering::Relaxed)
+            + symbol_errors,
+        local_link_integrity_errors: counter
+            .local_link_integrity_errors
+            .fetch_add(local_link_integrity_errors, Ordering::Relaxed)
+            + local_link_integrity_errors,
+        xmit_wait: counter.xmit_wait.fetch_add(xmit_wait, Ordering::Relaxed) + xmit_wait,
     }
 }
 
@@ -357,7 +447,7 @@ mod tests {
             for t in 0..100 {
                 let util = calculate_utilization(*pattern, f64::from(t) * 0.1);
                 assert!(
-                    util >= 0.0 && util <= 1.0,
+                    (0.0..=1.0).contains(&util),
                     "Pattern {pattern:?} at t={t}: util={util}"
                 );
             }
@@ -1,17 +1,71 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct IbtopOutput {
     pub(crate) hostname: String,
     pub(crate) adapters: Vec<AdapterInfo>,
+    /// Per-port rates averaged over the export window (`--export-avg`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) rates: Vec<PortRates>,
+    /// Per-port samples recorded over the export window (`--with-history`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) history: Vec<PortHistorySamples>,
+}
+
+/// One port in `--json-flat` output, with the counters as top-level keys
+#[derive(Debug, Serialize)]
+pub(crate) struct FlatPort {
+    pub(crate) hostname: String,
+    pub(crate) adapter: String,
+    pub(crate) port: u16,
+    pub(crate) state: PortState,
+    pub(crate) rate: String,
+    #[serde(flatten)]
+    pub(crate) counters: PortCounters,
+}
+
+/// Per-second rates for one port, averaged over an export window
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PortRates {
+    pub(crate) adapter: String,
+    pub(crate) port_number: u16,
+    pub(crate) rx_bytes_per_sec: f64,
+    pub(crate) tx_bytes_per_sec: f64,
+    pub(crate) rx_packets_per_sec: f64,
+    pub(crate) tx_packets_per_sec: f64,
+    pub(crate) error_rate: f64,
+    #[serde(default)]
+    pub(crate) discard_rate: f64,
+    /// Error rate above `--error-threshold`; left out when false
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) alert: bool,
+}
+
+/// One port's recorded history, oldest sample first; the arrays line up
+/// index by index with `timestamps` (seconds since the Unix epoch)
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PortHistorySamples {
+    pub(crate) adapter: String,
+    pub(crate) port_number: u16,
+    pub(crate) timestamps: Vec<f64>,
+    pub(crate) rx_bytes_per_sec: Vec<f64>,
+    pub(crate) tx_bytes_per_sec: Vec<f64>,
+    pub(crate) rx_packets_per_sec: Vec<f64>,
+    pub(crate) tx_packets_per_sec: Vec<f64>,
+    pub(crate) error_rate: Vec<f64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub(crate) enum PortState {
     Active,
     Down,
+    /// Link is up but the subnet manager has not configured the port yet
+    Init,
+    /// Configured by the subnet manager, waiting to be made active
+    Armed,
     #[default]
     Unknown,
 }
@@ -20,6 +74,8 @@ impl Display for PortState {
         match self {
             PortState::Active => write!(f, "ACTIVE"),
             PortState::Down => write!(f, "DOWN"),
+            PortState::Init => write!(f, "INIT"),
+            PortState::Armed => write!(f, "ARMED"),
             PortState::Unknown => write!(f, "UNKNOWN"),
         }
     }
@@ -31,32 +87,144 @@ impl FromStr for PortState {
         match s.trim() {
             "ACTIVE" => Ok(PortState::Active),
             "DOWN" => Ok(PortState::Down),
+            "INIT" => Ok(PortState::Init),
+            "ARMED" => Ok(PortState::Armed),
             _ => Ok(PortState::Unknown),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Physical link training state (`phys_state`), which says why a port is down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub(crate) enum PhysState {
+    LinkUp,
+    /// Looking for a link partner: typically no cable or a dead far end
+    Polling,
+    Disabled,
+    PortConfigurationTraining,
+    Sleep,
+    #[default]
+    Unknown,
+}
+
+impl Display for PhysState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhysState::LinkUp => write!(f, "LinkUp"),
+            PhysState::Polling => write!(f, "Polling"),
+            PhysState::Disabled => write!(f, "Disabled"),
+            PhysState::PortConfigurationTraining => write!(f, "PortConfigurationTraining"),
+            PhysState::Sleep => write!(f, "Sleep"),
+            PhysState::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+impl FromStr for PhysState {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "LinkUp" => Ok(PhysState::LinkUp),
+            "Polling" => Ok(PhysState::Polling),
+            "Disabled" => Ok(PhysState::Disabled),
+            "PortConfigurationTraining" => Ok(PhysState::PortConfigurationTraining),
+            "Sleep" => Ok(PhysState::Sleep),
+            _ => Ok(PhysState::Unknown),
+        }
+    }
+}
+
+/// Link layer a port runs (`link_layer`); RoCE ports are Ethernet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub(crate) enum LinkLayer {
+    InfiniBand,
+    Ethernet,
+    #[default]
+    Unknown,
+}
+
+impl FromStr for LinkLayer {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "InfiniBand" => Ok(LinkLayer::InfiniBand),
+            "Ethernet" => Ok(LinkLayer::Ethernet),
+            _ => Ok(LinkLayer::Unknown),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct AdapterInfo {
     pub(crate) name: String,
     pub(crate) ports: Vec<PortInfo>,
+    /// Member links of a bonded device and their state; empty otherwise
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) bond_members: Vec<(String, PortState)>,
+    /// Firmware version (`fw_ver`), e.g. `28.39.1002`
+    #[serde(default)]
+    pub(crate) fw_ver: Option<String>,
+    /// Board identifier (`board_id`), e.g. `MT_0000000838`
+    #[serde(default)]
+    pub(crate) board_id: Option<String>,
+    /// HCA model (`hca_type`), e.g. `MT4129`
+    #[serde(default)]
+    pub(crate) hca_type: Option<String>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PortInfo {
     pub(crate) port_number: u16,
     pub(crate) state: PortState,
+    /// Missing from snapshots taken before it was recorded
+    #[serde(default)]
+    pub(crate) phys_state: PhysState,
+    /// Decides how `rate` is read; missing from older snapshots
+    #[serde(default)]
+    pub(crate) link_layer: LinkLayer,
     pub(crate) rate: String,
+    /// Raw default GID (`gids/0`), if readable
+    #[serde(default)]
+    pub(crate) gid: Option<String>,
+    /// Local identifier the subnet manager assigned to the port (`lid`)
+    #[serde(default)]
+    pub(crate) lid: Option<u16>,
+    /// LID of the port's subnet manager (`sm_lid`)
+    #[serde(default)]
+    pub(crate) sm_lid: Option<u16>,
+    /// Port directory it was read from, under `INFINIBAND_PATH`; local to
+    /// this node, so left out of snapshots
+    #[serde(skip)]
+    pub(crate) sysfs_path: Option<PathBuf>,
     pub(crate) counters: PortCounters,
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct PortCounters {
     pub(crate) rx_bytes: u64,
     pub(crate) tx_bytes: u64,
     pub(crate) rx_packets: u64,
     pub(crate) tx_packets: u64,
+    /// Physical receive errors such as bad CRCs (`port_rcv_errors`)
     pub(crate) rx_errors: u64,
-    pub(crate) tx_errors: u64,
+    /// Packets dropped on transmit, mostly from congestion
+    /// (`port_xmit_discards`); older snapshots call it `tx_errors`
+    #[serde(alias = "tx_errors")]
+    pub(crate) tx_discards: u64,
     pub(crate) rx_dropped: u64,
+    /// Times the link recovered from errors without going down
+    #[serde(default)]
+    pub(crate) link_error_recovery: u64,
+    /// Times the link failed error recovery and went down
+    #[serde(default)]
+    pub(crate) link_downed: u64,
+    /// Physical-layer symbol errors (`symbol_error`)
+    #[serde(default)]
+    pub(crate) symbol_errors: u64,
+    #[serde(default)]
+    pub(crate) local_link_integrity_errors: u64,
+    /// Ticks the port had data to send but no credits to send it with
+    /// (`port_xmit_wait`), the fabric's own congestion signal
+    #[serde(default)]
+    pub(crate) xmit_wait: u64,
 }
@@ -0,0 +1,393 @@
+//! Column registry for the main table
+//!
+//! Every table column is described once here (name, title, width, renderer),
+//! so `--columns` can select and order them freely.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Cell,
+};
+
+use super::{
+    link_label, port_max_rate, render_inline_sparkline, render_utilization_bar, split_sparklines,
+    sustained_over_line_rate, utilization_percent, RateDisplay, Theme, SPARKLINE_SAMPLES,
+};
+use crate::history::PortHistory;
+use crate::metrics::PortMetrics;
+use crate::types::{PhysState, PortInfo, PortState};
+
+/// A column of the main table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Port,
+    State,
+    Link,
+    Util,
+    Rx,
+    Tx,
+    Errors,
+    Share,
+    Burst,
+    History,
+}
+
+/// Columns shown when `--columns` is not given
+pub const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Port,
+    Column::State,
+    Column::Link,
+    Column::Util,
+    Column::Rx,
+    Column::Tx,
+    Column::History,
+];
+
+/// Everything a column needs to render one port row
+pub struct PortRowContext<'a> {
+    pub port: &'a PortInfo,
+    pub metrics: Option<&'a PortMetrics>,
+    pub history: Option<&'a PortHistory>,
+    pub frame_count: u64,
+    pub split_sparkline: bool,
+    /// Percentile sparklines are scaled to instead of the max (`--sparkline-clip`)
+    pub sparkline_clip: Option<f64>,
+    /// Node-wide RX + TX throughput, for the share column
+    pub node_total: f64,
+    pub rate_display: RateDisplay,
+    pub theme: Theme,
+    /// The port does not meet its `--expect` expectation
+    pub falls_short: bool,
+}
+
+impl Column {
+    /// Every column, in default display order
+    pub const ALL: &'static [Column] = &[
+        Column::Port,
+        Column::State,
+        Column::Link,
+        Column::Util,
+        Column::Rx,
+        Column::Tx,
+        Column::Errors,
+        Column::Share,
+        Column::Burst,
+        Column::History,
+    ];
+
+    /// Name used on the command line
+    pub const fn name(self) -> &'static str {
+        match self {
+            Column::Port => "port",
+            Column::State => "state",
+            Column::Link => "link",
+            Column::Util => "util",
+            Column::Rx => "rx",
+            Column::Tx => "tx",
+            Column::Errors => "errors",
+            Column::Share => "share",
+            Column::Burst => "burst",
+            Column::History => "history",
+        }
+    }
+
+    /// Header text
+    pub const fn title(self) -> &'static str {
+        match self {
+            Column::Port => "Port",
+            Column::State => "State",
+            Column::Link => "Link",
+            Column::Util => "Load",
+            Column::Rx => "RX",
+            Column::Tx => "TX",
+            Column::Errors => "Err/s",
+            Column::Share => "Share",
+            Column::Burst => "Burst",
+            Column::History => "History",
+        }
+    }
+
+    /// Header text, marking rate columns that show per-consumer rates
+    pub fn title_for(self, rates: RateDisplay) -> String {
+        match self {
+            Column::Rx | Column::Tx => format!("{}{}", self.title(), rates.suffix()),
+            _ => self.title().to_string(),
+        }
+    }
+
+    /// Fixed width in terminal cells
+    pub const fn width(self) -> u16 {
+        match self {
+            Column::Port => 4,
+            Column::Share | Column::Burst => 7,
+            Column::State | Column::Errors => 8,
+            Column::Link => 15,
+            Column::Util | Column::Rx | Column::Tx => 10,
+            // Padded, plus the scale label
+            Column::History => SPARKLINE_SAMPLES as u16 + 4 + SCALE_LABEL_WIDTH,
+        }
+    }
+
+    /// Look up a column by its command-line name (aliases included)
+    fn from_name(name: &str) -> Option<Column> {
+        match name {
+            "rate" => Some(Column::Link),
+            "load" => Some(Column::Util),
+            "sparkline" => Some(Column::History),
+            "burstiness" => Some(Column::Burst),
+            _ => Column::ALL.iter().copied().find(|c| c.name() == name),
+        }
+    }
+
+    /// Render this column's cell for a port row
+    pub fn render(self, ctx: &PortRowContext) -> Cell<'static> {
+        let theme = ctx.theme;
+        match self {
+            Column::Port if ctx.falls_short => Cell::from(format!("✗ {}", ctx.port.port_number))
+                .style(Style::default().fg(theme.bad).add_modifier(Modifier::BOLD)),
+            Column::Port => Cell::from(format!("  {}", ctx.port.port_number))
+                .style(Style::default().fg(theme.accent)),
+            Column::State => {
+                let (state_str, state_color) = state_indicator(ctx.port, ctx.frame_count, theme);
+                Cell::from(state_str).style(Style::default().fg(state_color))
+            }
+            Column::Link => Cell::from(link_label(ctx.port))
+                .style(Style::default().fg(theme.text).add_modifier(Modifier::DIM)),
+            Column::Util => {
+                let max_rate = port_max_rate(ctx.port);
+                let utilization = ctx
+                    .metrics
+                    .map_or(0.0, |m| utilization_percent(m, max_rate));
+                let bar = Cell::from(render_utilization_bar(utilization, 8));
+                match ctx.metrics {
+                    // Impossible rates are shown in red rather than silently clamped
+                    Some(m) if sustained_over_line_rate(m, ctx.history, max_rate) => {
+                        bar.style(Style::default().fg(theme.bad))
+                    }
+                    _ => bar,
+                }
+            }
+            Column::Rx => Cell::from(ctx.metrics.map_or_else(
+                || "--".to_string(),
+                |m| ctx.rate_display.format(m.rx_bytes_per_sec),
+            ))
+            .style(Style::default().fg(theme.rx)),
+            Column::Tx => Cell::from(ctx.metrics.map_or_else(
+                || "--".to_string(),
+                |m| ctx.rate_display.format(m.tx_bytes_per_sec),
+            ))
+            .style(Style::default().fg(theme.tx)),
+            Column::Errors => match ctx.metrics {
+                Some(m) if m.error_rate > 0.0 => {
+                    Cell::from(format!("{:.1}", m.error_rate)).style(Style::default().fg(theme.bad))
+                }
+                Some(_) => Cell::from("0").style(Style::default().fg(theme.muted)),
+                None => Cell::from("--"),
+            },
+            Column::Share => match ctx.metrics.and_then(|m| {
+                throughput_share(m.rx_bytes_per_sec + m.tx_bytes_per_sec, ctx.node_total)
+            }) {
+                Some(share) => Cell::from(format!("{share:.1}%")),
+                None => Cell::from("--").style(Style::default().fg(theme.muted)),
+            },
+            Column::Burst => match ctx.history.and_then(PortHistory::burstiness) {
+                Some(ratio) => Cell::from(format!("{ratio:.1}×"))
+                    .style(Style::default().fg(burstiness_color(ratio, theme))),
+                None => Cell::from("--").style(Style::default().fg(theme.muted)),
+            },
+            Column::History => {
+                Cell::from(sparkline_line(ctx)).style(Style::default().fg(theme.accent))
+            }
+        }
+    }
+}
+
+/// State indicator with pulsing effect for active ports
+fn state_indicator(port: &PortInfo, frame_count: u64, theme: Theme) -> (String, Color) {
+    match port.state {
+        PortState::Active => {
+            // Subtle pulse: alternates between bright and dim dot
+            let pulse = if frame_count % 60 < 30 { "●" } else { "○" };
+            (format!("{pulse}ACTIVE"), theme.good)
+        }
+        // Still polling for a partner: nothing is plugged in, or the far end is dead
+        PortState::Down if port.phys_state == PhysState::Polling => {
+            ("○NOCABLE".to_string(), theme.bad)
+        }
+        PortState::Down => ("○DOWN".to_string(), theme.bad),
+        // Mid-bringup rather than dead: waiting on the subnet manager
+        PortState::Init => ("◔INIT".to_string(), theme.warn),
+        PortState::Armed => ("◑ARM".to_string(), theme.bringup),
+        PortState::Unknown => ("?UNKN".to_string(), theme.warn),
+    }
+}
+
+/// Peak/average ratio from which traffic counts as bursty
+const BURSTY_RATIO: f64 = 3.0;
+
+/// Steady traffic stays white; bursty traffic is called out in yellow
+pub fn burstiness_color(ratio: f64, theme: Theme) -> Color {
+    if ratio >= BURSTY_RATIO {
+        theme.warn
+    } else {
+        theme.text
+    }
+}
+
+/// A port's share (percent) of the node's total throughput
+///
+/// Unlike the load column, which compares a port to its own line rate, this
+/// shows which ports dominate the node. `None` while the node is idle.
+fn throughput_share(port_rate: f64, node_total: f64) -> Option<f64> {
+    (node_total > 0.0).then(|| port_rate / node_total * 100.0)
+}
+
+/// Room for the rate a combined sparkline is scaled to ("≤1023.9MB/s")
+const SCALE_LABEL_WIDTH: u16 = 11;
+
+/// Sparkline data (with padding)
+///
+/// The combined sparkline is followed by the rate its full height stands
+/// for; split sparklines scale RX and TX separately, so they have none.
+fn sparkline_line(ctx: &PortRowContext) -> Line<'static> {
+    let theme = ctx.theme;
+    match ctx.history {
+        Some(h) if ctx.split_sparkline => {
+            let (rx, tx) = split_sparklines(h, SPARKLINE_SAMPLES, ctx.sparkline_clip);
+            Line::from(vec![
+                Span::raw(" "),
+                Span::styled(rx, Style::default().fg(theme.rx)),
+                Span::raw(" "),
+                Span::styled(tx, Style::default().fg(theme.tx)),
+            ])
+        }
+        Some(h) => {
+            let (data, scale) = h.combined_sparkline(SPARKLINE_SAMPLES, ctx.sparkline_clip);
+            // Padded while history fills, so the labels line up
+            let sparkline = format!(" {:<SPARKLINE_SAMPLES$} ", render_inline_sparkline(&data));
+            if scale > 0.0 {
+                Line::from(vec![
+                    Span::raw(sparkline),
+                    Span::styled(
+                        format!("≤{}", ctx.rate_display.format(scale)),
+                        Style::default().fg(theme.muted),
+                    ),
+                ])
+            } else {
+                Line::from(sparkline)
+            }
+        }
+        None => Line::from(" ".repeat(SPARKLINE_SAMPLES + 2)),
+    }
+}
+
+/// Parse a comma-separated column list such as `port,state,rx,tx`
+pub fn parse_columns(list: &str) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::new();
+
+    for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let column = Column::from_name(&name.to_ascii_lowercase()).ok_or_else(|| {
+            let known: Vec<&str> = Column::ALL.iter().map(|c| c.name()).collect();
+            format!(
+                "unknown column '{name}' (expected one of: {})",
+                known.join(",")
+            )
+        })?;
+        if columns.contains(&column) {
+            return Err(format!("column '{name}' given more than once"));
+        }
+        columns.push(column);
+    }
+
+    if columns.is_empty() {
+        return Err("at least one column is required".to_string());
+    }
+
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_columns_order() {
+        let columns = parse_columns("port,state,rx,tx,util,errors").unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                Column::Port,
+                Column::State,
+                Column::Rx,
+                Column::Tx,
+                Column::Util,
+                Column::Errors
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_columns_aliases_and_whitespace() {
+        let columns = parse_columns(" TX , load,sparkline ").unwrap();
+        assert_eq!(columns, vec![Column::Tx, Column::Util, Column::History]);
+    }
+
+    #[test]
+    fn test_parse_columns_rejects_unknown() {
+        let err = parse_columns("port,bogus").unwrap_err();
+        assert!(err.contains("bogus"));
+    }
+
+    #[test]
+    fn test_parse_columns_rejects_empty_and_duplicates() {
+        assert!(parse_columns("").is_err());
+        assert!(parse_columns(",,").is_err());
+        assert!(parse_columns("rx,rx").is_err());
+    }
+
+    #[test]
+    fn test_throughput_share() {
+        let rates = [600.0, 300.0, 75.0, 25.0, 0.0];
+        let total: f64 = rates.iter().sum();
+
+        let shares: Vec<f64> = rates
+            .iter()
+            .map(|rate| throughput_share(*rate, total).unwrap())
+            .collect();
+        assert!((shares[0] - 60.0).abs() < 1e-9);
+        assert!((shares[4]).abs() < 1e-9);
+        assert!((shares.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+
+        assert_eq!(throughput_share(0.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_bringup_states_are_told_apart_from_down() {
+        let indicator = |raw: &str| {
+            let port = PortInfo {
+                state: raw.parse().unwrap(),
+                ..PortInfo::default()
+            };
+            state_indicator(&port, 0, Theme::DARK)
+        };
+
+        assert_eq!(indicator("DOWN"), ("○DOWN".to_string(), Color::Red));
+        assert_eq!(indicator("INIT"), ("◔INIT".to_string(), Color::Yellow));
+        assert_eq!(
+            indicator("ARMED"),
+            ("◑ARM".to_string(), Theme::DARK.bringup)
+        );
+
+        // Existing variants keep their JSON names; the new ones follow suit
+        let json =
+            serde_json::to_string(&[PortState::Active, PortState::Init, PortState::Armed]).unwrap();
+        assert_eq!(json, r#"["Active","Init","Armed"]"#);
+    }
+
+    #[test]
+    fn test_every_column_round_trips_by_name() {
+        for column in Column::ALL {
+            assert_eq!(Column::from_name(column.name()), Some(*column));
+        }
+    }
+}
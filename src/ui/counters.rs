@@ -0,0 +1,345 @@
+//! Counter registry for the Errors tab
+//!
+//! Every readable port counter is described once here (name, label, whether
+//! it counts trouble), so `--error-counters` can pick which ones the Errors
+//! tab lists and in what order, the way `--columns` does for the table.
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use std::str::FromStr;
+
+use super::{format_bytes, Theme};
+use crate::types::PortCounters;
+
+/// A counter of `PortCounters`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    RxBytes,
+    TxBytes,
+    RxPackets,
+    TxPackets,
+    RxErrors,
+    TxDiscards,
+    RxDropped,
+    LinkErrorRecovery,
+    LinkDowned,
+    SymbolErrors,
+    LocalLinkIntegrityErrors,
+    XmitWait,
+}
+
+/// Counters listed on the Errors tab when `--error-counters` is not given
+pub const DEFAULT_ERROR_COUNTERS: &[Counter] = &[
+    Counter::LinkErrorRecovery,
+    Counter::LinkDowned,
+    Counter::SymbolErrors,
+    Counter::LocalLinkIntegrityErrors,
+];
+
+/// Counters per line of the Errors tab
+const COUNTERS_PER_LINE: usize = 4;
+
+/// Smallest count `CounterNotation::Engineering` abbreviates; shorter
+/// numbers read fine in full
+const ENGINEERING_FROM: u64 = 1_000_000;
+
+/// How the Errors tab writes counter values (`--counter-notation`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CounterNotation {
+    /// Bytes in KB to PB, everything else in full digits
+    #[default]
+    Auto,
+    /// Every counter from a million up as three significant digits and a
+    /// power of ten that is a multiple of 3, e.g. `1.23e15` or `45.6e9`
+    Engineering,
+}
+
+impl FromStr for CounterNotation {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(CounterNotation::Auto),
+            "eng" | "engineering" => Ok(CounterNotation::Engineering),
+            _ => Err(()),
+        }
+    }
+}
+
+impl CounterNotation {
+    /// A counter's value as the Errors tab shows it
+    pub fn format(self, counter: Counter, value: u64) -> String {
+        match self {
+            CounterNotation::Engineering if value >= ENGINEERING_FROM => engineering(value),
+            CounterNotation::Auto if matches!(counter, Counter::RxBytes | Counter::TxBytes) => {
+                format_bytes(value)
+            }
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// `value` in engineering notation with three significant digits
+#[allow(clippy::cast_precision_loss)] // Three digits survive the rounding
+fn engineering(value: u64) -> String {
+    // Scientific notation rounds to three digits, e.g. "4.57e10"; moving
+    // the point right makes the exponent a multiple of 3 ("45.7e9")
+    let scientific = format!("{:.2e}", value as f64);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: u32 = exponent.parse().unwrap_or(0);
+    let shift = (exponent % 3) as usize;
+    let digits = mantissa.replace('.', "");
+    let (whole, fraction) = digits.split_at(1 + shift);
+    if fraction.is_empty() {
+        format!("{whole}e{}", exponent - exponent % 3)
+    } else {
+        format!("{whole}.{fraction}e{}", exponent - exponent % 3)
+    }
+}
+
+impl Counter {
+    /// Every counter, in `PortCounters` field order
+    pub const ALL: &'static [Counter] = &[
+        Counter::RxBytes,
+        Counter::TxBytes,
+        Counter::RxPackets,
+        Counter::TxPackets,
+        Counter::RxErrors,
+        Counter::TxDiscards,
+        Counter::RxDropped,
+        Counter::LinkErrorRecovery,
+        Counter::LinkDowned,
+        Counter::SymbolErrors,
+        Counter::LocalLinkIntegrityErrors,
+        Counter::XmitWait,
+    ];
+
+    /// Name used on the command line, the same as the `--json` key
+    pub const fn name(self) -> &'static str {
+        match self {
+            Counter::RxBytes => "rx_bytes",
+            Counter::TxBytes => "tx_bytes",
+            Counter::RxPackets => "rx_packets",
+            Counter::TxPackets => "tx_packets",
+            Counter::RxErrors => "rx_errors",
+            Counter::TxDiscards => "tx_discards",
+            Counter::RxDropped => "rx_dropped",
+            Counter::LinkErrorRecovery => "link_error_recovery",
+            Counter::LinkDowned => "link_downed",
+            Counter::SymbolErrors => "symbol_errors",
+            Counter::LocalLinkIntegrityErrors => "local_link_integrity_errors",
+            Counter::XmitWait => "xmit_wait",
+        }
+    }
+
+    /// Short label on the Errors tab
+    pub const fn label(self) -> &'static str {
+        match self {
+            Counter::RxBytes => "rx bytes",
+            Counter::TxBytes => "tx bytes",
+            Counter::RxPackets => "rx pkts",
+            Counter::TxPackets => "tx pkts",
+            Counter::RxErrors => "rx errors",
+            Counter::TxDiscards => "discards",
+            Counter::RxDropped => "dropped",
+            Counter::LinkErrorRecovery => "recovery",
+            Counter::LinkDowned => "downed",
+            Counter::SymbolErrors => "symbol",
+            Counter::LocalLinkIntegrityErrors => "integrity",
+            Counter::XmitWait => "xmit wait",
+        }
+    }
+
+    /// Whether any count at all is a problem worth colouring
+    const fn counts_trouble(self) -> bool {
+        !matches!(
+            self,
+            Counter::RxBytes | Counter::TxBytes | Counter::RxPackets | Counter::TxPackets
+        )
+    }
+
+    pub const fn value(self, counters: &PortCounters) -> u64 {
+        match self {
+            Counter::RxBytes => counters.rx_bytes,
+            Counter::TxBytes => counters.tx_bytes,
+            Counter::RxPackets => counters.rx_packets,
+            Counter::TxPackets => counters.tx_packets,
+            Counter::RxErrors => counters.rx_errors,
+            Counter::TxDiscards => counters.tx_discards,
+            Counter::RxDropped => counters.rx_dropped,
+            Counter::LinkErrorRecovery => counters.link_error_recovery,
+            Counter::LinkDowned => counters.link_downed,
+            Counter::SymbolErrors => counters.symbol_errors,
+            Counter::LocalLinkIntegrityErrors => counters.local_link_integrity_errors,
+            Counter::XmitWait => counters.xmit_wait,
+        }
+    }
+
+    /// Look up a counter by its command-line name (sysfs file names included)
+    fn from_name(name: &str) -> Option<Counter> {
+        match name {
+            "port_rcv_errors" => Some(Counter::RxErrors),
+            "port_xmit_discards" => Some(Counter::TxDiscards),
+            "port_rcv_constraint_errors" => Some(Counter::RxDropped),
+            "symbol_error" => Some(Counter::SymbolErrors),
+            "port_xmit_wait" => Some(Counter::XmitWait),
+            _ => Counter::ALL.iter().copied().find(|c| c.name() == name),
+        }
+    }
+}
+
+/// The counters since the driver loaded, `COUNTERS_PER_LINE` to a line,
+/// e.g. "Counters recovery 3  downed 1  symbol 12  integrity 0"; trouble
+/// counters that counted stand out
+pub fn counter_lines(
+    counters: &PortCounters,
+    shown: &[Counter],
+    notation: CounterNotation,
+    theme: Theme,
+) -> Vec<Line<'static>> {
+    shown
+        .chunks(COUNTERS_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let lead = if i == 0 { "Counters" } else { "        " };
+            let mut spans = vec![Span::styled(lead, Style::default().fg(theme.muted))];
+            for &counter in chunk {
+                let value = counter.value(counters);
+                let color = if counter.counts_trouble() && value > 0 {
+                    theme.bad
+                } else {
+                    theme.text
+                };
+                let text = notation.format(counter, value);
+                spans.push(Span::styled(
+                    format!(" {} ", counter.label()),
+                    Style::default().fg(theme.muted),
+                ));
+                spans.push(Span::styled(text, Style::default().fg(color)));
+                spans.push(Span::raw(" "));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Parse a comma-separated counter list such as `symbol_errors,xmit_wait`
+pub fn parse_counters(list: &str) -> Result<Vec<Counter>, String> {
+    let mut counters = Vec::new();
+
+    for name in list.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let counter = Counter::from_name(&name.to_ascii_lowercase()).ok_or_else(|| {
+            let known: Vec<&str> = Counter::ALL.iter().map(|c| c.name()).collect();
+            format!(
+                "unknown counter '{name}' (expected one of: {})",
+                known.join(",")
+            )
+        })?;
+        if counters.contains(&counter) {
+            return Err(format!("counter '{name}' given more than once"));
+        }
+        counters.push(counter);
+    }
+
+    if counters.is_empty() {
+        return Err("at least one counter is required".to_string());
+    }
+
+    Ok(counters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_configured_counters_drive_error_rows() {
+        let counters = PortCounters {
+            rx_bytes: 4096,
+            rx_dropped: 3,
+            link_error_recovery: 7,
+            symbol_errors: 12,
+            xmit_wait: 900,
+            ..PortCounters::default()
+        };
+
+        let default = counter_lines(
+            &counters,
+            DEFAULT_ERROR_COUNTERS,
+            CounterNotation::Auto,
+            Theme::DARK,
+        );
+        assert_eq!(default.len(), 1);
+        assert_eq!(
+            text(&default[0]),
+            "Counters recovery 7  downed 0  symbol 12  integrity 0 "
+        );
+
+        let shown =
+            parse_counters("symbol_error, rx_dropped,xmit_wait,rx_bytes,rx_errors").unwrap();
+        let lines = counter_lines(&counters, &shown, CounterNotation::Auto, Theme::DARK);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            text(&lines[0]),
+            "Counters symbol 12  dropped 3  xmit wait 900  rx bytes 4.0KB "
+        );
+        assert_eq!(text(&lines[1]), "         rx errors 0 ");
+        assert!(!lines.iter().any(|l| text(l).contains("recovery")));
+
+        // Traffic is never alarming; a counted error is
+        let value_color = |line: &Line, label: &str| {
+            let at = line.spans.iter().position(|s| s.content.trim() == label);
+            line.spans[at.unwrap() + 1].style.fg
+        };
+        assert_eq!(value_color(&lines[0], "symbol"), Some(Color::Red));
+        assert_eq!(value_color(&lines[0], "rx bytes"), Some(Color::White));
+        assert_eq!(value_color(&lines[1], "rx errors"), Some(Color::White));
+    }
+
+    #[test]
+    fn test_engineering_notation_above_a_million() {
+        let eng = CounterNotation::Engineering;
+        assert_eq!(eng.format(Counter::SymbolErrors, 0), "0");
+        assert_eq!(eng.format(Counter::XmitWait, 999_999), "999999");
+        assert_eq!(eng.format(Counter::XmitWait, 1_000_000), "1.00e6");
+        assert_eq!(eng.format(Counter::RxPackets, 45_678_901_234), "45.7e9");
+        assert_eq!(eng.format(Counter::RxPackets, 123_456_789), "123e6");
+        assert_eq!(
+            eng.format(Counter::RxBytes, 1_234_000_000_000_000),
+            "1.23e15"
+        );
+        // Rounding up carries into the next power of 1000
+        assert_eq!(eng.format(Counter::TxBytes, 999_999_999), "1.00e9");
+        assert_eq!(eng.format(Counter::TxBytes, u64::MAX), "18.4e18");
+
+        // The default keeps bytes in binary units and other counts whole
+        let auto = CounterNotation::Auto;
+        assert_eq!(
+            auto.format(Counter::RxBytes, 1_234_000_000_000_000),
+            "1.0PB"
+        );
+        assert_eq!(
+            auto.format(Counter::XmitWait, 45_678_901_234),
+            "45678901234"
+        );
+
+        assert_eq!("eng".parse(), Ok(CounterNotation::Engineering));
+        assert_eq!("sci".parse::<CounterNotation>(), Err(()));
+    }
+
+    #[test]
+    fn test_parse_counters_rejects_bad_lists() {
+        assert!(parse_counters("symbol_errors,nope").is_err());
+        assert!(parse_counters("symbol_errors,symbol_error").is_err());
+        assert!(parse_counters(" , ").is_err());
+        assert_eq!(parse_counters("XMIT_WAIT"), Ok(vec![Counter::XmitWait]));
+    }
+}
@@ -0,0 +1,4448 @@
+//! Enhanced TUI with real-time charts and sparklines
+//!
+//! Provides a visually stunning interface with:
+//! - Sparklines in the main table view
+//! - Expandable detail view with full throughput charts
+//! - Color-coded status indicators
+//! - Smooth visual transitions
+
+#![allow(clippy::cast_precision_loss)] // Acceptable for chart coordinates
+#![allow(clippy::cast_possible_truncation)] // Acceptable for UI values
+#![allow(clippy::cast_sign_loss)] // Values are always positive
+#![allow(clippy::similar_names)] // rx/tx pairs are intentionally similar
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    backend::TestBackend,
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::{Line, Span},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Dataset, GraphType, Paragraph, Row, Table, Tabs, Widget,
+    },
+    Frame, Terminal,
+};
+use regex::Regex;
+
+mod columns;
+mod counters;
+mod theme;
+
+use columns::PortRowContext;
+pub use columns::{parse_columns, Column, DEFAULT_COLUMNS};
+pub use counters::{parse_counters, Counter, CounterNotation, DEFAULT_ERROR_COUNTERS};
+pub use theme::Theme;
+
+use crate::alerts::{self, AlertLog, Severity};
+use crate::baseline::Baseline;
+use crate::discovery::{parse_vport_counters, LinkDetails, VportCounters};
+use crate::expectations::Expectations;
+use crate::history::{ema, PortHistory};
+use crate::metrics::{MetricsCollector, PortMetrics, TripMeter};
+use crate::types::{AdapterInfo, LinkLayer, PhysState, PortCounters, PortInfo, PortState};
+use crate::warnings;
+
+/// Counter files as `dir/file` and raw value
+type RawCounters = Vec<(String, String)>;
+
+/// Number of sparkline samples to show in the main table
+const SPARKLINE_SAMPLES: usize = 20;
+
+/// Titles of the detail panel tabs, in order
+const DETAIL_TABS: &[&str] = &["Throughput", "Packets", "Errors", "Info", "Raw", "VPorts"];
+/// Index of the "Errors" tab, which also lists the link-level error counters
+const ERRORS_TAB: usize = 2;
+/// Index of the "Info" tab, which shows port attributes instead of a chart
+const INFO_TAB: usize = 3;
+/// Index of the "Raw" tab, which lists every counter file verbatim
+const RAW_TAB: usize = 4;
+/// Index of the "`VPorts`" tab, which splits an SR-IOV port's traffic by vport
+const VPORT_TAB: usize = 5;
+
+/// Selectable metrics refresh intervals, in milliseconds
+const REFRESH_STEPS_MS: &[u64] = &[100, 250, 500, 1_000, 2_000, 5_000, 10_000];
+
+/// Range the adaptive refresh moves the interval within
+const ADAPTIVE_MIN_INTERVAL: Duration = Duration::from_millis(100);
+const ADAPTIVE_MAX_INTERVAL: Duration = Duration::from_secs(2);
+/// Relative throughput change between refreshes that counts as a burst
+const ADAPTIVE_BURST_CHANGE: f64 = 0.25;
+/// Relative change below which the node counts as steady
+const ADAPTIVE_IDLE_CHANGE: f64 = 0.05;
+/// Node throughput (bytes/s) below which changes are measured against this
+/// floor, so noise on an idle node does not look like a burst
+const ADAPTIVE_NOISE_FLOOR: f64 = 1_000_000.0;
+
+/// Tick spacings (seconds) the wall-clock chart axis may use
+const WALL_CLOCK_STEPS: &[i64] = &[1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1_800, 3_600];
+/// Intervals between wall-clock ticks (one more label than this)
+const WALL_CLOCK_TICKS: i64 = 4;
+
+/// Frames a transient notice stays up (about 3 seconds at the UI refresh
+/// rate); a vanished port's notice closes the detail view when it expires
+const NOTICE_FRAMES: u64 = 90;
+
+/// How long the carousel shows each port before moving on
+const CAROUSEL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Refresh intervals that may pass without new metrics before the footer's
+/// "updated ... ago" turns red
+const STALL_INTERVALS: u32 = 3;
+
+/// Default share of the screen (percent) given to the detail panel
+const DETAIL_RATIO_DEFAULT: u16 = 50;
+/// Bounds and step for resizing the detail panel
+const DETAIL_RATIO_MIN: u16 = 20;
+const DETAIL_RATIO_MAX: u16 = 80;
+const DETAIL_RATIO_STEP: u16 = 10;
+
+/// Application state for the UI
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)] // Independent view toggles, not a state machine
+pub struct AppState {
+    /// Currently selected row index (for navigation)
+    pub selected_row: usize,
+    /// Whether detail view is expanded
+    pub detail_expanded: bool,
+    /// Moving past the last port selects the first one, and vice versa
+    pub wrap_navigation: bool,
+    /// Currently selected tab in detail view
+    pub detail_tab: usize,
+    /// Scroll offset for the main table (for future scrolling support)
+    #[allow(dead_code)]
+    pub scroll_offset: usize,
+    /// Animation frame counter
+    pub frame_count: u64,
+    /// Show separate RX/TX sparklines instead of the combined one
+    pub split_sparkline: bool,
+    /// Scale sparklines to this percentile of their window, not its max
+    pub sparkline_clip: Option<f64>,
+    /// Percentage of the screen height used by the detail panel
+    pub detail_ratio: u16,
+    /// Columns of the main table, in display order (never empty)
+    pub columns: Vec<Column>,
+    /// Counters listed on the Errors tab, in display order
+    pub error_counters: Vec<Counter>,
+    /// How the Errors tab writes large counter values
+    pub counter_notation: CounterNotation,
+    /// Whether the History column is drawn when it is among `columns`
+    pub show_sparkline: bool,
+    /// Frames are too slow, so sparklines and the state pulse are off
+    pub degraded: bool,
+    /// How often metrics are re-collected
+    pub refresh_interval: Duration,
+    /// Throughput as measured or per consumer (`--divide-by`)
+    pub rate_display: RateDisplay,
+    /// Let the refresh interval follow how fast throughput is changing
+    pub adaptive_refresh: bool,
+    /// Node throughput at the previous refresh, for the adaptive refresh
+    last_node_total: Option<f64>,
+    /// When metrics were last refreshed, for the footer heartbeat
+    last_update: Option<Instant>,
+    /// When monitoring started, for the elapsed time in the detail title
+    started: Instant,
+    /// Show how long ibtop has been monitoring in the detail title
+    pub show_elapsed: bool,
+    /// Replace each adapter's DOWN ports with a single summary row
+    pub collapse_down: bool,
+    /// Show the sums, average and peak in the summary strip above the footer
+    pub show_summary: bool,
+    /// Counters captured with `b`; the detail view shows deltas from it
+    pub baseline: Option<Baseline>,
+    /// Anchor the chart x-axis to wall-clock time instead of "ago → now"
+    pub wall_clock: bool,
+    /// Mark the line rate on the throughput chart and shade what lies above it
+    pub line_rate_band: bool,
+    /// Show the raw counts of the last interval in the detail view
+    pub show_interval_delta: bool,
+    /// Plot moving averages in the detail chart instead of raw samples
+    pub smooth_chart: bool,
+    /// Plot RX upward and TX downward from a zero line, like bmon or nload
+    pub mirror_chart: bool,
+    /// Unit of the chart's "ago" label
+    pub time_unit: TimeUnit,
+    /// Node throughput limit in bits per second (`--alert-total`)
+    pub alert_total: Option<f64>,
+    /// Session label shown before the hostname (`--title`)
+    pub title: Option<String>,
+    /// Colours everything is drawn with (`--theme`)
+    pub theme: Theme,
+    /// Errors per second above which a port's row turns bold red
+    pub error_threshold: Option<f64>,
+    /// When the carousel last moved to a port; `None` while it is off
+    carousel: Option<Instant>,
+    /// Ports whose "adapter:port" matches are emphasised in the table
+    pub highlight: Option<Regex>,
+    /// States and rates ports are expected to have (`--expect`)
+    pub expectations: Expectations,
+    /// Raw counter files of the port shown on the "Raw" tab, and that port
+    raw_counters: Option<((String, u16), RawCounters)>,
+    /// First line shown on the "Raw" tab
+    raw_scroll: usize,
+    /// Phys state and cable of the port shown on the "Info" tab, and that port
+    link_details: Option<((String, u16), LinkDetails)>,
+    /// Recent flaps, resets, error onsets and other alerts
+    pub alerts: AlertLog,
+    /// Show the alert history in place of the detail panel
+    pub show_alerts: bool,
+    /// List the busiest adapters first rather than in discovery order
+    pub sort_adapters: bool,
+    /// How ports are ordered; anything but `Name` drops the adapter grouping
+    pub sort_key: SortKey,
+    /// Only adapters whose name contains this (case-insensitive) are listed
+    pub filter: String,
+    /// Keys are typed into `filter` rather than acting as commands
+    pub filter_editing: bool,
+    /// Live updates are frozen; the last counters stay on screen
+    pub paused: bool,
+    /// List of selectable items (adapter, port) or None for adapter headers
+    selectable_items: Vec<Option<(String, u16)>>,
+    /// Port selected on the previous frame, so the selection follows it
+    selected_key: Option<(String, u16)>,
+    /// Port that disappeared while shown in the detail view, and the frame it did
+    vanished: Option<((String, u16), u64)>,
+    /// Transient footer message, and the frame it was posted on
+    notice: Option<(String, u64)>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            selected_row: 0,
+            detail_expanded: false,
+            wrap_navigation: false,
+            detail_tab: 0,
+            scroll_offset: 0,
+            frame_count: 0,
+            split_sparkline: false,
+            sparkline_clip: None,
+            detail_ratio: DETAIL_RATIO_DEFAULT,
+            columns: DEFAULT_COLUMNS.to_vec(),
+            error_counters: DEFAULT_ERROR_COUNTERS.to_vec(),
+            counter_notation: CounterNotation::default(),
+            show_sparkline: true,
+            degraded: false,
+            refresh_interval: Duration::from_millis(crate::METRICS_UPDATE_INTERVAL_MS),
+            rate_display: RateDisplay::default(),
+            adaptive_refresh: false,
+            last_node_total: None,
+            last_update: None,
+            started: Instant::now(),
+            show_elapsed: true,
+            collapse_down: false,
+            show_summary: true,
+            baseline: None,
+            wall_clock: false,
+            show_interval_delta: false,
+            smooth_chart: false,
+            mirror_chart: false,
+            line_rate_band: false,
+            time_unit: TimeUnit::default(),
+            alert_total: None,
+            title: None,
+            theme: Theme::default(),
+            error_threshold: None,
+            carousel: None,
+            highlight: None,
+            expectations: Expectations::default(),
+            raw_counters: None,
+            raw_scroll: 0,
+            link_details: None,
+            alerts: AlertLog::default(),
+            show_alerts: false,
+            sort_adapters: false,
+            sort_key: SortKey::default(),
+            filter: String::new(),
+            filter_editing: false,
+            paused: false,
+            selectable_items: Vec::new(),
+            selected_key: None,
+            vanished: None,
+            notice: None,
+        }
+    }
+}
+
+impl AppState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move selection up
+    pub fn select_prev(&mut self) {
+        self.carousel = None;
+        self.vanished = None;
+        // Skip adapter header rows
+        let prev = if self.wrap_navigation {
+            self.port_row_wrapping(false)
+        } else {
+            (0..self.selected_row)
+                .rev()
+                .find(|&row| !self.is_header_row(row))
+        };
+        if let Some(prev) = prev {
+            self.selected_row = prev;
+        }
+        self.remember_selection();
+    }
+
+    /// Move selection down
+    pub fn select_next(&mut self) {
+        self.carousel = None;
+        self.vanished = None;
+        // Skip adapter header rows
+        let next = if self.wrap_navigation {
+            self.port_row_wrapping(true)
+        } else {
+            (self.selected_row + 1..self.selectable_items.len())
+                .find(|&row| !self.is_header_row(row))
+        };
+        if let Some(next) = next {
+            self.selected_row = next;
+        }
+        self.remember_selection();
+    }
+
+    /// Start or stop cycling the detail view through every port
+    pub fn toggle_carousel(&mut self, now: Instant) {
+        self.carousel = match self.carousel {
+            Some(_) => None,
+            None => Some(now),
+        };
+    }
+
+    /// Whether the carousel is cycling through ports
+    pub fn carousel_active(&self) -> bool {
+        self.carousel.is_some()
+    }
+
+    /// Move the carousel to the next port, wrapping around, once its
+    /// interval has passed; returns whether it moved
+    pub fn advance_carousel(&mut self, now: Instant) -> bool {
+        let Some(last) = self.carousel else {
+            return false;
+        };
+        if !self.detail_expanded || now.duration_since(last) < CAROUSEL_INTERVAL {
+            return false;
+        }
+
+        let next = self.port_row_wrapping(true);
+        if let Some(row) = next {
+            self.selected_row = row;
+            self.vanished = None;
+            self.remember_selection();
+        }
+        self.carousel = Some(now);
+        next.is_some()
+    }
+
+    /// Nearest port row after (or before) the selection, continuing from the
+    /// other end of the list; header rows are skipped on either side
+    fn port_row_wrapping(&self, forward: bool) -> Option<usize> {
+        let len = self.selectable_items.len();
+        (1..=len)
+            .map(|offset| {
+                if forward {
+                    (self.selected_row + offset) % len
+                } else {
+                    (self.selected_row + len - offset % len) % len
+                }
+            })
+            .find(|&row| !self.is_header_row(row))
+    }
+
+    /// Check if a row is a header (not selectable)
+    fn is_header_row(&self, row: usize) -> bool {
+        match self.selectable_items.get(row) {
+            None | Some(None) => true,
+            Some(Some(_)) => false,
+        }
+    }
+
+    /// Toggle detail view
+    pub fn toggle_detail(&mut self) {
+        self.detail_expanded = !self.detail_expanded;
+        self.vanished = None;
+    }
+
+    /// Toggle the alert history panel
+    pub fn toggle_alerts(&mut self) {
+        self.show_alerts = !self.show_alerts;
+    }
+
+    /// Toggle the monitoring time in the detail view title
+    pub fn toggle_elapsed(&mut self) {
+        self.show_elapsed = !self.show_elapsed;
+    }
+
+    /// Toggle between combined and split RX/TX sparklines
+    pub fn toggle_split_sparkline(&mut self) {
+        self.split_sparkline = !self.split_sparkline;
+    }
+
+    /// Record that metrics were refreshed at `now`
+    pub fn mark_updated(&mut self, now: Instant) {
+        self.last_update = Some(now);
+    }
+
+    /// Enter or leave degraded rendering, saying so in the footer
+    pub fn set_degraded(&mut self, degraded: bool) {
+        self.degraded = degraded;
+        self.notify(if degraded {
+            "Rendering cannot keep up: sparklines and animation off"
+        } else {
+            "Rendering caught up: sparklines and animation back on"
+        });
+    }
+
+    /// Show or hide the History column
+    pub fn toggle_sparkline(&mut self) {
+        self.show_sparkline = !self.show_sparkline;
+    }
+
+    /// Columns actually drawn: `columns` without History while it is hidden
+    /// or the UI is degraded, unless that would leave the table without columns
+    pub fn visible_columns(&self) -> Vec<Column> {
+        let visible: Vec<Column> = self
+            .columns
+            .iter()
+            .copied()
+            .filter(|column| (self.show_sparkline && !self.degraded) || *column != Column::History)
+            .collect();
+        if visible.is_empty() {
+            self.columns.clone()
+        } else {
+            visible
+        }
+    }
+
+    /// Give the detail panel more room
+    pub fn grow_detail(&mut self) {
+        self.detail_ratio = clamp_detail_ratio(self.detail_ratio.saturating_add(DETAIL_RATIO_STEP));
+    }
+
+    /// Give the main table more room
+    pub fn shrink_detail(&mut self) {
+        self.detail_ratio = clamp_detail_ratio(self.detail_ratio.saturating_sub(DETAIL_RATIO_STEP));
+    }
+
+    /// Refresh less often; a manual choice ends adaptive refresh
+    pub fn slower_refresh(&mut self) {
+        self.adaptive_refresh = false;
+        self.refresh_interval = adjust_interval(self.refresh_interval, true);
+    }
+
+    /// Refresh more often; a manual choice ends adaptive refresh
+    pub fn faster_refresh(&mut self) {
+        self.adaptive_refresh = false;
+        self.refresh_interval = adjust_interval(self.refresh_interval, false);
+    }
+
+    /// After a refresh, retune the interval to how much node throughput moved
+    pub fn adapt_refresh(&mut self, adapters: &[AdapterInfo], metrics: &MetricsCollector) {
+        if !self.adaptive_refresh {
+            return;
+        }
+        let (total_rx, total_tx) = calculate_totals(adapters, metrics);
+        let total = total_rx + total_tx;
+        if let Some(previous) = self.last_node_total.replace(total) {
+            let change = (total - previous).abs() / previous.max(total).max(ADAPTIVE_NOISE_FLOOR);
+            self.refresh_interval = adaptive_interval(self.refresh_interval, change);
+        }
+    }
+
+    /// Get currently selected port
+    pub fn selected_port(&self) -> Option<(&str, u16)> {
+        self.selectable_items
+            .get(self.selected_row)?
+            .as_ref()
+            .map(|(a, p)| (a.as_str(), *p))
+    }
+
+    /// Cycle detail tab
+    pub fn next_tab(&mut self) {
+        self.detail_tab = (self.detail_tab + 1) % DETAIL_TABS.len();
+    }
+
+    /// Cycle detail tab backward
+    pub fn prev_tab(&mut self) {
+        self.detail_tab = if self.detail_tab == 0 {
+            DETAIL_TABS.len() - 1
+        } else {
+            self.detail_tab - 1
+        };
+    }
+
+    /// Port whose raw counters the "Raw" or "`VPorts`" tab is showing, if it
+    /// is on screen
+    pub fn raw_counters_port(&self) -> Option<(String, u16)> {
+        if !self.detail_expanded || !matches!(self.detail_tab, RAW_TAB | VPORT_TAB) {
+            return None;
+        }
+        self.selected_port()
+            .map(|(adapter, port)| (adapter.to_string(), port))
+    }
+
+    /// Whether the raw counters held for `port` are missing
+    pub fn needs_raw_counters(&self, port: &(String, u16)) -> bool {
+        self.raw_counters
+            .as_ref()
+            .is_none_or(|(shown, _)| shown != port)
+    }
+
+    /// Replace the raw counters; scrolling restarts when the port changed
+    pub fn set_raw_counters(&mut self, port: (String, u16), entries: RawCounters) {
+        if self.needs_raw_counters(&port) {
+            self.raw_scroll = 0;
+        }
+        self.raw_counters = Some((port, entries));
+    }
+
+    /// Port the "Info" tab still needs link details for, if it is on screen
+    pub fn link_details_wanted(&self) -> Option<(String, u16)> {
+        if !self.detail_expanded || self.detail_tab != INFO_TAB {
+            return None;
+        }
+        let (adapter, port) = self.selected_port()?;
+        match &self.link_details {
+            Some(((shown, shown_port), _)) if shown == adapter && *shown_port == port => None,
+            _ => Some((adapter.to_string(), port)),
+        }
+    }
+
+    /// Remember the link details read for `port`
+    pub fn set_link_details(&mut self, port: (String, u16), details: LinkDetails) {
+        self.link_details = Some((port, details));
+    }
+
+    /// Scroll the "Raw" tab by `lines` (negative scrolls up)
+    pub fn scroll_raw(&mut self, lines: isize) {
+        self.raw_scroll = self.raw_scroll.saturating_add_signed(lines);
+    }
+
+    /// Show `message` in the footer for a few seconds
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.notice = Some((message.into(), self.frame_count));
+    }
+
+    fn active_notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, since)| self.frame_count.saturating_sub(*since) < NOTICE_FRAMES)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// Toggle the line-rate ceiling on the throughput chart
+    pub fn toggle_line_rate_band(&mut self) {
+        self.line_rate_band = !self.line_rate_band;
+    }
+
+    /// Toggle between a relative and a wall-clock chart x-axis
+    pub fn toggle_wall_clock(&mut self) {
+        self.wall_clock = !self.wall_clock;
+    }
+
+    /// Toggle the mirrored (RX up, TX down) chart
+    pub fn toggle_mirror_chart(&mut self) {
+        self.mirror_chart = !self.mirror_chart;
+    }
+
+    /// Toggle between raw and moving-average chart lines
+    pub fn toggle_smooth_chart(&mut self) {
+        self.smooth_chart = !self.smooth_chart;
+    }
+
+    /// Toggle the per-interval delta counts in the detail view
+    pub fn toggle_interval_delta(&mut self) {
+        self.show_interval_delta = !self.show_interval_delta;
+    }
+
+    /// Whether a live error rate is above `--error-threshold`
+    pub fn over_error_threshold(&self, error_rate: f64) -> bool {
+        self.error_threshold
+            .is_some_and(|threshold| error_rate > threshold)
+    }
+
+    /// Whether `--highlight` matches the port, as "adapter:port"
+    pub fn is_highlighted(&self, adapter: &str, port: u16) -> bool {
+        self.highlight
+            .as_ref()
+            .is_some_and(|re| re.is_match(&format!("{adapter}:{port}")))
+    }
+
+    /// Toggle the summary strip's sums, average and peak
+    pub fn toggle_summary(&mut self) {
+        self.show_summary = !self.show_summary;
+    }
+
+    /// Toggle ordering adapters by their aggregate throughput
+    pub fn toggle_sort_adapters(&mut self) {
+        self.sort_adapters = !self.sort_adapters;
+        self.notify(if self.sort_adapters {
+            "Adapters sorted by throughput"
+        } else {
+            "Adapters in discovery order"
+        });
+    }
+
+    /// Switch to the next port sort key
+    pub fn cycle_sort_key(&mut self) {
+        self.sort_key = self.sort_key.next();
+        self.notify(match self.sort_key {
+            SortKey::Name => "Ports grouped by adapter".to_string(),
+            key => format!("Ports sorted by {}, highest first", key.label()),
+        });
+    }
+
+    /// Freeze or resume live updates
+    pub fn toggle_paused(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Start typing an adapter filter, refining the current one
+    pub fn start_filter(&mut self) {
+        self.filter_editing = true;
+    }
+
+    /// Finish typing and keep the filter
+    pub fn commit_filter(&mut self) {
+        self.filter_editing = false;
+    }
+
+    /// Finish typing and drop the filter
+    pub fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filter_editing = false;
+    }
+
+    /// Whether the adapter filter lets `adapter` through
+    fn shows_adapter(&self, adapter: &str) -> bool {
+        self.filter.is_empty() || adapter.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    /// Toggle collapsing DOWN ports into one summary row per adapter
+    pub fn toggle_collapse_down(&mut self) {
+        self.collapse_down = !self.collapse_down;
+    }
+
+    fn update_selectable_items(&mut self, adapters: &[AdapterInfo], metrics: &MetricsCollector) {
+        let items = build_rows(adapters, metrics, self)
+            .iter()
+            .map(|row| match row {
+                TableRow::Port(adapter, port) => Some((adapter.name.clone(), port.port_number)),
+                _ => None,
+            })
+            .collect();
+        self.selectable_items = items;
+        self.follow_selected_port(adapters);
+        // Ensure selection is valid
+        if self.selected_row >= self.selectable_items.len() {
+            self.selected_row = self.selectable_items.len().saturating_sub(1);
+        }
+        // Skip adapter headers
+        while self.selected_row < self.selectable_items.len()
+            && self.is_header_row(self.selected_row)
+        {
+            if self.selected_row + 1 < self.selectable_items.len() {
+                self.selected_row += 1;
+            } else {
+                break;
+            }
+        }
+        self.remember_selection();
+    }
+
+    fn remember_selection(&mut self) {
+        self.selected_key = self
+            .selected_port()
+            .map(|(adapter, port)| (adapter.to_string(), port));
+    }
+
+    /// Keep the selection on the same port when rows move, and notice when
+    /// the port shown in the detail view is gone (e.g. a hot-removed HCA)
+    fn follow_selected_port(&mut self, adapters: &[AdapterInfo]) {
+        let row_of = |items: &[Option<(String, u16)>], key: &(String, u16)| {
+            items.iter().position(|item| item.as_ref() == Some(key))
+        };
+
+        if let Some((key, since)) = &self.vanished {
+            if let Some(row) = row_of(&self.selectable_items, key) {
+                // It came back
+                self.selected_row = row;
+                self.vanished = None;
+            } else if self.frame_count.saturating_sub(*since) >= NOTICE_FRAMES {
+                self.detail_expanded = false;
+                self.vanished = None;
+            }
+            return;
+        }
+
+        let Some(key) = self.selected_key.take() else {
+            return;
+        };
+        if let Some(row) = row_of(&self.selectable_items, &key) {
+            self.selected_row = row;
+        } else if self.detail_expanded && !port_exists(adapters, &key.0, key.1) {
+            self.vanished = Some((key, self.frame_count));
+        }
+    }
+}
+
+/// Adapters in display order: busiest first (RX + TX over all ports) when
+/// sorting is on, discovery order otherwise; ports stay in their order
+fn ordered_adapters<'a>(
+    adapters: &'a [AdapterInfo],
+    metrics: &MetricsCollector,
+    state: &AppState,
+) -> Cow<'a, [AdapterInfo]> {
+    if !state.sort_adapters {
+        return Cow::Borrowed(adapters);
+    }
+    let total = |adapter: &AdapterInfo| -> f64 {
+        adapter
+            .ports
+            .iter()
+            .filter_map(|port| metrics.get_metrics(&adapter.name, port.port_number))
+            .map(|m| m.rx_bytes_per_sec + m.tx_bytes_per_sec)
+            .sum()
+    };
+    let mut sorted = adapters.to_vec();
+    // Stable, so idle adapters keep their discovery order
+    sorted.sort_by(|a, b| total(b).total_cmp(&total(a)));
+    Cow::Owned(sorted)
+}
+
+/// How ports are ordered in the main table
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Grouped under their adapter, in discovery order
+    #[default]
+    Name,
+    RxRate,
+    TxRate,
+    /// RX + TX
+    Total,
+    /// Error rate
+    Errors,
+}
+
+impl SortKey {
+    /// The key `s` switches to next
+    pub const fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::RxRate,
+            SortKey::RxRate => SortKey::TxRate,
+            SortKey::TxRate => SortKey::Total,
+            SortKey::Total => SortKey::Errors,
+            SortKey::Errors => SortKey::Name,
+        }
+    }
+
+    /// Short name for the footer
+    pub const fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::RxRate => "rx",
+            SortKey::TxRate => "tx",
+            SortKey::Total => "total",
+            SortKey::Errors => "errors",
+        }
+    }
+
+    /// What ports are ranked by, highest first
+    fn value(self, m: &PortMetrics) -> f64 {
+        match self {
+            SortKey::Name => 0.0,
+            SortKey::RxRate => m.rx_bytes_per_sec,
+            SortKey::TxRate => m.tx_bytes_per_sec,
+            SortKey::Total => m.rx_bytes_per_sec + m.tx_bytes_per_sec,
+            SortKey::Errors => m.error_rate,
+        }
+    }
+}
+
+/// Parses a footer label such as `total`
+impl std::str::FromStr for SortKey {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            SortKey::Name,
+            SortKey::RxRate,
+            SortKey::TxRate,
+            SortKey::Total,
+            SortKey::Errors,
+        ]
+        .into_iter()
+        .find(|key| key.label() == s)
+        .ok_or(())
+    }
+}
+
+fn port_exists(adapters: &[AdapterInfo], adapter_name: &str, port_number: u16) -> bool {
+    adapters
+        .iter()
+        .filter(|adapter| adapter.name == adapter_name)
+        .any(|adapter| adapter.ports.iter().any(|p| p.port_number == port_number))
+}
+
+/// One row of the main table
+#[derive(Debug, PartialEq)]
+enum TableRow<'a> {
+    /// Adapter header (not selectable)
+    Adapter(&'a AdapterInfo),
+    /// Note for an adapter that exposes no ports
+    NoPorts,
+    /// A selectable port
+    Port(&'a AdapterInfo, &'a PortInfo),
+    /// Stand-in for an adapter's collapsed DOWN ports
+    DownSummary(usize),
+}
+
+/// Lay out the main table; drawing and navigation both index into this
+fn build_rows<'a>(
+    adapters: &'a [AdapterInfo],
+    metrics: &MetricsCollector,
+    state: &AppState,
+) -> Vec<TableRow<'a>> {
+    if state.sort_key != SortKey::Name {
+        return build_flat_rows(adapters, metrics, state);
+    }
+
+    let mut rows = Vec::new();
+
+    for adapter in adapters.iter().filter(|a| state.shows_adapter(&a.name)) {
+        rows.push(TableRow::Adapter(adapter));
+        if adapter.ports.is_empty() {
+            rows.push(TableRow::NoPorts);
+        }
+
+        let mut down_count = 0;
+        for port in &adapter.ports {
+            if state.collapse_down && port.state == PortState::Down {
+                down_count += 1;
+            } else {
+                rows.push(TableRow::Port(adapter, port));
+            }
+        }
+        if down_count > 0 {
+            rows.push(TableRow::DownSummary(down_count));
+        }
+    }
+
+    rows
+}
+
+/// Every port in one list ranked by the sort key, without adapter headers;
+/// collapsed DOWN ports share a single summary row at the end
+fn build_flat_rows<'a>(
+    adapters: &'a [AdapterInfo],
+    metrics: &MetricsCollector,
+    state: &AppState,
+) -> Vec<TableRow<'a>> {
+    let mut down_count = 0;
+    let mut ranked = Vec::new();
+    for adapter in adapters.iter().filter(|a| state.shows_adapter(&a.name)) {
+        for port in &adapter.ports {
+            if state.collapse_down && port.state == PortState::Down {
+                down_count += 1;
+                continue;
+            }
+            let value = row_metrics(metrics, &adapter.name, port.port_number)
+                .map_or(0.0, |m| state.sort_key.value(&m));
+            ranked.push((value, TableRow::Port(adapter, port)));
+        }
+    }
+    // Stable, so ties keep adapter and port order
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut rows: Vec<TableRow> = ranked.into_iter().map(|(_, row)| row).collect();
+    if down_count > 0 {
+        rows.push(TableRow::DownSummary(down_count));
+    }
+    rows
+}
+
+/// Width of the leading adapter column of the flat (sorted) table; `None`
+/// while ports are grouped under adapter headers
+fn flat_adapter_width(adapters: &[AdapterInfo], state: &AppState) -> Option<u16> {
+    (state.sort_key != SortKey::Name).then(|| {
+        let longest = adapters.iter().map(|a| a.name.chars().count()).max();
+        u16::try_from(longest.unwrap_or(0).max("Adapter".len()) + 1).unwrap_or(u16::MAX)
+    })
+}
+
+/// Move to the next longer (or shorter) refresh step, clamped to the step range
+fn adjust_interval(current: Duration, longer: bool) -> Duration {
+    let current_ms = u64::try_from(current.as_millis()).unwrap_or(u64::MAX);
+    let step = if longer {
+        REFRESH_STEPS_MS.iter().find(|&&ms| ms > current_ms)
+    } else {
+        REFRESH_STEPS_MS.iter().rev().find(|&&ms| ms < current_ms)
+    };
+    let clamped = step.copied().unwrap_or_else(|| {
+        current_ms.clamp(
+            REFRESH_STEPS_MS[0],
+            REFRESH_STEPS_MS[REFRESH_STEPS_MS.len() - 1],
+        )
+    });
+    Duration::from_millis(clamped)
+}
+
+/// Next interval for the adaptive refresh, given the relative throughput
+/// change since the last refresh: one step faster during bursts, one step
+/// slower while steady, within the adaptive bounds
+fn adaptive_interval(current: Duration, change: f64) -> Duration {
+    let next = if change >= ADAPTIVE_BURST_CHANGE {
+        adjust_interval(current, false)
+    } else if change <= ADAPTIVE_IDLE_CHANGE {
+        adjust_interval(current, true)
+    } else {
+        current
+    };
+    next.clamp(ADAPTIVE_MIN_INTERVAL, ADAPTIVE_MAX_INTERVAL)
+}
+
+/// Format an interval compactly, e.g. `250ms` or `2s`
+fn format_interval(interval: Duration) -> String {
+    let ms = interval.as_millis();
+    if ms < 1_000 {
+        format!("{ms}ms")
+    } else if ms.is_multiple_of(1_000) {
+        format!("{}s", ms / 1_000)
+    } else {
+        format!("{:.1}s", interval.as_secs_f64())
+    }
+}
+
+/// Format how long ago metrics were refreshed, e.g. `0.2s` or `2m05s`
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 10 {
+        format!("{:.1}s", age.as_secs_f64())
+    } else if secs < 60 {
+        format!("{secs}s")
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Format how long ibtop has been monitoring, e.g. `42s`, `5m 03s` or `2h 05m`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        3_600..86_400 => format!("{}h {:02}m", secs / 3_600, secs % 3_600 / 60),
+        _ => format!("{}d {:02}h", secs / 86_400, secs % 86_400 / 3_600),
+    }
+}
+
+/// Whether metrics are so old that refreshing has evidently stalled
+fn is_stalled(age: Duration, interval: Duration) -> bool {
+    age > interval * STALL_INTERVALS
+}
+
+/// Keep the detail panel split within usable bounds
+fn clamp_detail_ratio(ratio: u16) -> u16 {
+    ratio.clamp(DETAIL_RATIO_MIN, DETAIL_RATIO_MAX)
+}
+
+/// Main draw function
+pub fn draw(
+    frame: &mut Frame,
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    hostname: &str,
+    state: &mut AppState,
+) {
+    state.frame_count += 1;
+    let adapters = &*ordered_adapters(adapters, metrics, state);
+    state.update_selectable_items(adapters, metrics);
+
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(if state.detail_expanded || state.show_alerts {
+            vec![
+                Constraint::Percentage(100 - state.detail_ratio),
+                Constraint::Percentage(state.detail_ratio),
+            ]
+        } else {
+            vec![Constraint::Min(0)]
+        })
+        .split(frame.area());
+
+    // Draw main table (always visible)
+    draw_main_table(frame, main_layout[0], adapters, metrics, hostname, state);
+
+    // The alert history takes the detail panel's place while shown
+    if main_layout.len() > 1 {
+        if state.show_alerts {
+            draw_alerts_panel(frame, main_layout[1], &state.alerts, state.theme);
+        } else {
+            draw_detail_panel(frame, main_layout[1], adapters, metrics, state);
+        }
+    }
+}
+
+/// Draw the alert history, newest first, colored by severity
+fn draw_alerts_panel(frame: &mut Frame, area: Rect, alerts: &AlertLog, theme: Theme) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted))
+        .title(Line::from(vec![Span::styled(
+            format!(" Alerts ({}) ", alerts.len()),
+            Style::default().fg(theme.warn).add_modifier(Modifier::BOLD),
+        )]));
+
+    if alerts.is_empty() {
+        let msg = Paragraph::new("No alerts yet")
+            .style(Style::default().fg(theme.muted))
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let lines: Vec<Line> = alerts
+        .newest_first()
+        .take(usize::from(area.height.saturating_sub(2)))
+        .map(|alert| {
+            let (label, color) = match alert.severity {
+                Severity::Info => ("INFO", theme.good),
+                Severity::Warning => ("WARN", theme.warn),
+                Severity::Critical => ("CRIT", theme.bad),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(" {}  ", format_alert_time(alert.timestamp)),
+                    Style::default().fg(theme.muted),
+                ),
+                Span::styled(
+                    format!("{label:<4}  "),
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:<12} ", alert.source),
+                    Style::default().fg(theme.accent),
+                ),
+                Span::styled(alert.message.clone(), Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// Local wall-clock time of an alert, `HH:MM:SS`
+#[allow(clippy::cast_possible_truncation)] // Whole seconds since the epoch fit in i64
+fn format_alert_time(timestamp: f64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0).map_or_else(
+        || "--:--:--".to_string(),
+        |time| {
+            time.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        },
+    )
+}
+
+/// Calculate total throughput across all active ports
+pub fn calculate_totals(adapters: &[AdapterInfo], metrics: &MetricsCollector) -> (f64, f64) {
+    let mut total_rx = 0.0;
+    let mut total_tx = 0.0;
+    for adapter in adapters {
+        for port in &adapter.ports {
+            if let Some(m) = metrics.get_metrics(&adapter.name, port.port_number) {
+                total_rx += m.rx_bytes_per_sec;
+                total_tx += m.tx_bytes_per_sec;
+            }
+        }
+    }
+    (total_rx, total_tx)
+}
+
+/// Rates a table row shows: averages since the trip meter was zeroed while
+/// it runs, the latest rates otherwise
+fn row_metrics(
+    metrics: &MetricsCollector,
+    adapter_name: &str,
+    port_number: u16,
+) -> Option<PortMetrics> {
+    match metrics.trip(adapter_name, port_number) {
+        Some(trip) => Some(trip.rates),
+        None => metrics.get_metrics(adapter_name, port_number).cloned(),
+    }
+}
+
+/// Throughput summed over the rows, which the Share column divides by
+fn rows_total(adapters: &[AdapterInfo], metrics: &MetricsCollector) -> f64 {
+    adapters
+        .iter()
+        .flat_map(|adapter| {
+            adapter
+                .ports
+                .iter()
+                .filter_map(|port| row_metrics(metrics, &adapter.name, port.port_number))
+        })
+        .map(|m| m.rx_bytes_per_sec + m.tx_bytes_per_sec)
+        .sum()
+}
+
+/// Node-wide aggregates shown in the summary strip
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct NodeSummary {
+    total_rx: f64,
+    total_tx: f64,
+    /// Errors per second over all ports
+    error_rate: f64,
+    /// Ports that are ACTIVE, and that are DOWN (INIT and ARMED are neither)
+    active_ports: usize,
+    down_ports: usize,
+    /// Mean utilization (percent) over ports that have metrics
+    avg_utilization: f64,
+    /// Busiest single port, max(RX, TX)
+    max_port_rate: f64,
+}
+
+/// Aggregate per-port metrics in a single pass; the rates are the ones the
+/// table rows show, see `row_metrics`
+fn summarize<'a>(
+    ports: impl IntoIterator<Item = (&'a PortInfo, Option<PortMetrics>)>,
+) -> NodeSummary {
+    let mut summary = NodeSummary::default();
+    let mut measured = 0usize;
+    let mut utilization_sum = 0.0;
+
+    for (port, metrics) in ports {
+        match port.state {
+            PortState::Active => summary.active_ports += 1,
+            PortState::Down => summary.down_ports += 1,
+            _ => {}
+        }
+        let Some(m) = metrics else { continue };
+        summary.total_rx += m.rx_bytes_per_sec;
+        summary.total_tx += m.tx_bytes_per_sec;
+        summary.error_rate += m.error_rate;
+        summary.max_port_rate = summary
+            .max_port_rate
+            .max(m.rx_bytes_per_sec.max(m.tx_bytes_per_sec));
+        utilization_sum += utilization_percent(&m, port_max_rate(port));
+        measured += 1;
+    }
+
+    if measured > 0 {
+        summary.avg_utilization = utilization_sum / measured as f64;
+    }
+
+    summary
+}
+
+/// The summary strip's aggregates over every port of `adapters`
+fn node_summary(adapters: &[AdapterInfo], metrics: &MetricsCollector) -> NodeSummary {
+    summarize(adapters.iter().flat_map(|adapter| {
+        adapter
+            .ports
+            .iter()
+            .map(|port| (port, row_metrics(metrics, &adapter.name, port.port_number)))
+    }))
+}
+
+/// A port row's cells; over the error threshold, each cell's own colour is
+/// replaced so the whole row reads red
+fn port_cells(columns: &[Column], ctx: &PortRowContext, over_errors: bool) -> Vec<Cell<'static>> {
+    columns
+        .iter()
+        .map(|c| {
+            let cell = c.render(ctx);
+            if over_errors {
+                cell.style(ctx.theme.error_row)
+            } else {
+                cell
+            }
+        })
+        .collect()
+}
+
+/// RX and TX summed over an adapter's ports, the same rates its port rows show
+fn adapter_totals(adapter: &AdapterInfo, metrics: &MetricsCollector) -> (f64, f64) {
+    adapter
+        .ports
+        .iter()
+        .filter_map(|port| row_metrics(metrics, &adapter.name, port.port_number))
+        .fold((0.0, 0.0), |(rx, tx), m| {
+            (rx + m.rx_bytes_per_sec, tx + m.tx_bytes_per_sec)
+        })
+}
+
+/// Cells of an adapter header row: its name, firmware version and the
+/// adapter's RX/TX subtotals under the rate columns
+fn adapter_header_cells(
+    adapter: &AdapterInfo,
+    metrics: &MetricsCollector,
+    state: &AppState,
+    columns: &[Column],
+    label_col: usize,
+    is_selected: bool,
+) -> Vec<Cell<'static>> {
+    let theme = state.theme;
+    // Yellow flags a warning, spelled out in the detail view
+    let header_style = if is_selected {
+        Style::default()
+            .fg(theme.accent)
+            .add_modifier(Modifier::BOLD)
+    } else if !warnings::for_adapter(adapter).is_empty() {
+        Style::default().fg(theme.warn).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.good).add_modifier(Modifier::BOLD)
+    };
+    let mut cells = vec![Cell::from(""); columns.len() + 1];
+    cells[label_col] = Cell::from(format!(" {} ", adapter.name)).style(header_style);
+    // Firmware version alongside, for support tickets
+    let fw_col = match (&adapter.fw_ver, label_col + 1 < columns.len()) {
+        (Some(fw_ver), true) => {
+            cells[label_col + 1] = Cell::from(fw_ver.clone())
+                .style(Style::default().fg(theme.text).add_modifier(Modifier::DIM));
+            Some(label_col + 1)
+        }
+        _ => None,
+    };
+
+    // Before the first rate nothing is summed, just as the ports show "--"
+    let measured = adapter.ports.iter().any(|port| {
+        metrics
+            .get_metrics(&adapter.name, port.port_number)
+            .is_some()
+    });
+    if !measured {
+        return cells;
+    }
+    let (rx, tx) = adapter_totals(adapter, metrics);
+    for (i, column) in columns.iter().enumerate() {
+        let (total, color) = match column {
+            Column::Rx => (rx, theme.rx),
+            Column::Tx => (tx, theme.tx),
+            _ => continue,
+        };
+        // Never cover the name or firmware with a subtotal
+        if i != label_col && Some(i) != fw_col {
+            cells[i] = Cell::from(state.rate_display.format(total))
+                .style(Style::default().fg(color).add_modifier(Modifier::BOLD));
+        }
+    }
+    cells
+}
+
+/// Build the main table (without its border), highlighting `selected`
+///
+/// Shared by the screen and the plain-text export so both show the same rows.
+fn build_table<'a>(
+    adapters: &'a [AdapterInfo],
+    metrics: &'a MetricsCollector,
+    state: &AppState,
+    selected: Option<usize>,
+) -> Table<'a> {
+    let theme = state.theme;
+    let node_total = rows_total(adapters, metrics);
+
+    let columns = &state.visible_columns();
+    // Sorted flat, each port row names its adapter in a leading column
+    let adapter_width = flat_adapter_width(adapters, state);
+    let lead = usize::from(adapter_width.is_some());
+    // Adapter names and notes go in the second column when there is one
+    let label_col = 1.min(columns.len() - 1);
+    let label_row = |cell: Cell<'static>| {
+        let mut cells = vec![Cell::from(""); columns.len() + 1 + lead];
+        cells[label_col + lead] = cell;
+        Row::new(cells)
+    };
+
+    let mut rows: Vec<Row> = Vec::new();
+
+    if let Some(note) = empty_table_note(adapters, state) {
+        rows.push(label_row(note));
+    }
+
+    for (row_idx, table_row) in build_rows(adapters, metrics, state).into_iter().enumerate() {
+        let is_selected = selected == Some(row_idx);
+        match table_row {
+            TableRow::Adapter(adapter) => {
+                rows.push(
+                    Row::new(adapter_header_cells(
+                        adapter,
+                        metrics,
+                        state,
+                        columns,
+                        label_col,
+                        is_selected,
+                    ))
+                    .height(1),
+                );
+            }
+            TableRow::NoPorts => {
+                rows.push(label_row(
+                    Cell::from("no ports").style(
+                        Style::default()
+                            .fg(theme.muted)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                ));
+            }
+            TableRow::DownSummary(count) => {
+                // Shaped like the state indicator so it fits a narrow column
+                rows.push(label_row(
+                    Cell::from(format!("○DOWN×{count}")).style(Style::default().fg(theme.bad)),
+                ));
+            }
+            TableRow::Port(adapter, port) => {
+                let port_metrics = row_metrics(metrics, &adapter.name, port.port_number);
+                let ctx = PortRowContext {
+                    port,
+                    metrics: port_metrics.as_ref(),
+                    history: metrics.get_history(&adapter.name, port.port_number),
+                    // A frozen frame count stops the state pulse
+                    frame_count: if state.degraded { 0 } else { state.frame_count },
+                    split_sparkline: state.split_sparkline,
+                    sparkline_clip: state.sparkline_clip,
+                    node_total,
+                    rate_display: state.rate_display,
+                    theme,
+                    falls_short: !state.expectations.check(&adapter.name, port).is_empty(),
+                };
+
+                let row_style = if is_selected {
+                    state.theme.selected
+                } else if state.is_highlighted(&adapter.name, port.port_number) {
+                    state.theme.highlight
+                } else {
+                    Style::default()
+                };
+
+                let over_errors = port_metrics
+                    .as_ref()
+                    .is_some_and(|m| state.over_error_threshold(m.error_rate));
+                let mut cells = port_cells(columns, &ctx, over_errors);
+                if lead > 0 {
+                    cells.insert(
+                        0,
+                        Cell::from(adapter.name.clone()).style(Style::default().fg(theme.good)),
+                    );
+                }
+                // The selection marker wins; the detail view spells the warning out
+                let packet_anomaly = port_metrics.as_ref().and_then(|m| {
+                    warnings::detect_packet_anomaly(&adapter.name, port.port_number, m)
+                });
+                cells.push(match (is_selected, packet_anomaly) {
+                    (true, _) => Cell::from("◀").style(Style::default().fg(theme.accent)),
+                    (false, Some(_)) => Cell::from("⚠").style(Style::default().fg(theme.warn)),
+                    (false, None) => Cell::from(" "),
+                });
+
+                rows.push(Row::new(cells).style(row_style).height(1));
+            }
+        }
+    }
+
+    let (header, widths) = table_header(columns, adapter_width, state.rate_display, theme);
+    Table::new(rows, widths).header(header)
+}
+
+/// Why the table has no rows, if it has none
+fn empty_table_note(adapters: &[AdapterInfo], state: &AppState) -> Option<Cell<'static>> {
+    let theme = state.theme;
+    if adapters.is_empty() {
+        Some(Cell::from("No InfiniBand adapters found").style(Style::default().fg(theme.warn)))
+    } else if !adapters.iter().any(|a| state.shows_adapter(&a.name)) {
+        // Short enough for the label column; the footer shows the filter
+        Some(
+            Cell::from("no match").style(
+                Style::default()
+                    .fg(theme.warn)
+                    .add_modifier(Modifier::ITALIC),
+            ),
+        )
+    } else {
+        None
+    }
+}
+
+/// Header row and column widths, led by the adapter column when the table
+/// is flat, and followed by the selection indicator
+fn table_header(
+    columns: &[Column],
+    adapter_width: Option<u16>,
+    rates: RateDisplay,
+    theme: Theme,
+) -> (Row<'static>, Vec<Constraint>) {
+    let widths: Vec<Constraint> = adapter_width
+        .into_iter()
+        .chain(columns.iter().map(|c| c.width()))
+        .map(Constraint::Length)
+        .chain(std::iter::once(Constraint::Length(2))) // Selection indicator
+        .collect();
+
+    let header_style = Style::default().fg(theme.text).add_modifier(Modifier::BOLD);
+
+    let header_cells: Vec<Cell> = adapter_width
+        .map(|_| "Adapter".to_string())
+        .into_iter()
+        .chain(columns.iter().map(|c| c.title_for(rates)))
+        .map(|title| Cell::from(title).style(header_style))
+        .chain(std::iter::once(Cell::from("").style(header_style)))
+        .collect();
+
+    (Row::new(header_cells).height(1).bottom_margin(0), widths)
+}
+
+/// Render the table as currently configured (columns, collapsed ports) into
+/// aligned plain text, headed by the totals line
+pub fn plain_text_table(
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    hostname: &str,
+    state: &AppState,
+) -> String {
+    let adapters = &*ordered_adapters(adapters, metrics, state);
+    let summary = node_summary(adapters, metrics);
+    let (total_rx, total_tx) = (summary.total_rx, summary.total_tx);
+    let table = build_table(adapters, metrics, state, None);
+
+    // Column widths plus one cell of spacing each, and the indicator column
+    let width = flat_adapter_width(adapters, state)
+        .into_iter()
+        .chain(state.visible_columns().iter().map(|c| c.width()))
+        .map(|width| width + 1)
+        .sum::<u16>()
+        + 2;
+    let height = build_rows(adapters, metrics, state).len().max(1) as u16 + 1;
+    let area = Rect::new(0, 0, width, height);
+    let mut buffer = Buffer::empty(area);
+    table.render(area, &mut buffer);
+
+    let rates = state.rate_display;
+    let suffix = rates.suffix();
+    let node = match &state.title {
+        Some(label) => format!("{label} ({hostname})"),
+        None => hostname.to_string(),
+    };
+    let mut text = format!(
+        "ibtop @ {node}  RX{suffix} {}  TX{suffix} {}\n",
+        rates.format(total_rx),
+        rates.format(total_tx)
+    );
+    push_buffer_text(&mut text, &buffer);
+    text
+}
+
+/// Render one full frame at a fixed size and return it as plain text
+///
+/// Used by `--render-once` for reproducible screenshots; colors are lost,
+/// layout and text are exactly what the terminal would show.
+pub fn render_to_text(
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    hostname: &str,
+    state: &mut AppState,
+    width: u16,
+    height: u16,
+) -> std::io::Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|frame| draw(frame, adapters, metrics, hostname, state))?;
+
+    let mut text = String::new();
+    push_buffer_text(&mut text, terminal.backend().buffer());
+    Ok(text)
+}
+
+/// Append every line of `buffer`, without trailing blanks
+fn push_buffer_text(text: &mut String, buffer: &Buffer) {
+    let area = buffer.area;
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right())
+            .map(|x| buffer[(x, y)].symbol())
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+}
+
+/// Draw the main table with sparklines
+#[allow(clippy::too_many_lines)]
+fn draw_main_table(
+    frame: &mut Frame,
+    area: Rect,
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    hostname: &str,
+    state: &AppState,
+) {
+    let theme = state.theme;
+    // The header totals are the strip's, so both follow the trip meter
+    let summary = node_summary(adapters, metrics);
+    let (total_rx, total_tx) = (summary.total_rx, summary.total_tx);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    let mut title = vec![
+        Span::styled(
+            " ibtop ",
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("@ ", Style::default().fg(theme.muted)),
+    ];
+    // A custom title leads, so screenshots name the node the way its
+    // owner does; the hostname stays for reference
+    match &state.title {
+        Some(label) => title.extend([
+            Span::styled(
+                label.clone(),
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" ({hostname})"), Style::default().fg(theme.muted)),
+        ]),
+        None => title.push(Span::styled(hostname, Style::default().fg(theme.text))),
+    }
+    title.extend([
+        Span::styled("  │  ", Style::default().fg(theme.muted)),
+        Span::styled("▲ ", Style::default().fg(theme.rx)),
+        Span::styled(
+            state.rate_display.format(total_rx) + state.rate_display.suffix(),
+            Style::default().fg(theme.rx),
+        ),
+        Span::styled("  ▼ ", Style::default().fg(theme.tx)),
+        Span::styled(
+            state.rate_display.format(total_tx) + state.rate_display.suffix(),
+            Style::default().fg(theme.tx),
+        ),
+        Span::styled(" ", Style::default()),
+    ]);
+    if let Some(limit) = state
+        .alert_total
+        .filter(|limit| alerts::over_total_limit(total_rx + total_tx, *limit))
+    {
+        title.push(Span::styled(
+            format!(" ⚠ NODE OVER {} ", format_bytes_per_sec(limit / 8.0)),
+            theme.alarm,
+        ));
+        title.push(Span::styled(" ", Style::default()));
+    }
+    if state.paused {
+        title.push(Span::styled(" PAUSED ", theme.notice));
+        title.push(Span::styled(" ", Style::default()));
+    }
+
+    let table = build_table(adapters, metrics, state, Some(state.selected_row)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.muted))
+            .title(Line::from(title))
+            .title_style(Style::default()),
+    );
+
+    frame.render_widget(table, chunks[0]);
+
+    draw_summary_strip(frame, chunks[1], &summary, state);
+
+    // Help footer - context-sensitive
+    let mut help_spans = if state.detail_expanded {
+        vec![
+            Span::styled(" ", Style::default().fg(theme.muted)),
+            Span::styled("Tab", Style::default().fg(theme.accent)),
+            Span::styled(" switch tab  ", Style::default().fg(theme.muted)),
+            Span::styled("Enter", Style::default().fg(theme.accent)),
+            Span::styled(" close  ", Style::default().fg(theme.muted)),
+            Span::styled("</>", Style::default().fg(theme.accent)),
+            Span::styled(" resize  ", Style::default().fg(theme.muted)),
+            Span::styled("w", Style::default().fg(theme.accent)),
+            Span::styled(" wall clock  ", Style::default().fg(theme.muted)),
+            Span::styled("l", Style::default().fg(theme.accent)),
+            Span::styled(" line rate  ", Style::default().fg(theme.muted)),
+            Span::styled("C", Style::default().fg(theme.accent)),
+            Span::styled(" carousel  ", Style::default().fg(theme.muted)),
+            Span::styled("j/k", Style::default().fg(theme.accent)),
+            Span::styled(" select port  ", Style::default().fg(theme.muted)),
+            Span::styled("q", Style::default().fg(theme.accent)),
+            Span::styled(" quit ", Style::default().fg(theme.muted)),
+        ]
+    } else {
+        vec![
+            Span::styled(" ", Style::default().fg(theme.muted)),
+            Span::styled("j/k", Style::default().fg(theme.accent)),
+            Span::styled(" navigate  ", Style::default().fg(theme.muted)),
+            Span::styled("Enter", Style::default().fg(theme.accent)),
+            Span::styled(" details  ", Style::default().fg(theme.muted)),
+            Span::styled("x", Style::default().fg(theme.accent)),
+            Span::styled(" split rx/tx  ", Style::default().fg(theme.muted)),
+            Span::styled("d", Style::default().fg(theme.accent)),
+            Span::styled(" collapse down  ", Style::default().fg(theme.muted)),
+            Span::styled("s", Style::default().fg(theme.accent)),
+            Span::styled(
+                format!(" sort:{}  ", state.sort_key.label()),
+                Style::default().fg(theme.muted),
+            ),
+            Span::styled("a", Style::default().fg(theme.accent)),
+            Span::styled(" alerts  ", Style::default().fg(theme.muted)),
+            Span::styled("e", Style::default().fg(theme.accent)),
+            Span::styled(" export  ", Style::default().fg(theme.muted)),
+            Span::styled("q", Style::default().fg(theme.accent)),
+            Span::styled(" quit ", Style::default().fg(theme.muted)),
+        ]
+    };
+
+    // Heartbeat first, so a stall shows even when the footer is cut off
+    if let Some(last_update) = state.last_update {
+        let age = last_update.elapsed();
+        // Old data is expected while paused, not a stall
+        let color = if is_stalled(age, state.refresh_interval) && !state.paused {
+            theme.bad
+        } else {
+            theme.muted
+        };
+        help_spans.insert(
+            0,
+            Span::styled(
+                format!(" updated {} ago │", format_age(age)),
+                Style::default().fg(color),
+            ),
+        );
+    }
+
+    if state.filter_editing || !state.filter.is_empty() {
+        let cursor = if state.filter_editing { "▏" } else { "" };
+        help_spans.insert(
+            1.min(help_spans.len()),
+            Span::styled(
+                format!(" /{}{cursor} │", state.filter),
+                Style::default().fg(theme.warn),
+            ),
+        );
+    }
+
+    if let Some(age) = metrics.reference_age() {
+        help_spans.insert(
+            1.min(help_spans.len()),
+            Span::styled(
+                format!(" trip {} (0 resets) │", format_age(age)),
+                Style::default().fg(theme.warn),
+            ),
+        );
+    }
+
+    help_spans.extend([
+        Span::styled(" +/-", Style::default().fg(theme.accent)),
+        Span::styled(
+            format!(
+                " every {}{} ",
+                format_interval(state.refresh_interval),
+                match (state.adaptive_refresh, state.degraded) {
+                    (true, true) => " (auto, degraded)",
+                    (true, false) => " (auto)",
+                    (false, true) => " (degraded)",
+                    (false, false) => "",
+                }
+            ),
+            Style::default().fg(theme.muted),
+        ),
+    ]);
+
+    let mut footer = vec![Line::from(help_spans)];
+    if let Some(notice) = state.active_notice() {
+        footer.push(Line::from(Span::styled(
+            format!(" {notice}"),
+            Style::default().fg(theme.warn),
+        )));
+    }
+
+    frame.render_widget(Paragraph::new(footer), chunks[2]);
+}
+
+/// One-line strip with node-wide sums, average and peak, and how many ports
+/// are up; errors and down ports stand out once there are any. The error
+/// rate and port counts stay when `S` hides the rest
+fn draw_summary_strip(frame: &mut Frame, area: Rect, summary: &NodeSummary, state: &AppState) {
+    let theme = state.theme;
+    let label = Style::default().fg(theme.muted);
+    let alarming =
+        |nonzero: bool| Style::default().fg(if nonzero { theme.bad } else { theme.text });
+    let mut spans = Vec::new();
+    if state.show_summary {
+        spans.extend(summary_aggregate_spans(summary, state.rate_display, theme));
+    }
+    spans.extend([
+        Span::styled(" errors ", label),
+        Span::styled(
+            format!("{:.1}/s", summary.error_rate),
+            alarming(summary.error_rate > 0.0),
+        ),
+        Span::styled("  ports ", label),
+        Span::styled(
+            summary.active_ports.to_string(),
+            Style::default().fg(theme.good),
+        ),
+        Span::styled(" active ", label),
+        Span::styled(
+            summary.down_ports.to_string(),
+            alarming(summary.down_ports > 0),
+        ),
+        Span::styled(" down", label),
+    ]);
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// The strip's sums, average and peak, which `S` hides
+fn summary_aggregate_spans(
+    summary: &NodeSummary,
+    rates: RateDisplay,
+    theme: Theme,
+) -> [Span<'static>; 9] {
+    let label = Style::default().fg(theme.muted);
+    let suffix = rates.suffix();
+    [
+        Span::styled(format!(" Σ RX{suffix} "), label),
+        Span::styled(
+            rates.format(summary.total_rx),
+            Style::default().fg(theme.rx),
+        ),
+        Span::styled(format!("  Σ TX{suffix} "), label),
+        Span::styled(
+            rates.format(summary.total_tx),
+            Style::default().fg(theme.tx),
+        ),
+        Span::styled("  avg load ", label),
+        Span::styled(
+            format!("{:.1}%", summary.avg_utilization),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(format!("  max port{suffix} "), label),
+        Span::styled(
+            rates.format(summary.max_port_rate),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(" ", label),
+    ]
+}
+
+/// Draw the detail panel with charts
+#[allow(clippy::too_many_lines)]
+fn draw_detail_panel(
+    frame: &mut Frame,
+    area: Rect,
+    adapters: &[AdapterInfo],
+    metrics: &MetricsCollector,
+    state: &AppState,
+) {
+    let theme = state.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.muted))
+        .title(Line::from(vec![
+            Span::styled(
+                if state.carousel_active() {
+                    " Detail View (carousel) "
+                } else {
+                    " Detail View "
+                },
+                Style::default().fg(theme.warn).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                if state.show_elapsed {
+                    format!("monitoring {} ", format_elapsed(state.started.elapsed()))
+                } else {
+                    String::new()
+                },
+                Style::default().fg(theme.muted),
+            ),
+        ]));
+
+    if let Some(((adapter_name, port_num), _)) = &state.vanished {
+        let msg = Paragraph::new(format!("{adapter_name}:{port_num} is no longer present"))
+            .style(Style::default().fg(theme.warn))
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    // Get selected port info
+    let Some((adapter_name, port_num)) = state.selected_port() else {
+        let msg = Paragraph::new("Select a port to view details")
+            .style(Style::default().fg(theme.muted))
+            .block(block);
+        frame.render_widget(msg, area);
+        return;
+    };
+    let port_info = adapters
+        .iter()
+        .find(|a| a.name == adapter_name)
+        .and_then(|a| a.ports.iter().find(|p| p.port_number == port_num));
+
+    let history = metrics.get_history(adapter_name, port_num);
+    let current_metrics = metrics.get_metrics(adapter_name, port_num);
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let stats_lines = match (port_info, current_metrics) {
+        (Some(port), Some(m)) => {
+            let trip = metrics.trip(adapter_name, port.port_number);
+            let mut lines = detail_stats_lines(
+                adapters,
+                adapter_name,
+                port,
+                m,
+                history,
+                trip.as_ref(),
+                state,
+            );
+            if let Some(delta) = metrics
+                .interval_delta(adapter_name, port.port_number)
+                .filter(|_| state.show_interval_delta)
+            {
+                lines.push(interval_delta_line(delta, theme));
+            }
+            lines
+        }
+        _ => Vec::new(),
+    };
+
+    // Layout for detail panel
+    let detail_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),                               // Tab bar
+            Constraint::Length(stats_lines.len().max(3) as u16), // Stats summary
+            Constraint::Min(0),                                  // Chart area
+        ])
+        .split(inner);
+
+    // Tab bar
+    let tabs = Tabs::new(DETAIL_TABS.to_vec())
+        .select(state.detail_tab)
+        .style(Style::default().fg(theme.muted))
+        .highlight_style(
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        )
+        .divider(Span::raw(" | "));
+
+    frame.render_widget(tabs, detail_layout[0]);
+
+    frame.render_widget(Paragraph::new(stats_lines), detail_layout[1]);
+
+    // Chart area
+    if state.detail_tab == RAW_TAB {
+        draw_raw_counters(frame, detail_layout[2], state);
+    } else if state.detail_tab == VPORT_TAB {
+        let entries = state
+            .raw_counters
+            .as_ref()
+            .map_or(&[][..], |(_, entries)| entries.as_slice());
+        draw_vports(
+            frame,
+            detail_layout[2],
+            &parse_vport_counters(entries),
+            theme,
+        );
+    } else if state.detail_tab == INFO_TAB {
+        if let Some(port) = port_info {
+            let details = state
+                .link_details
+                .as_ref()
+                .filter(|((shown, shown_port), _)| shown == adapter_name && *shown_port == port_num)
+                .map(|(_, details)| details);
+            draw_info(frame, detail_layout[2], adapter_name, port, details, theme);
+        }
+    } else if let Some(h) = history {
+        let line_rate = port_info
+            .filter(|_| state.line_rate_band)
+            .map(port_max_rate);
+        draw_chart(
+            frame,
+            detail_layout[2],
+            h,
+            state.detail_tab,
+            state.wall_clock,
+            state.time_unit,
+            line_rate,
+            state.smooth_chart.then_some(CHART_EMA_ALPHA),
+            state.mirror_chart,
+            theme,
+        );
+    } else {
+        let msg = Paragraph::new("Collecting data...").style(Style::default().fg(theme.muted));
+        frame.render_widget(msg, detail_layout[2]);
+    }
+}
+
+/// Bond members, then the adapter's warnings and the port's packet anomaly
+fn warning_lines(
+    adapter: &AdapterInfo,
+    port_number: u16,
+    m: &PortMetrics,
+    theme: Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if !adapter.bond_members.is_empty() {
+        lines.push(bond_members_line(&adapter.bond_members, theme));
+    }
+    let packet_anomaly = warnings::detect_packet_anomaly(&adapter.name, port_number, m);
+    for warning in warnings::for_adapter(adapter)
+        .into_iter()
+        .chain(packet_anomaly)
+    {
+        lines.push(Line::from(Span::styled(
+            format!("⚠ {}: {}", warning.adapter, warning.message),
+            Style::default().fg(theme.warn),
+        )));
+    }
+    lines
+}
+
+/// Stats lines above the detail chart: rates and odometers, how the current
+/// rate compares to the average, then any warnings, the trip meter and the
+/// baseline delta
+fn detail_stats_lines(
+    adapters: &[AdapterInfo],
+    adapter_name: &str,
+    port: &PortInfo,
+    m: &PortMetrics,
+    history: Option<&PortHistory>,
+    trip: Option<&TripMeter>,
+    state: &AppState,
+) -> Vec<Line<'static>> {
+    let theme = state.theme;
+    let mut stats_line = Line::from(vec![
+        Span::styled(format!("{adapter_name}:"), Style::default().fg(theme.good)),
+        Span::styled(
+            format!("{port_num} ", port_num = port.port_number),
+            Style::default().fg(theme.accent),
+        ),
+        Span::styled(
+            format!("{} ", port.state),
+            Style::default().fg(match port.state {
+                PortState::Active => theme.good,
+                PortState::Down => theme.bad,
+                PortState::Armed => theme.bringup,
+                PortState::Init | PortState::Unknown => theme.warn,
+            }),
+        ),
+        Span::styled(
+            format!(
+                "LID {} SM {} ",
+                format_lid(port.lid),
+                format_lid(port.sm_lid)
+            ),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled("| ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!("RX{}: ", state.rate_display.suffix()),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled(
+            state.rate_display.format(m.rx_bytes_per_sec),
+            Style::default().fg(theme.rx),
+        ),
+        Span::styled(
+            format!(" TX{}: ", state.rate_display.suffix()),
+            Style::default().fg(theme.muted),
+        ),
+        Span::styled(
+            state.rate_display.format(m.tx_bytes_per_sec),
+            Style::default().fg(theme.tx),
+        ),
+    ]);
+    stats_line.spans.extend(packet_size_spans(m, theme));
+    if let Some(h) = history {
+        stats_line.spans.extend(odometer_spans(h, theme));
+    }
+
+    let mut stats_lines = vec![stats_line];
+    if let Some(h) = history {
+        let mut line = divergence_line(
+            m.rx_bytes_per_sec + m.tx_bytes_per_sec,
+            h.avg_throughput(),
+            state.rate_display,
+            theme,
+        );
+        line.spans.extend(burstiness_spans(h, theme));
+        stats_lines.push(line);
+    }
+    if state.detail_tab == ERRORS_TAB {
+        stats_lines.push(error_split_line(m, port_max_rate(port), theme));
+        stats_lines.extend(counters::counter_lines(
+            &port.counters,
+            &state.error_counters,
+            state.counter_notation,
+            theme,
+        ));
+    }
+    if sustained_over_line_rate(m, history, port_max_rate(port)) {
+        stats_lines.push(Line::from(Span::styled(
+            "⚠ Throughput exceeds the link's line rate - check the counter data multiplier",
+            Style::default().fg(theme.bad).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    if port.state == PortState::Down && port.phys_state == PhysState::Polling {
+        stats_lines.push(Line::from(Span::styled(
+            "○ No cable or link partner detected (phys state Polling)",
+            Style::default().fg(theme.bad),
+        )));
+    }
+
+    for shortfall in state.expectations.check(adapter_name, port) {
+        stats_lines.push(Line::from(Span::styled(
+            format!("✗ Not as expected: {shortfall}"),
+            Style::default().fg(theme.bad).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    if let Some(adapter) = adapters.iter().find(|a| a.name == adapter_name) {
+        stats_lines.extend(warning_lines(adapter, port.port_number, m, theme));
+    }
+
+    if let Some(trip) = trip {
+        stats_lines.push(trip_line(trip, state.rate_display, theme));
+    }
+    if let Some(baseline) = &state.baseline {
+        stats_lines.push(baseline_line(baseline, adapter_name, port, theme));
+    }
+
+    stats_lines
+}
+
+/// Instantaneous against history-averaged throughput (RX + TX), e.g.
+/// "now 18.2 / avg 12.1 GB/s (+50%)", to tell a spike from the norm
+fn divergence_line(now: f64, avg: f64, rates: RateDisplay, theme: Theme) -> Line<'static> {
+    let (now, avg) = (rates.divide(now), rates.divide(avg));
+    // Both values in the unit that suits the larger one
+    let peak = now.max(avg);
+    let (scaled_peak, unit) = auto_scale_throughput(peak);
+    let scale = if peak > 0.0 { scaled_peak / peak } else { 1.0 };
+
+    let mut spans = vec![
+        Span::styled("now ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!("{:.1}", now * scale),
+            Style::default().fg(theme.text),
+        ),
+        Span::styled(" / avg ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!("{:.1} {unit}{}", avg * scale, rates.suffix()),
+            Style::default().fg(theme.text),
+        ),
+    ];
+    if let Some(percent) = divergence_percent(now, avg) {
+        let color = if percent.abs() >= 50.0 {
+            theme.warn
+        } else {
+            theme.muted
+        };
+        spans.push(Span::styled(
+            format!(" ({percent:+.0}%)"),
+            Style::default().fg(color),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Peak/average throughput ratio, e.g. "│ peak/avg 3.2×"; empty while idle
+fn burstiness_spans(history: &PortHistory, theme: Theme) -> Vec<Span<'static>> {
+    let Some(ratio) = history.burstiness() else {
+        return Vec::new();
+    };
+    vec![
+        Span::styled(" │ peak/avg ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!("{ratio:.1}×"),
+            Style::default().fg(columns::burstiness_color(ratio, theme)),
+        ),
+    ]
+}
+
+/// Utilization (percent) from which credit waits mean the fabric, not the
+/// host, is holding a port back
+const CONGESTED_UTILIZATION: f64 = 70.0;
+
+/// Whether a port is busy and still waiting for credits: the clearest sign
+/// of fabric congestion
+fn fabric_congested(m: &PortMetrics, max_rate: f64) -> bool {
+    m.xmit_wait_rate > 0.0 && utilization_percent(m, max_rate) >= CONGESTED_UTILIZATION
+}
+
+/// Physical errors, congestion discards and credit waits per second, kept
+/// apart: the first points at cables and optics, the others at the fabric
+/// being full
+fn error_split_line(m: &PortMetrics, max_rate: f64, theme: Theme) -> Line<'static> {
+    let rate = |value: f64, alarm: Color| {
+        let color = if value > 0.0 { alarm } else { theme.text };
+        Span::styled(format!("{value:.1}/s"), Style::default().fg(color))
+    };
+    let mut spans = vec![
+        Span::styled("Errors ", Style::default().fg(theme.muted)),
+        rate(m.error_rate, theme.bad),
+        Span::styled(" physical  Discards ", Style::default().fg(theme.muted)),
+        rate(m.discard_rate, theme.warn),
+        Span::styled(" congestion  Wait ", Style::default().fg(theme.muted)),
+    ];
+    if fabric_congested(m, max_rate) {
+        let congested = Style::default().fg(theme.tx).add_modifier(Modifier::BOLD);
+        spans.push(Span::styled(
+            format!("{:.0}/s", m.xmit_wait_rate),
+            congested,
+        ));
+        spans.push(Span::styled(" ticks ◆ fabric congested", congested));
+    } else {
+        spans.push(rate(m.xmit_wait_rate, theme.warn));
+        spans.push(Span::styled(" ticks", Style::default().fg(theme.muted)));
+    }
+    Line::from(spans)
+}
+
+/// How far `now` is above (+) or below (-) `avg`, in percent; `None` while
+/// the average is zero
+fn divergence_percent(now: f64, avg: f64) -> Option<f64> {
+    (avg > 0.0).then(|| (now - avg) / avg * 100.0)
+}
+
+/// Odometer windows shown next to the current rates
+const ODOMETER_WINDOWS: &[(&str, u64)] = &[("1s", 1), ("1m", 60), ("1h", 3_600)];
+
+/// Bytes moved over the last second, minute and hour; "≥" marks windows
+/// longer than the recorded history
+fn odometer_spans(history: &PortHistory, theme: Theme) -> Vec<Span<'static>> {
+    let mut spans = vec![Span::styled(" |", Style::default().fg(theme.muted))];
+    for (label, secs) in ODOMETER_WINDOWS {
+        let (bytes, complete) = history.bytes_over(Duration::from_secs(*secs));
+        let prefix = if complete { "" } else { "≥" };
+        spans.push(Span::styled(
+            format!(" {label} "),
+            Style::default().fg(theme.muted),
+        ));
+        spans.push(Span::styled(
+            format!("{prefix}{}", format_bytes(bytes as u64)),
+            Style::default().fg(theme.text),
+        ));
+    }
+    spans
+}
+
+/// Member links of a bond, each colored by its state
+fn bond_members_line(members: &[(String, PortState)], theme: Theme) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        "Bond members:",
+        Style::default().fg(theme.muted),
+    )];
+    for (name, state) in members {
+        let (text, color) = match state {
+            PortState::Active => ("up", theme.good),
+            PortState::Down => ("DOWN", theme.bad),
+            // Bond slaves only report up or down
+            PortState::Init | PortState::Armed | PortState::Unknown => ("?", theme.warn),
+        };
+        spans.push(Span::styled(
+            format!(" {name} "),
+            Style::default().fg(theme.text),
+        ));
+        spans.push(Span::styled(text, Style::default().fg(color)));
+    }
+    Line::from(spans)
+}
+
+/// Bytes and errors accrued by a port since the baseline
+fn baseline_line(
+    baseline: &Baseline,
+    adapter_name: &str,
+    port: &PortInfo,
+    theme: Theme,
+) -> Line<'static> {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(theme.muted));
+
+    let Some(delta) = baseline.delta(adapter_name, port) else {
+        return Line::from(label("Since baseline: port not in baseline"));
+    };
+
+    let error_style =
+        |count: u64| Style::default().fg(if count > 0 { theme.bad } else { theme.muted });
+
+    Line::from(vec![
+        label("Since baseline: RX "),
+        Span::styled(format_bytes(delta.rx_bytes), Style::default().fg(theme.rx)),
+        label("  TX "),
+        Span::styled(format_bytes(delta.tx_bytes), Style::default().fg(theme.tx)),
+        label("  RX errors "),
+        Span::styled(delta.rx_errors.to_string(), error_style(delta.rx_errors)),
+        label("  TX discards "),
+        Span::styled(
+            delta.tx_discards.to_string(),
+            error_style(delta.tx_discards),
+        ),
+    ])
+}
+
+/// Raw counts between the last two samples, e.g. "Δ per interval: RX
+/// 1048576 B 512 pkts  TX 0 B 0 pkts"; plain numbers, easier to check
+/// against a traffic generator than rates
+fn interval_delta_line(delta: &PortCounters, theme: Theme) -> Line<'static> {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(theme.muted));
+    Line::from(vec![
+        label("Δ per interval: RX "),
+        Span::styled(
+            format!("{} B {} pkts", delta.rx_bytes, delta.rx_packets),
+            Style::default().fg(theme.rx),
+        ),
+        label("  TX "),
+        Span::styled(
+            format!("{} B {} pkts", delta.tx_bytes, delta.tx_packets),
+            Style::default().fg(theme.tx),
+        ),
+    ])
+}
+
+/// Average RX / TX packet size, e.g. "avg pkt: 4.0KB / 64B", to tell many
+/// small messages from few large ones
+fn packet_size_spans(m: &PortMetrics, theme: Theme) -> [Span<'static>; 2] {
+    [
+        Span::styled(" | avg pkt: ", Style::default().fg(theme.muted)),
+        Span::styled(
+            format!(
+                "{} / {}",
+                format_packet_size(m.avg_rx_packet_size()),
+                format_packet_size(m.avg_tx_packet_size())
+            ),
+            Style::default().fg(theme.text),
+        ),
+    ]
+}
+
+/// An average packet size such as "4.0KB", `-` without packets
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn format_packet_size(bytes: Option<f64>) -> String {
+    bytes.map_or_else(|| "-".to_string(), |b| format_bytes(b.round() as u64))
+}
+
+/// A LID the way subnet manager tools print it, `-` when unknown
+fn format_lid(lid: Option<u16>) -> String {
+    lid.map_or_else(|| "-".to_string(), |lid| format!("{lid:#06x}"))
+}
+
+/// Bytes, errors and average rates since the trip meter was zeroed
+fn trip_line(trip: &TripMeter, rates: RateDisplay, theme: Theme) -> Line<'static> {
+    let label = |text: String| Span::styled(text, Style::default().fg(theme.muted));
+    let count_style =
+        |count: u64| Style::default().fg(if count > 0 { theme.bad } else { theme.muted });
+
+    Line::from(vec![
+        label(format!("Trip {}: RX ", format_age(trip.elapsed))),
+        Span::styled(
+            format!(
+                "{} (⌀ {})",
+                format_bytes(trip.counters.rx_bytes),
+                rates.format(trip.rates.rx_bytes_per_sec)
+            ),
+            Style::default().fg(theme.rx),
+        ),
+        label("  TX ".to_string()),
+        Span::styled(
+            format!(
+                "{} (⌀ {})",
+                format_bytes(trip.counters.tx_bytes),
+                rates.format(trip.rates.tx_bytes_per_sec)
+            ),
+            Style::default().fg(theme.tx),
+        ),
+        label("  errors ".to_string()),
+        Span::styled(
+            trip.counters.rx_errors.to_string(),
+            count_style(trip.counters.rx_errors),
+        ),
+        label("  discards ".to_string()),
+        Span::styled(
+            trip.counters.tx_discards.to_string(),
+            count_style(trip.counters.tx_discards),
+        ),
+    ])
+}
+
+/// Draw every counter file of the selected port as name and raw value
+fn draw_raw_counters(frame: &mut Frame, area: Rect, state: &AppState) {
+    let theme = state.theme;
+    let entries = state
+        .raw_counters
+        .as_ref()
+        .map_or(&[][..], |(_, entries)| entries.as_slice());
+    if entries.is_empty() {
+        let msg = Paragraph::new("No counter files found").style(Style::default().fg(theme.muted));
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    // One line is taken by the header
+    let visible = usize::from(area.height.saturating_sub(1));
+    let first = state.raw_scroll.min(entries.len().saturating_sub(visible));
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{}-{} of {} files  (PgUp/PgDn to scroll)",
+            first + 1,
+            (first + visible).min(entries.len()),
+            entries.len()
+        ),
+        Style::default().fg(theme.muted),
+    ))];
+    lines.extend(
+        entries
+            .iter()
+            .skip(first)
+            .take(visible)
+            .map(|(name, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{name:<name_width$}  "),
+                        Style::default().fg(theme.muted),
+                    ),
+                    Span::styled(value.clone(), Style::default().fg(theme.text)),
+                ])
+            }),
+    );
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Draw the selected port's traffic per vport, busiest first
+fn draw_vports(
+    frame: &mut Frame,
+    area: Rect,
+    vports: &BTreeMap<String, VportCounters>,
+    theme: Theme,
+) {
+    if vports.is_empty() {
+        let msg = Paragraph::new("No vport counters on this port (SR-IOV not enabled)")
+            .style(Style::default().fg(theme.muted));
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let mut rows: Vec<(&String, &VportCounters)> = vports.iter().collect();
+    rows.sort_by_key(|(_, c)| std::cmp::Reverse(c.rx_bytes.saturating_add(c.tx_bytes)));
+    let total: u64 = rows
+        .iter()
+        .map(|(_, c)| c.rx_bytes.saturating_add(c.tx_bytes))
+        .sum();
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    let header = Style::default().fg(theme.muted);
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "{:<name_width$}  {:>10}  {:>10}  {:>12}  {:>12}  {:>6}",
+            "vport", "RX", "TX", "RX pkts", "TX pkts", "share"
+        ),
+        header,
+    ))];
+    lines.extend(rows.iter().map(|(name, c)| {
+        #[allow(clippy::cast_precision_loss)] // Only a percentage
+        let share = if total == 0 {
+            0.0
+        } else {
+            c.rx_bytes.saturating_add(c.tx_bytes) as f64 / total as f64 * 100.0
+        };
+        Line::from(vec![
+            Span::styled(
+                format!("{name:<name_width$}  "),
+                Style::default().fg(theme.accent),
+            ),
+            Span::styled(
+                format!("{:>10}  ", format_bytes(c.rx_bytes)),
+                Style::default().fg(theme.rx),
+            ),
+            Span::styled(
+                format!("{:>10}  ", format_bytes(c.tx_bytes)),
+                Style::default().fg(theme.tx),
+            ),
+            Span::styled(
+                format!("{:>12}  {:>12}  {share:>5.1}%", c.rx_packets, c.tx_packets),
+                Style::default().fg(theme.text),
+            ),
+        ])
+    }));
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Draw static attributes of the selected port
+fn draw_info(
+    frame: &mut Frame,
+    area: Rect,
+    adapter_name: &str,
+    port: &PortInfo,
+    details: Option<&LinkDetails>,
+    theme: Theme,
+) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(theme.muted));
+    let value = |text: String| Span::styled(text, Style::default().fg(theme.text));
+
+    let mut lines = vec![
+        Line::from(vec![label("Adapter  "), value(adapter_name.to_string())]),
+        Line::from(vec![
+            label("Port     "),
+            value(port.port_number.to_string()),
+        ]),
+        Line::from(vec![label("State    "), value(port.state.to_string())]),
+        Line::from(vec![label("Rate     "), value(port.rate.clone())]),
+        Line::from(vec![
+            label("Lanes    "),
+            value(parse_link_config(&port.rate).map_or_else(
+                || "-".to_string(),
+                |(lanes, per_lane_gbps, generation)| {
+                    format!("{lanes} × {per_lane_gbps} Gb/s ({generation})")
+                },
+            )),
+        ]),
+        Line::from(vec![
+            label("GID      "),
+            value(
+                port.gid
+                    .as_deref()
+                    .map_or_else(|| "-".to_string(), format_gid),
+            ),
+        ]),
+    ];
+
+    // For `cat`ting counter files by hand; simulated ports have none
+    if let Some(path) = &port.sysfs_path {
+        lines.push(Line::from(vec![
+            label("Sysfs    "),
+            value(path.display().to_string()),
+        ]));
+    }
+
+    // Only some drivers expose these, so they are left out when missing
+    if port.phys_state != PhysState::Unknown {
+        lines.push(Line::from(vec![
+            label("Phys     "),
+            value(port.phys_state.to_string()),
+        ]));
+    }
+    if let Some(cable) = details.and_then(|d| d.cable.as_ref()) {
+        let length = cable
+            .length_m
+            .map(|length| format!(", {length} m"))
+            .unwrap_or_default();
+        let text = format!(
+            "{} {} SN {}{length}",
+            cable.vendor, cable.part_number, cable.serial
+        );
+        lines.push(Line::from(vec![label("Cable    "), value(text)]));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Format a raw sysfs GID as a compressed IPv6-style address
+///
+/// sysfs prints GIDs as eight colon-separated 16-bit groups
+/// (`fe80:0000:0000:0000:0002:c903:00f1:2345`); admins recognize RoCE
+/// endpoints by the standard form (`fe80::2:c903:f1:2345`). An all-zero GID
+/// means no address is assigned.
+fn format_gid(raw: &str) -> String {
+    let hex: String = raw.trim().chars().filter(|c| *c != ':').collect();
+    let Some(value) = Some(&hex)
+        .filter(|h| h.len() == 32)
+        .and_then(|h| u128::from_str_radix(h, 16).ok())
+    else {
+        return raw.trim().to_string();
+    };
+    if value == 0 {
+        return "unassigned".to_string();
+    }
+    std::net::Ipv6Addr::from(value).to_string()
+}
+
+/// Auto-scale throughput value and return scaled value with unit
+fn auto_scale_throughput(bytes_per_sec: f64) -> (f64, &'static str) {
+    if bytes_per_sec >= 1_000_000_000.0 {
+        (bytes_per_sec / 1_000_000_000.0, "GB/s")
+    } else if bytes_per_sec >= 1_000_000.0 {
+        (bytes_per_sec / 1_000_000.0, "MB/s")
+    } else if bytes_per_sec >= 1_000.0 {
+        (bytes_per_sec / 1_000.0, "KB/s")
+    } else {
+        (bytes_per_sec, "B/s")
+    }
+}
+
+/// Height of the throughput chart when the line rate is shown, as a multiple
+/// of the line rate, so the over-capacity band stays visible
+const OVER_RATE_BAND_TOP: f64 = 1.25;
+
+/// Weight of the newest sample in the chart's moving average (`m`)
+const CHART_EMA_ALPHA: f64 = 0.2;
+
+/// Draw a chart based on the selected tab
+///
+/// `line_rate` (bytes/s) adds the ceiling to the throughput tab;
+/// `ema_alpha` plots moving averages instead of the raw samples, and
+/// `mirrored` plots RX upward and TX downward from a zero line.
+#[allow(clippy::too_many_lines)]
+#[allow(clippy::too_many_arguments)]
+fn draw_chart(
+    frame: &mut Frame,
+    area: Rect,
+    history: &PortHistory,
+    tab: usize,
+    wall_clock: bool,
+    time_unit: TimeUnit,
+    line_rate: Option<f64>,
+    ema_alpha: Option<f64>,
+    mirrored: bool,
+    theme: Theme,
+) {
+    // The errors tab has a single series, so there is nothing to mirror
+    let mirrored = mirrored && tab != 2;
+    // First, find the max value to determine scale
+    let (rx_raw, tx_raw): (Vec<f64>, Vec<f64>) = match tab {
+        0 => (
+            history.rx_bytes_per_sec.iter().copied().collect(),
+            history.tx_bytes_per_sec.iter().copied().collect(),
+        ),
+        1 => (
+            history.rx_packets_per_sec.iter().copied().collect(),
+            history.tx_packets_per_sec.iter().copied().collect(),
+        ),
+        _ => {
+            let errors: Vec<f64> = history.error_rate.iter().copied().collect();
+            (errors.clone(), errors)
+        }
+    };
+    let (rx_raw, tx_raw) = match ema_alpha {
+        Some(alpha) => (ema(rx_raw, alpha), ema(tx_raw, alpha)),
+        None => (rx_raw, tx_raw),
+    };
+    let series_name = |name: &str| match ema_alpha {
+        Some(_) => format!("{name} (avg)"),
+        None => name.to_string(),
+    };
+
+    if rx_raw.is_empty() {
+        return;
+    }
+
+    let max_raw = rx_raw
+        .iter()
+        .chain(tx_raw.iter())
+        .copied()
+        .fold(0.0_f64, f64::max)
+        .max(0.001); // Avoid division by zero
+    let line_rate = line_rate.filter(|_| tab == 0);
+
+    // Determine scale and unit based on max value
+    let (divisor, y_label) = match tab {
+        0 => {
+            // Throughput - auto-scale, keeping the line rate in range
+            let (_, unit) = auto_scale_throughput(line_rate.map_or(max_raw, |r| r.max(max_raw)));
+            let div = match unit {
+                "GB/s" => 1_000_000_000.0,
+                "MB/s" => 1_000_000.0,
+                "KB/s" => 1_000.0,
+                _ => 1.0,
+            };
+            (div, unit)
+        }
+        1 => {
+            // Packets - scale to K or M
+            if max_raw >= 1_000_000.0 {
+                (1_000_000.0, "Mpps")
+            } else if max_raw >= 1_000.0 {
+                (1_000.0, "Kpps")
+            } else {
+                (1.0, "pps")
+            }
+        }
+        _ => (1.0, "err/s"),
+    };
+
+    // X positions: sample index, or recording time in wall-clock mode
+    let xs: Vec<f64> = if wall_clock {
+        history.timestamps.iter().copied().collect()
+    } else {
+        (0..rx_raw.len()).map(|i| i as f64).collect()
+    };
+
+    // Scale the data
+    let rx_data: Vec<(f64, f64)> = xs
+        .iter()
+        .zip(&rx_raw)
+        .map(|(x, v)| (*x, v / divisor))
+        .collect();
+    let tx_data: Vec<(f64, f64)> = xs
+        .iter()
+        .zip(&tx_raw)
+        .map(|(x, v)| (*x, v / divisor))
+        .collect();
+
+    let max_scaled = max_raw / divisor;
+    let ceiling = line_rate.map(|rate| rate / divisor);
+    let y_top = ceiling.map_or(max_scaled * 1.1, |c| {
+        (max_scaled * 1.1).max(c * OVER_RATE_BAND_TOP)
+    });
+    let (tx_data, y_bounds) = if mirrored {
+        mirror_tx(tx_data, y_top)
+    } else {
+        (tx_data, [0.0, y_top])
+    };
+
+    // Colors
+    let (rx_color, tx_color) = match tab {
+        0 => (theme.rx, theme.tx),
+        1 => (theme.good, theme.warn),
+        _ => (theme.bad, theme.bad),
+    };
+
+    let mut datasets = if tab == 2 {
+        vec![Dataset::default()
+            .name(series_name("Errors"))
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(rx_color))
+            .data(&rx_data)]
+    } else {
+        vec![
+            Dataset::default()
+                .name(series_name("RX"))
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(rx_color))
+                .data(&rx_data),
+            Dataset::default()
+                .name(series_name("TX"))
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(tx_color))
+                .data(&tx_data),
+        ]
+    };
+
+    let (x_bounds, x_labels) = if wall_clock {
+        let first = xs.first().copied().unwrap_or_default();
+        let latest = xs.last().copied().unwrap_or_default();
+        let utc_offset = chrono::Local::now().offset().local_minus_utc();
+        let (bounds, labels) = wall_clock_axis(latest, latest - first, i64::from(utc_offset));
+        let labels = labels
+            .into_iter()
+            .map(|label| Span::styled(label, Style::default().fg(theme.muted)))
+            .collect();
+        (bounds, labels)
+    } else {
+        // Each sample spans the interval it was measured over, which `+`/`-`,
+        // adaptive refresh or a restored history file may have varied
+        let time_label = ago_label(history.span_secs(), time_unit);
+        (
+            [0.0, rx_data.len() as f64],
+            vec![
+                Span::styled(time_label, Style::default().fg(theme.muted)),
+                Span::styled("now", Style::default().fg(theme.text)),
+            ],
+        )
+    };
+
+    // Shade the over-capacity region and draw the ceiling under the traffic
+    let (band, ceiling_line, ceiling_name) = match (ceiling, line_rate) {
+        (Some(c), Some(rate)) => {
+            let latest = rx_raw
+                .last()
+                .copied()
+                .unwrap_or_default()
+                .max(tx_raw.last().copied().unwrap_or_default());
+            (
+                // Mirrored, the band only fills the upper (RX) half
+                over_rate_band(
+                    c,
+                    y_top,
+                    x_bounds,
+                    area.width,
+                    if mirrored {
+                        area.height / 2
+                    } else {
+                        area.height
+                    },
+                ),
+                vec![(x_bounds[0], c), (x_bounds[1], c)],
+                format!(
+                    "Line rate ({:.0}% headroom)",
+                    headroom_percent(latest, rate)
+                ),
+            )
+        }
+        _ => (Vec::new(), Vec::new(), String::new()),
+    };
+    if !band.is_empty() {
+        datasets.insert(
+            0,
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(theme.bad).add_modifier(Modifier::DIM))
+                .data(&band),
+        );
+    }
+    if !ceiling_line.is_empty() {
+        datasets.insert(
+            1.min(datasets.len()),
+            Dataset::default()
+                .name(ceiling_name)
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.bad))
+                .data(&ceiling_line),
+        );
+    }
+
+    let top_label = format!("{:.1}", if ceiling.is_some() { y_top } else { max_scaled });
+    let (y_title, y_labels) = if mirrored {
+        (
+            format!("↑RX {y_label} ↓TX"),
+            vec![
+                Span::styled(format!("-{top_label}"), Style::default().fg(theme.text)),
+                Span::raw("0"),
+                Span::styled(top_label, Style::default().fg(theme.text)),
+            ],
+        )
+    } else {
+        (
+            y_label.to_string(),
+            vec![
+                Span::raw("0"),
+                Span::styled(top_label, Style::default().fg(theme.text)),
+            ],
+        )
+    };
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(theme.muted))
+                .bounds(x_bounds)
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .title(y_title)
+                .style(Style::default().fg(theme.muted))
+                .bounds(y_bounds)
+                .labels(y_labels),
+        );
+
+    frame.render_widget(chart, area);
+}
+
+/// TX points below the zero line for the mirrored chart, and y-bounds
+/// reaching `y_top` on both sides so RX and TX share one scale
+fn mirror_tx(tx_data: Vec<(f64, f64)>, y_top: f64) -> (Vec<(f64, f64)>, [f64; 2]) {
+    let mirrored = tx_data.into_iter().map(|(x, y)| (x, -y)).collect();
+    (mirrored, [-y_top, y_top])
+}
+
+/// Points filling the chart above `ceiling`, one per terminal cell of a
+/// `cols` × `rows` chart spanning `x_bounds` and `0..y_top`
+///
+/// Charts cannot fill areas, so a scatter dense enough to tint every cell
+/// stands in for shading.
+fn over_rate_band(
+    ceiling: f64,
+    y_top: f64,
+    x_bounds: [f64; 2],
+    cols: u16,
+    rows: u16,
+) -> Vec<(f64, f64)> {
+    if cols == 0 || rows == 0 {
+        return Vec::new();
+    }
+    let x_step = (x_bounds[1] - x_bounds[0]) / f64::from(cols);
+    let y_step = y_top / f64::from(rows);
+
+    (0..rows)
+        .map(|row| (f64::from(row) + 0.5) * y_step)
+        .filter(|y| *y > ceiling)
+        .flat_map(|y| (0..cols).map(move |col| (x_bounds[0] + (f64::from(col) + 0.5) * x_step, y)))
+        .collect()
+}
+
+/// Share of the line rate still unused by the busier direction, in percent
+fn headroom_percent(bytes_per_sec: f64, line_rate: f64) -> f64 {
+    if line_rate <= 0.0 {
+        return 0.0;
+    }
+    ((1.0 - bytes_per_sec / line_rate) * 100.0).clamp(0.0, 100.0)
+}
+
+/// X-axis bounds and tick labels anchored to round wall-clock times
+///
+/// `latest` is the newest sample (seconds since the epoch) and `span` the
+/// time the samples cover. The axis only moves when `latest` crosses a tick,
+/// so the labels stay put while the data scrolls underneath them.
+fn wall_clock_axis(latest: f64, span: f64, utc_offset: i64) -> ([f64; 2], Vec<String>) {
+    // Leave one tick of headroom so the oldest samples stay in view
+    let step = WALL_CLOCK_STEPS
+        .iter()
+        .copied()
+        .find(|step| (step * (WALL_CLOCK_TICKS - 1)) as f64 >= span)
+        .unwrap_or(WALL_CLOCK_STEPS[WALL_CLOCK_STEPS.len() - 1]);
+
+    let end = (latest / step as f64).ceil() as i64 * step;
+    let start = end - step * WALL_CLOCK_TICKS;
+    let labels = (0..=WALL_CLOCK_TICKS)
+        .map(|i| format_clock(start + i * step + utc_offset))
+        .collect();
+
+    ([start as f64, end as f64], labels)
+}
+
+/// Unit of the chart's "… ago" x-axis label (`--time-unit`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// Seconds under a minute, whole minutes from there on
+    #[default]
+    Auto,
+    /// Always seconds, e.g. `90s ago`
+    Seconds,
+    /// Always minutes, e.g. `0.5m ago`
+    Minutes,
+}
+
+impl std::str::FromStr for TimeUnit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TimeUnit::Auto),
+            "s" | "sec" | "seconds" => Ok(TimeUnit::Seconds),
+            "m" | "min" | "minutes" => Ok(TimeUnit::Minutes),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Label for the oldest end of a chart covering `span_secs`
+fn ago_label(span_secs: f64, unit: TimeUnit) -> String {
+    match unit {
+        TimeUnit::Auto if span_secs >= 60.0 => format!("{:.0}m ago", span_secs / 60.0),
+        TimeUnit::Auto | TimeUnit::Seconds => format!("{span_secs:.0}s ago"),
+        TimeUnit::Minutes => {
+            // One decimal for windows that are not whole minutes
+            let mins = format!("{:.1}", span_secs / 60.0);
+            format!("{}m ago", mins.trim_end_matches(".0"))
+        }
+    }
+}
+
+/// Format seconds since the epoch (already shifted to local time) as `HH:MM:SS`
+fn format_clock(secs: i64) -> String {
+    let day = secs.rem_euclid(86_400);
+    format!("{:02}:{:02}:{:02}", day / 3_600, day % 3_600 / 60, day % 60)
+}
+
+/// Render an inline sparkline as Unicode characters
+fn render_inline_sparkline(data: &[u64]) -> String {
+    const SPARK_CHARS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    if data.is_empty() {
+        return String::new();
+    }
+
+    data.iter()
+        .map(|&v| SPARK_CHARS[(v as usize).min(7)])
+        .collect()
+}
+
+/// Render RX and TX sparklines that share one column, each taking half its width
+///
+/// Both halves are right-aligned so the newest samples line up while history fills.
+fn split_sparklines(history: &PortHistory, width: usize, clip: Option<f64>) -> (String, String) {
+    let half = width / 2;
+    let rx = render_inline_sparkline(&history.rx_sparkline_data(half, clip));
+    let tx = render_inline_sparkline(&history.tx_sparkline_data(half, clip));
+    (format!("{rx:>half$}"), format!("{tx:>half$}"))
+}
+
+/// Render a utilization bar
+fn render_utilization_bar(percent: f64, width: usize) -> String {
+    let filled = ((percent / 100.0) * width as f64).round() as usize;
+    let filled = filled.min(width);
+
+    (0..width)
+        .map(|i| if i < filled { '█' } else { '░' })
+        .collect()
+}
+
+/// Line rate assumed when a port's rate cannot be parsed: 100 Gb/s, in bytes
+const DEFAULT_LINE_RATE: f64 = 12_500_000_000.0;
+
+/// A port's line rate in bits per second, read by its link layer's conventions
+///
+/// Ethernet (RoCE) speeds follow ethtool, where a bare number is megabits;
+/// the IB width and generation the driver appends to them are ignored.
+pub fn port_rate_bps(port: &PortInfo) -> Option<f64> {
+    match port.link_layer {
+        LinkLayer::Ethernet => match port.rate.trim().parse::<f64>() {
+            Ok(mbps) => Some(mbps * 1e6).filter(|bps| *bps > 0.0),
+            Err(_) => parse_rate_bps(&port.rate),
+        },
+        LinkLayer::InfiniBand | LinkLayer::Unknown => parse_rate_bps(&port.rate),
+    }
+}
+
+/// A port's line rate in bytes per second, the reference for utilization
+pub fn port_max_rate(port: &PortInfo) -> f64 {
+    match port.link_layer {
+        LinkLayer::Ethernet => port_rate_bps(port).map_or(DEFAULT_LINE_RATE, |bps| bps / 8.0),
+        LinkLayer::InfiniBand | LinkLayer::Unknown => parse_max_rate(&port.rate),
+    }
+}
+
+/// Parse max rate from rate string (e.g., "100 Gb/sec" -> bytes/sec)
+pub fn parse_max_rate(rate_str: &str) -> f64 {
+    // Convert bits/sec to bytes/sec, defaulting to 100 Gbps
+    parse_rate_bps(rate_str).map_or(DEFAULT_LINE_RATE, |bps| bps / 8.0)
+}
+
+/// Parse a sysfs link rate into bits per second
+///
+/// Drivers disagree on the exact format: mlx5 prints `"100 Gb/sec (4X EDR)"`,
+/// hfi1 omits the width (`"100 Gb/sec"`), and others vary spacing and case
+/// (`"2.5Gb/s"`, `"56 gb/sec"`, `"400000 Mb/sec"`). A bare number is taken
+/// to be Gb/sec, the sysfs unit. Zero or unparsable rates yield `None`.
+pub fn parse_rate_bps(rate_str: &str) -> Option<f64> {
+    let rate = rate_str.trim();
+    let num_len = rate
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rate.len());
+    let num: f64 = rate[..num_len].parse().ok()?;
+
+    let unit = rate[num_len..].trim_start().to_ascii_lowercase();
+    let multiplier = match unit.chars().next() {
+        Some('t') => 1e12,
+        None | Some('g' | '(') => 1e9,
+        Some('m') => 1e6,
+        Some('k') => 1e3,
+        Some('b') => 1.0,
+        Some(_) => return None,
+    };
+
+    Some(num * multiplier).filter(|bps| *bps > 0.0)
+}
+
+/// Rates above line rate by more than this factor cannot be real traffic
+const LINE_RATE_TOLERANCE: f64 = 1.05;
+
+/// Consecutive history samples that must be over line rate before flagging
+const OVER_RATE_SAMPLES: usize = 4;
+
+/// Port utilization in percent, capped at 100
+///
+/// InfiniBand is full-duplex, so we use max(RX, TX) not sum
+fn utilization_percent(metrics: &PortMetrics, max_rate: f64) -> f64 {
+    let current_rate = metrics.rx_bytes_per_sec.max(metrics.tx_bytes_per_sec);
+    (current_rate / max_rate * 100.0).min(100.0)
+}
+
+/// Check whether a port reports more traffic than its link can carry
+///
+/// That usually means a wrong data multiplier or a misread counter rather
+/// than a saturated link, so it is worth surfacing instead of clamping.
+pub fn exceeds_line_rate(metrics: &PortMetrics, max_rate: f64) -> bool {
+    metrics.rx_bytes_per_sec.max(metrics.tx_bytes_per_sec) > max_rate * LINE_RATE_TOLERANCE
+}
+
+/// Like [`exceeds_line_rate`], but also requires the recent history to be over
+/// line rate so a single counter glitch does not raise the flag
+pub fn sustained_over_line_rate(
+    metrics: &PortMetrics,
+    history: Option<&PortHistory>,
+    max_rate: f64,
+) -> bool {
+    let Some(history) = history else {
+        return false;
+    };
+    if !exceeds_line_rate(metrics, max_rate) || history.rx_bytes_per_sec.len() < OVER_RATE_SAMPLES {
+        return false;
+    }
+    history
+        .rx_bytes_per_sec
+        .last_n(OVER_RATE_SAMPLES)
+        .zip(history.tx_bytes_per_sec.last_n(OVER_RATE_SAMPLES))
+        .all(|(rx, tx)| rx.max(*tx) > max_rate * LINE_RATE_TOLERANCE)
+}
+
+/// Nominal per-lane signalling rate (Gb/s) of each InfiniBand generation
+const LANE_GBPS: &[(&str, f64)] = &[
+    ("SDR", 2.5),
+    ("DDR", 5.0),
+    ("QDR", 10.0),
+    ("FDR10", 10.0),
+    ("FDR", 14.0),
+    ("EDR", 25.0),
+    ("HDR", 50.0),
+    ("NDR", 100.0),
+    ("XDR", 200.0),
+];
+
+/// Split a rate like `"400 Gb/sec (4X NDR)"` into `(lanes, per_lane_gbps, generation)`
+///
+/// The aggregate alone is ambiguous (400 Gb/s is 4X NDR or 2X XDR), so this
+/// reads the negotiated width and generation from the parenthesized part.
+/// The per-lane rate is the aggregate divided by the lanes, falling back to
+/// the generation's nominal rate when the aggregate is missing.
+fn parse_link_config(rate: &str) -> Option<(u8, f64, String)> {
+    let mode = rate.split_once('(')?.1.split(')').next()?;
+    let (width, generation) = mode.trim().split_once(|c: char| c.is_whitespace())?;
+    let lanes: u8 = width
+        .strip_suffix(['X', 'x'])?
+        .parse()
+        .ok()
+        .filter(|lanes| *lanes > 0)?;
+    let generation = generation.trim().to_ascii_uppercase();
+
+    let per_lane_gbps = match parse_rate_bps(rate) {
+        Some(bps) => bps / 1e9 / f64::from(lanes),
+        None => LANE_GBPS
+            .iter()
+            .find(|(name, _)| *name == generation)
+            .map(|(_, gbps)| *gbps)?,
+    };
+
+    Some((lanes, per_lane_gbps, generation))
+}
+
+/// Link column text: the link layer tag, then the speed ("ETH 25 Gb/sec")
+fn link_label(port: &PortInfo) -> String {
+    let speed = truncate_rate(&port.rate);
+    match port.link_layer {
+        LinkLayer::InfiniBand => format!("IB {speed}"),
+        LinkLayer::Ethernet => format!("ETH {speed}"),
+        LinkLayer::Unknown => speed,
+    }
+}
+
+/// Truncate rate string for display
+fn truncate_rate(rate: &str) -> String {
+    // Extract just the speed part (e.g., "100 Gb/sec"), normalizing spacing
+    let speed = rate.split('(').next().unwrap_or(rate);
+    speed.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
+    let mut value = bytes;
+    let mut unit_index = 0;
+
+    while value >= 1024 && unit_index < UNITS.len() - 1 {
+        value /= 1024;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{}{}", value, UNITS[unit_index])
+    } else {
+        let fractional = (bytes >> (10 * (unit_index - 1))) % 1024;
+        let decimal_part = (fractional * 10) / 1024;
+        format!("{}.{}{}", value, decimal_part, UNITS[unit_index])
+    }
+}
+
+/// How throughput is shown: as measured, or split evenly across the
+/// `--divide-by` consumers (e.g. GPUs sharing an HCA)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateDisplay {
+    /// Consumers sharing each port; never zero, 1 shows rates as measured
+    divide_by: u32,
+}
+
+impl Default for RateDisplay {
+    fn default() -> Self {
+        Self { divide_by: 1 }
+    }
+}
+
+impl RateDisplay {
+    /// Show rates per consumer; `None` for zero, which cannot divide anything
+    pub fn per_consumer(divide_by: u32) -> Option<Self> {
+        (divide_by > 0).then_some(Self { divide_by })
+    }
+
+    /// Format a measured rate for display
+    pub fn format(self, bytes_per_sec: f64) -> String {
+        format_bytes_per_sec(self.divide(bytes_per_sec))
+    }
+
+    /// A measured rate as displayed, before formatting
+    fn divide(self, bytes_per_sec: f64) -> f64 {
+        bytes_per_sec / f64::from(self.divide_by)
+    }
+
+    /// Appended to rate labels ("RX/GPU") while rates are divided
+    pub fn suffix(self) -> &'static str {
+        if self.divide_by > 1 {
+            "/GPU"
+        } else {
+            ""
+        }
+    }
+}
+
+pub fn format_bytes_per_sec(bytes_per_sec: f64) -> String {
+    const UNITS: &[&str] = &["B/s", "KB/s", "MB/s", "GB/s", "TB/s"];
+    let mut value = bytes_per_sec;
+    let mut unit_index = 0;
+
+    while value >= 1024.0 && unit_index < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit_index += 1;
+    }
+
+    if value < 0.1 {
+        format!("{:.2}{}", value, UNITS[unit_index])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit_index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0B");
+        assert_eq!(format_bytes(1023), "1023B");
+        assert_eq!(format_bytes(1024), "1.0KB");
+        assert_eq!(format_bytes(1025), "1.0KB");
+        assert_eq!(format_bytes(1024 * 1024), "1.0MB");
+        assert_eq!(format_bytes(1024 * 1024 * 1024), "1.0GB");
+        assert_eq!(format_bytes(1024 * 1024 * 1024 * 1024), "1.0TB");
+        assert_eq!(format_bytes(1024 * 1024 * 1024 * 1024 * 1024), "1.0PB");
+    }
+
+    #[test]
+    fn test_rate_display_divides_and_labels() {
+        let raw = RateDisplay::default();
+        assert_eq!(raw.format(8.0 * 1024.0 * 1024.0), "8.0MB/s");
+        assert_eq!(raw.suffix(), "");
+        assert_eq!(Column::Rx.title_for(raw), "RX");
+
+        let per_gpu = RateDisplay::per_consumer(8).unwrap();
+        assert_eq!(per_gpu.format(8.0 * 1024.0 * 1024.0), "1.0MB/s");
+        assert_eq!(per_gpu.suffix(), "/GPU");
+        assert_eq!(Column::Tx.title_for(per_gpu), "TX/GPU");
+        assert_eq!(Column::Port.title_for(per_gpu), "Port");
+
+        // Dividing by one is the same as not dividing
+        assert_eq!(RateDisplay::per_consumer(1), Some(raw));
+        assert_eq!(RateDisplay::per_consumer(0), None);
+    }
+
+    #[test]
+    fn test_format_bytes_per_sec() {
+        assert_eq!(format_bytes_per_sec(0.0), "0.00B/s");
+        assert_eq!(format_bytes_per_sec(1023.0), "1023.0B/s");
+        assert_eq!(format_bytes_per_sec(1024.0), "1.0KB/s");
+        assert_eq!(format_bytes_per_sec(1025.0), "1.0KB/s");
+        assert_eq!(format_bytes_per_sec(1024.0 * 1024.0), "1.0MB/s");
+        assert_eq!(format_bytes_per_sec(1024.0 * 1024.0 * 1024.0), "1.0GB/s");
+        assert_eq!(
+            format_bytes_per_sec(1024.0 * 1024.0 * 1024.0 * 1024.0),
+            "1.0TB/s"
+        );
+    }
+
+    #[test]
+    fn test_render_inline_sparkline() {
+        let data = vec![0, 1, 2, 3, 4, 5, 6, 7];
+        let result = render_inline_sparkline(&data);
+        assert_eq!(result, "▁▂▃▄▅▆▇█");
+    }
+
+    #[test]
+    fn test_render_inline_sparkline_empty() {
+        let data: Vec<u64> = vec![];
+        let result = render_inline_sparkline(&data);
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_parse_max_rate() {
+        assert!((parse_max_rate("100 Gb/sec (4X EDR)") - 12_500_000_000.0).abs() < 1.0);
+        assert!((parse_max_rate("100 Gb/sec (2X HDR)") - 12_500_000_000.0).abs() < 1.0);
+        assert!((parse_max_rate("200 Gb/sec") - 25_000_000_000.0).abs() < 1.0);
+        assert!((parse_max_rate("400 Gb/sec (4X NDR)") - 50_000_000_000.0).abs() < 1.0);
+        assert!((parse_max_rate("800 Gb/sec (4X XDR)") - 100_000_000_000.0).abs() < 1.0);
+        assert!((parse_max_rate("800Gb/sec") - 100_000_000_000.0).abs() < 1.0); // No space
+        assert!((parse_max_rate("invalid") - 12_500_000_000.0).abs() < 1.0); // Default
+    }
+
+    #[test]
+    fn test_line_rate_follows_link_layer() {
+        let port = |link_layer, rate: &str| PortInfo {
+            link_layer,
+            rate: rate.to_string(),
+            ..PortInfo::default()
+        };
+        let metrics = PortMetrics {
+            rx_bytes_per_sec: 1_250_000_000.0, // 10 Gb/s
+            ..PortMetrics::default()
+        };
+        let utilization = |port: &PortInfo| utilization_percent(&metrics, port_max_rate(port));
+
+        // InfiniBand: bare numbers are Gb/s
+        let ib = port(LinkLayer::InfiniBand, "100 Gb/sec (4X EDR)");
+        assert_eq!(port_rate_bps(&ib), Some(100e9));
+        assert!((utilization(&ib) - 10.0).abs() < 1e-9);
+        assert_eq!(
+            port_rate_bps(&port(LinkLayer::InfiniBand, "25")),
+            Some(25e9)
+        );
+
+        // Ethernet: ethtool-style bare megabits, and the synthesized IB
+        // width RoCE drivers append does not matter
+        let roce = port(LinkLayer::Ethernet, "25000");
+        assert_eq!(port_rate_bps(&roce), Some(25e9));
+        assert!((utilization(&roce) - 40.0).abs() < 1e-9);
+        let roce = port(LinkLayer::Ethernet, "25 Gb/sec (1X EDR)");
+        assert!((utilization(&roce) - 40.0).abs() < 1e-9);
+        assert_eq!(
+            port_rate_bps(&port(LinkLayer::Ethernet, "100GbE")),
+            Some(100e9)
+        );
+        assert_eq!(port_rate_bps(&port(LinkLayer::Ethernet, "0")), None);
+
+        // Unknown link layers read like InfiniBand
+        assert_eq!(port_rate_bps(&port(LinkLayer::Unknown, "25")), Some(25e9));
+    }
+
+    #[test]
+    fn test_exceeds_line_rate() {
+        let max_rate = parse_max_rate("100 Gb/sec (4X EDR)");
+        let at = |rx: f64, tx: f64| PortMetrics {
+            rx_bytes_per_sec: rx,
+            tx_bytes_per_sec: tx,
+            ..PortMetrics::default()
+        };
+
+        assert!(!exceeds_line_rate(&at(0.0, 0.0), max_rate));
+        assert!(!exceeds_line_rate(&at(max_rate, max_rate), max_rate));
+        // Within rounding tolerance
+        assert!(!exceeds_line_rate(&at(max_rate * 1.02, 0.0), max_rate));
+        // 4x over, e.g. a double-applied data multiplier
+        assert!(exceeds_line_rate(&at(0.0, max_rate * 4.0), max_rate));
+    }
+
+    #[test]
+    fn test_summarize_ports() {
+        let port = |rate: &str| PortInfo {
+            rate: rate.to_string(),
+            ..PortInfo::default()
+        };
+        let at = |rx: f64, tx: f64| PortMetrics {
+            rx_bytes_per_sec: rx,
+            tx_bytes_per_sec: tx,
+            ..PortMetrics::default()
+        };
+        let edr = port("100 Gb/sec");
+        let line = parse_max_rate("100 Gb/sec");
+        let busy = at(line / 2.0, line / 4.0);
+        let idle = at(0.0, line / 10.0);
+
+        let summary = summarize([(&edr, Some(busy)), (&edr, Some(idle)), (&edr, None)]);
+
+        assert!((summary.total_rx - line / 2.0).abs() < 1.0);
+        assert!((summary.total_tx - line * 0.35).abs() < 1.0);
+        assert!((summary.max_port_rate - line / 2.0).abs() < 1.0);
+        // Ports without metrics don't drag the average down
+        assert!((summary.avg_utilization - 30.0).abs() < 1e-9);
+
+        assert_eq!(summarize([]), NodeSummary::default());
+    }
+
+    #[test]
+    fn test_header_totals_match_strip_while_trip_meter_runs() {
+        let sample = |rx_bytes| {
+            let mut adapters = vec![adapter("mlx5_0", &[PortState::Active])];
+            adapters[0].ports[0].counters.rx_bytes = rx_bytes;
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+        metrics.set_reference();
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(1_000_000_000));
+        // The latest interval is idle, the trip average is not
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(1_000_000_000));
+        let adapters = sample(1_000_000_000);
+        let (live_rx, _) = calculate_totals(&adapters, &metrics);
+        assert!(live_rx.abs() < f64::EPSILON);
+
+        let mut state = AppState::new();
+        let text = render_to_text(&adapters, &metrics, "node01", &mut state, 130, 10).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let after = |line: &str, marker: &str| {
+            let rest = &line[line.find(marker).unwrap() + marker.len()..];
+            rest.split_whitespace().next().unwrap().to_string()
+        };
+        let header_rx = after(lines[0], "▲ ");
+        let strip_rx = after(lines[lines.len() - 3], "Σ RX ");
+        assert_eq!(header_rx, strip_rx);
+        assert_ne!(header_rx, state.rate_display.format(live_rx));
+
+        let plain = plain_text_table(&adapters, &metrics, "node01", &state);
+        assert_eq!(after(plain.lines().next().unwrap(), "RX "), strip_rx);
+    }
+
+    #[test]
+    fn test_over_line_rate_must_be_sustained() {
+        let max_rate = parse_max_rate("100 Gb/sec");
+        let over = PortMetrics {
+            rx_bytes_per_sec: max_rate * 2.0,
+            ..PortMetrics::default()
+        };
+
+        let mut history = PortHistory::with_capacity(10);
+        history.record(1000.0, 1000.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        for _ in 0..OVER_RATE_SAMPLES - 1 {
+            history.record(max_rate * 2.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        assert!(!sustained_over_line_rate(&over, Some(&history), max_rate));
+
+        history.record(max_rate * 2.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        assert!(sustained_over_line_rate(&over, Some(&history), max_rate));
+        assert!(!sustained_over_line_rate(&over, None, max_rate));
+    }
+
+    #[test]
+    fn test_link_label_tags_link_layer() {
+        let port = |link_layer| PortInfo {
+            link_layer,
+            rate: "100 Gb/sec (4X EDR)".to_string(),
+            ..PortInfo::default()
+        };
+        assert_eq!(link_label(&port(LinkLayer::InfiniBand)), "IB 100 Gb/sec");
+        assert_eq!(link_label(&port(LinkLayer::Ethernet)), "ETH 100 Gb/sec");
+        assert_eq!(link_label(&port(LinkLayer::Unknown)), "100 Gb/sec");
+    }
+
+    #[test]
+    fn test_format_packet_size() {
+        assert_eq!(format_packet_size(Some(4_096.0)), "4.0KB");
+        assert_eq!(format_packet_size(Some(64.4)), "64B");
+        assert_eq!(format_packet_size(None), "-");
+    }
+
+    #[test]
+    fn test_mirror_tx_negates_and_balances_bounds() {
+        let (tx, bounds) = mirror_tx(vec![(0.0, 2.0), (1.0, 0.0), (2.0, 5.5)], 6.0);
+        assert_eq!(tx, vec![(0.0, -2.0), (1.0, -0.0), (2.0, -5.5)]);
+        assert!(tx.iter().all(|(_, y)| *y <= 0.0));
+        assert!((bounds[1] - 6.0).abs() < f64::EPSILON);
+        assert!((bounds[0] + bounds[1]).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_truncate_rate() {
+        assert_eq!(truncate_rate("100 Gb/sec (4X EDR)"), "100 Gb/sec");
+        assert_eq!(truncate_rate("200 Gb/sec"), "200 Gb/sec");
+        assert_eq!(truncate_rate("  56  Gb/sec\t(4X FDR)\n"), "56 Gb/sec");
+    }
+
+    #[test]
+    fn test_parse_link_config_generations() {
+        let config = |rate: &str| parse_link_config(rate);
+        let lanes =
+            |lanes: u8, gbps: f64, generation: &str| Some((lanes, gbps, generation.to_string()));
+
+        assert_eq!(config("100 Gb/sec (4X EDR)"), lanes(4, 25.0, "EDR"));
+        assert_eq!(config("200 Gb/sec (4X HDR)"), lanes(4, 50.0, "HDR"));
+        assert_eq!(config("100 Gb/sec (2X HDR)"), lanes(2, 50.0, "HDR"));
+        assert_eq!(config("400 Gb/sec (4X NDR)"), lanes(4, 100.0, "NDR"));
+        assert_eq!(config("200 Gb/sec (2X NDR)"), lanes(2, 100.0, "NDR"));
+        // Same aggregate as 4X NDR, different negotiated configuration
+        assert_eq!(config("400 Gb/sec (2X XDR)"), lanes(2, 200.0, "XDR"));
+        assert_eq!(config("800 Gb/sec (4X XDR)"), lanes(4, 200.0, "XDR"));
+        assert_eq!(config("56 Gb/sec (4X FDR)"), lanes(4, 14.0, "FDR"));
+        // Nominal lane rate when the aggregate is unreadable
+        assert_eq!(config("? (4X NDR)"), lanes(4, 100.0, "NDR"));
+
+        assert_eq!(config("100 Gb/sec"), None);
+        assert_eq!(config("200 Gb/sec (Bonded)"), None);
+        assert_eq!(config("100 Gb/sec (0X EDR)"), None);
+    }
+
+    #[test]
+    fn test_parse_rate_bps_driver_variants() {
+        let gbps = |s: &str| parse_rate_bps(s).map(|bps| bps / 1e9);
+
+        // mlx5 / mlx4
+        assert_eq!(gbps("100 Gb/sec (4X EDR)"), Some(100.0));
+        assert_eq!(gbps("2.5 Gb/sec (1X SDR)"), Some(2.5));
+        // hfi1 / qib: no width in parentheses
+        assert_eq!(gbps("100 Gb/sec"), Some(100.0));
+        assert_eq!(gbps("40 Gb/sec\n"), Some(40.0));
+        // Spacing and case variations
+        assert_eq!(gbps("56gb/s"), Some(56.0));
+        assert_eq!(gbps("  25   GB/sec "), Some(25.0));
+        assert_eq!(gbps("200 Gbps"), Some(200.0));
+        // Other units and bare numbers
+        assert_eq!(gbps("400000 Mb/sec"), Some(400.0));
+        assert_eq!(gbps("1.6 Tb/sec"), Some(1600.0));
+        assert_eq!(gbps("100"), Some(100.0));
+        assert_eq!(gbps("100(4X EDR)"), Some(100.0));
+        // Unusable
+        assert_eq!(gbps("0 Gb/sec"), None);
+        assert_eq!(gbps(""), None);
+        assert_eq!(gbps("Unknown"), None);
+        assert_eq!(gbps("100 furlongs"), None);
+    }
+
+    #[test]
+    fn test_split_sparklines_fill_column() {
+        let mut history = PortHistory::with_capacity(50);
+        for i in 0..3 {
+            history.record(
+                f64::from(i) * 100.0,
+                50.0,
+                0.0,
+                0.0,
+                0.0,
+                Duration::from_secs(1),
+            );
+        }
+
+        // Short history is padded to half the column
+        let (rx, tx) = split_sparklines(&history, SPARKLINE_SAMPLES, None);
+        assert_eq!(rx.chars().count(), SPARKLINE_SAMPLES / 2);
+        assert_eq!(tx.chars().count(), SPARKLINE_SAMPLES / 2);
+        assert!(rx.ends_with("▁▅█"));
+
+        // Long history is truncated to the newest samples
+        for _ in 0..40 {
+            history.record(10.0, 20.0, 0.0, 0.0, 0.0, Duration::from_secs(1));
+        }
+        let (rx, tx) = split_sparklines(&history, SPARKLINE_SAMPLES, None);
+        assert_eq!(rx.chars().count(), SPARKLINE_SAMPLES / 2);
+        assert_eq!(tx.chars().count(), SPARKLINE_SAMPLES / 2);
+    }
+
+    #[test]
+    fn test_format_gid() {
+        assert_eq!(
+            format_gid("fe80:0000:0000:0000:0002:c903:00f1:2345\n"),
+            "fe80::2:c903:f1:2345"
+        );
+        assert_eq!(
+            format_gid("0000:0000:0000:0000:0000:ffff:0a00:0001"),
+            "::ffff:10.0.0.1"
+        );
+        assert_eq!(
+            format_gid("0000:0000:0000:0000:0000:0000:0000:0000"),
+            "unassigned"
+        );
+        assert_eq!(format_gid("garbage"), "garbage");
+    }
+
+    #[test]
+    fn test_credit_waits_on_a_busy_port_mean_congestion() {
+        let max_rate = 12.5e9; // 100 Gb/s
+        let rates = |tx_bytes_per_sec, xmit_wait_rate| PortMetrics {
+            tx_bytes_per_sec,
+            xmit_wait_rate,
+            ..PortMetrics::default()
+        };
+        let text = |m: &PortMetrics| -> String {
+            error_split_line(m, max_rate, Theme::DARK)
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect()
+        };
+
+        // Waiting while busy is the fabric; waiting while idle is not
+        assert!(fabric_congested(&rates(11.0e9, 5_000.0), max_rate));
+        assert!(!fabric_congested(&rates(1.0e9, 5_000.0), max_rate));
+        assert!(!fabric_congested(&rates(11.0e9, 0.0), max_rate));
+
+        assert!(text(&rates(11.0e9, 5_000.0)).ends_with("Wait 5000/s ticks ◆ fabric congested"));
+        assert!(text(&rates(1.0e9, 5_000.0)).ends_with("Wait 5000.0/s ticks"));
+    }
+
+    #[test]
+    fn test_utilization_bar() {
+        let bar = render_utilization_bar(50.0, 10);
+        // Unicode chars are multi-byte, so count chars not bytes
+        assert_eq!(bar.chars().count(), 10);
+        assert!(bar.contains('█'));
+        assert!(bar.contains('░'));
+    }
+
+    #[test]
+    fn test_app_state_navigation() {
+        let mut state = AppState::new();
+        state.selectable_items = vec![
+            None,
+            Some(("mlx5_0".to_string(), 1)),
+            Some(("mlx5_0".to_string(), 2)),
+            None,
+            Some(("mlx5_1".to_string(), 1)),
+        ];
+        state.selected_row = 1;
+
+        state.select_next();
+        assert_eq!(state.selected_row, 2);
+
+        state.select_next();
+        // Should skip the None at index 3
+        assert_eq!(state.selected_row, 4);
+
+        state.select_prev();
+        assert_eq!(state.selected_row, 2);
+
+        // Without wrapping the ends stop the selection
+        state.selected_row = 4;
+        state.select_next();
+        assert_eq!(state.selected_row, 4);
+        state.selected_row = 1;
+        state.select_prev();
+        assert_eq!(state.selected_row, 1);
+    }
+
+    #[test]
+    fn test_navigation_wraps_past_headers() {
+        let mut state = AppState::new();
+        state.wrap_navigation = true;
+        state.selectable_items = vec![
+            None,
+            Some(("mlx5_0".to_string(), 1)),
+            None,
+            Some(("mlx5_1".to_string(), 1)),
+            Some(("mlx5_1".to_string(), 2)),
+        ];
+
+        // Last → first, skipping the leading header
+        state.selected_row = 4;
+        state.select_next();
+        assert_eq!(state.selected_row, 1);
+
+        // First → last, across the header at the top
+        state.select_prev();
+        assert_eq!(state.selected_row, 4);
+
+        // Within the list it moves as usual
+        state.select_prev();
+        assert_eq!(state.selected_row, 3);
+        state.select_prev();
+        assert_eq!(state.selected_row, 1);
+    }
+
+    fn adapter(name: &str, states: &[PortState]) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_string(),
+            ports: states
+                .iter()
+                .zip(1..)
+                .map(|(state, port_number)| PortInfo {
+                    port_number,
+                    state: *state,
+                    ..PortInfo::default()
+                })
+                .collect(),
+            ..AdapterInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_adapters_sorted_by_aggregate_throughput() {
+        // Bytes each port has moved by the second sample
+        let sample = |scale: u64| {
+            let mut adapters = vec![
+                adapter("mlx5_0", &[PortState::Active, PortState::Active]),
+                adapter("mlx5_1", &[PortState::Active]),
+                adapter("mlx5_2", &[PortState::Active, PortState::Active]),
+                adapter("mlx5_3", &[PortState::Down]),
+            ];
+            let moved = [[10, 20], [500, 0], [100, 150], [0, 0]];
+            for (adapter, bytes) in adapters.iter_mut().zip(moved) {
+                for (port, bytes) in adapter.ports.iter_mut().zip(bytes) {
+                    port.counters.rx_bytes = bytes * scale;
+                }
+            }
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+        metrics.update(&sample(1_000));
+        let adapters = sample(1_000);
+
+        let mut state = AppState::new();
+        let names = |state: &AppState| -> Vec<String> {
+            ordered_adapters(&adapters, &metrics, state)
+                .iter()
+                .map(|a| a.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&state), ["mlx5_0", "mlx5_1", "mlx5_2", "mlx5_3"]);
+
+        state.toggle_sort_adapters();
+        // 500 beats 100 + 150 beats 10 + 20; the idle adapter goes last
+        assert_eq!(names(&state), ["mlx5_1", "mlx5_2", "mlx5_0", "mlx5_3"]);
+        // Ports keep their order beneath each adapter
+        let sorted = ordered_adapters(&adapters, &metrics, &state);
+        let ports: Vec<u16> = sorted[1].ports.iter().map(|p| p.port_number).collect();
+        assert_eq!(ports, [1, 2]);
+    }
+
+    #[test]
+    fn test_sort_key_flattens_and_ranks_ports() {
+        let sample = |scale: u64| {
+            let mut adapters = vec![
+                adapter("mlx5_0", &[PortState::Active, PortState::Down]),
+                adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+            ];
+            // (rx, tx) bytes moved per port
+            let moved = [[(10, 900), (0, 0)], [(500, 0), (100, 100)]];
+            for (adapter, bytes) in adapters.iter_mut().zip(moved) {
+                for (port, (rx, tx)) in adapter.ports.iter_mut().zip(bytes) {
+                    port.counters.rx_bytes = rx * scale;
+                    port.counters.tx_bytes = tx * scale;
+                }
+            }
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+        metrics.update(&sample(1_000));
+        let adapters = sample(1_000);
+
+        let mut state = AppState::new();
+        let order = |state: &AppState| -> Vec<(String, u16)> {
+            build_rows(&adapters, &metrics, state)
+                .iter()
+                .map(|row| match row {
+                    TableRow::Port(a, p) => (a.name.clone(), p.port_number),
+                    other => panic!("flat table has no {other:?} rows"),
+                })
+                .collect()
+        };
+        let key = |name: &str, port| (name.to_string(), port);
+
+        state.cycle_sort_key();
+        assert_eq!(state.sort_key, SortKey::RxRate);
+        assert_eq!(
+            order(&state),
+            [
+                key("mlx5_1", 1),
+                key("mlx5_1", 2),
+                key("mlx5_0", 1),
+                key("mlx5_0", 2)
+            ]
+        );
+        state.cycle_sort_key();
+        assert_eq!(order(&state)[0], key("mlx5_0", 1));
+        state.cycle_sort_key();
+        assert_eq!(state.sort_key, SortKey::Total);
+        // 910 beats 500 beats 200; the idle port goes last
+        assert_eq!(
+            order(&state),
+            [
+                key("mlx5_0", 1),
+                key("mlx5_1", 1),
+                key("mlx5_1", 2),
+                key("mlx5_0", 2)
+            ]
+        );
+
+        // Rows name their adapter, and the first row is selectable
+        let text = plain_text_table(&adapters, &metrics, "node01", &state);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[1].starts_with("Adapter"), "{text}");
+        assert!(lines[2].starts_with("mlx5_0"), "{text}");
+        state.update_selectable_items(&adapters, &metrics);
+        assert_eq!(state.selected_port(), Some(("mlx5_0", 1)));
+
+        // Cycling all the way round restores the grouped layout
+        state.cycle_sort_key();
+        state.cycle_sort_key();
+        assert_eq!(state.sort_key, SortKey::Name);
+        assert!(matches!(
+            build_rows(&adapters, &metrics, &state)[0],
+            TableRow::Adapter(_)
+        ));
+    }
+
+    #[test]
+    fn test_filter_limits_rows_and_navigation() {
+        let adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+            adapter("mlx4_0", &[PortState::Active]),
+        ];
+        let metrics = MetricsCollector::new();
+        let mut state = AppState::new();
+
+        state.start_filter();
+        state.filter.push_str("MLX5_1");
+        assert_eq!(build_rows(&adapters, &metrics, &state).len(), 3);
+        state.commit_filter();
+        assert!(!state.filter_editing);
+
+        // j/k only move through the ports left visible
+        state.update_selectable_items(&adapters, &metrics);
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+        state.select_next();
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 2)));
+        state.select_next();
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 2)));
+
+        // Sorted flat, the filter still applies
+        state.sort_key = SortKey::Total;
+        assert_eq!(build_rows(&adapters, &metrics, &state).len(), 2);
+        state.sort_key = SortKey::Name;
+
+        state.filter = "ib".to_string();
+        let text = plain_text_table(&adapters, &metrics, "node01", &state);
+        assert!(text.contains("no match"), "{text}");
+
+        state.clear_filter();
+        assert_eq!(build_rows(&adapters, &metrics, &state).len(), 3 + 4);
+    }
+
+    #[test]
+    fn test_collapse_down_summary_counts() {
+        let adapters = vec![
+            adapter(
+                "mlx5_0",
+                &[PortState::Active, PortState::Down, PortState::Down],
+            ),
+            adapter("mlx5_1", &[PortState::Active]),
+            adapter("mlx5_2", &[PortState::Down]),
+        ];
+        let mut state = AppState::new();
+
+        // Expanded: every port gets its own row
+        let metrics = MetricsCollector::new();
+        let rows = build_rows(&adapters, &metrics, &state);
+        assert_eq!(rows.len(), 3 + 5);
+        assert!(!rows.iter().any(|r| matches!(r, TableRow::DownSummary(_))));
+
+        state.toggle_collapse_down();
+        let rows = build_rows(&adapters, &metrics, &state);
+        let summaries: Vec<usize> = rows
+            .iter()
+            .filter_map(|r| match r {
+                TableRow::DownSummary(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(summaries, vec![2, 1]);
+        assert_eq!(rows[2], TableRow::DownSummary(2));
+
+        // Summary rows are skipped by navigation like headers
+        state.update_selectable_items(&adapters, &metrics);
+        assert_eq!(state.selected_row, 1);
+        state.select_next();
+        assert_eq!(state.selected_row, 4); // mlx5_1 port 1
+        state.select_next();
+        assert_eq!(state.selected_row, 4); // Nothing selectable after it
+    }
+
+    fn render(adapters: &[AdapterInfo], state: &mut AppState) -> String {
+        let backend = ratatui::backend::TestBackend::new(100, 30);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal
+            .draw(|f| draw(f, adapters, &MetricsCollector::new(), "node01", state))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect()
+    }
+
+    #[test]
+    fn test_paused_badge_in_title() {
+        let adapters = vec![adapter("mlx5_0", &[PortState::Active])];
+        let mut state = AppState::new();
+        assert!(!render(&adapters, &mut state).contains("PAUSED"));
+
+        state.toggle_paused();
+        assert!(state.paused);
+        let screen = render(&adapters, &mut state);
+        assert!(screen.contains(" PAUSED "), "{screen}");
+        // Navigation keeps working on the frozen data
+        state.toggle_detail();
+        assert!(render(&adapters, &mut state).contains("mlx5_0"));
+    }
+
+    #[test]
+    fn test_carousel_advances_on_timer() {
+        let adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+        ];
+        let mut state = AppState::new();
+        state.update_selectable_items(&adapters, &MetricsCollector::new());
+        state.toggle_detail();
+
+        let start = Instant::now();
+        state.toggle_carousel(start);
+        assert!(!state.advance_carousel(start + CAROUSEL_INTERVAL / 2));
+        assert_eq!(state.selected_port(), Some(("mlx5_0", 1)));
+
+        // Adapter header rows are skipped
+        assert!(state.advance_carousel(start + CAROUSEL_INTERVAL));
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+
+        // The timer restarts from the last advance
+        let last = start + CAROUSEL_INTERVAL;
+        assert!(!state.advance_carousel(last + CAROUSEL_INTERVAL / 2));
+        assert!(state.advance_carousel(last + CAROUSEL_INTERVAL));
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 2)));
+
+        // Wraps around to the first port
+        assert!(state.advance_carousel(last + CAROUSEL_INTERVAL * 2));
+        assert_eq!(state.selected_port(), Some(("mlx5_0", 1)));
+
+        // Manual navigation stops it
+        state.select_next();
+        assert!(!state.carousel_active());
+        assert!(!state.advance_carousel(last + CAROUSEL_INTERVAL * 10));
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+    }
+
+    #[test]
+    fn test_highlighted_rows_are_styled() {
+        let adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+        ];
+        let mut state = AppState::new();
+        state.highlight = Some(Regex::new("mlx5_1:2").unwrap());
+
+        let area = Rect::new(0, 0, 80, 6);
+        let mut buffer = Buffer::empty(area);
+        build_table(&adapters, &MetricsCollector::new(), &state, None).render(area, &mut buffer);
+
+        // Rows: header, mlx5_0, port 1, mlx5_1, port 1, port 2
+        let row_bg = |y| buffer[(0, y)].bg;
+        assert_eq!(row_bg(2), Color::Reset);
+        assert_eq!(row_bg(4), Color::Reset);
+        assert_eq!(row_bg(5), Theme::DARK.highlight.bg.unwrap());
+    }
+
+    #[test]
+    fn test_mono_theme_draws_without_colour() {
+        let adapters = vec![adapter("mlx5_0", &[PortState::Active, PortState::Down])];
+        let mut state = AppState::new();
+        state.theme = Theme::MONO;
+
+        let area = Rect::new(0, 0, 80, 4);
+        let mut buffer = Buffer::empty(area);
+        build_table(&adapters, &MetricsCollector::new(), &state, Some(1)).render(area, &mut buffer);
+
+        assert!(buffer
+            .content()
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+        // Rows: header, mlx5_0, port 1 (selected), port 2
+        let reversed = |y| buffer[(0, y)].modifier.contains(Modifier::REVERSED);
+        assert!(reversed(2));
+        assert!(!reversed(3));
+    }
+
+    #[test]
+    fn test_rows_over_error_threshold_are_red() {
+        let sample = |errors| {
+            let mut adapters = vec![
+                adapter("mlx5_0", &[PortState::Active]),
+                adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+            ];
+            adapters[1].ports[1].counters.rx_errors = errors;
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(5));
+        let adapters = sample(5);
+
+        let render_rows = |state: &AppState| {
+            let area = Rect::new(0, 0, 80, 6);
+            let mut buffer = Buffer::empty(area);
+            build_table(&adapters, &metrics, state, None).render(area, &mut buffer);
+            buffer
+        };
+
+        // Rows: header, mlx5_0, port 1, mlx5_1, port 1, port 2
+        let mut state = AppState::new();
+        assert_ne!(render_rows(&state)[(0, 5)].fg, Color::Red);
+
+        state.error_threshold = Some(1.0);
+        let buffer = render_rows(&state);
+        assert_eq!(buffer[(0, 5)].fg, Color::Red);
+        assert!(buffer[(0, 5)].modifier.contains(Modifier::BOLD));
+        assert_ne!(buffer[(0, 4)].fg, Color::Red);
+
+        // Cumulative errors alone do not count once the rate drops to zero
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(5));
+        metrics.update(&sample(5));
+        let area = Rect::new(0, 0, 80, 6);
+        let mut buffer = Buffer::empty(area);
+        build_table(&adapters, &metrics, &state, None).render(area, &mut buffer);
+        assert_ne!(buffer[(0, 5)].fg, Color::Red);
+    }
+
+    #[test]
+    fn test_adapter_header_shows_subtotals() {
+        let sample = |bytes: u64| {
+            let mut adapters = vec![
+                adapter("mlx5_0", &[PortState::Active]),
+                adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+            ];
+            for (i, port) in adapters[1].ports.iter_mut().enumerate() {
+                port.counters.rx_bytes = bytes * (i as u64 + 1);
+                port.counters.tx_bytes = bytes;
+            }
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0));
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(1 << 30));
+        let adapters = sample(1 << 30);
+
+        let port_rate = |port: u16| metrics.get_metrics("mlx5_1", port).unwrap().clone();
+        let (rx, tx) = adapter_totals(&adapters[1], &metrics);
+        assert!((rx - port_rate(1).rx_bytes_per_sec - port_rate(2).rx_bytes_per_sec).abs() < 1.0);
+        assert!((tx - 2.0 * port_rate(1).tx_bytes_per_sec).abs() < 1.0);
+        assert_eq!(adapter_totals(&adapters[0], &metrics), (0.0, 0.0));
+
+        let state = AppState::new();
+        let area = Rect::new(0, 0, 100, 6);
+        let mut buffer = Buffer::empty(area);
+        build_table(&adapters, &metrics, &state, None).render(area, &mut buffer);
+        let row = |y: u16| -> String { (0..area.width).map(|x| buffer[(x, y)].symbol()).collect() };
+
+        // Rows: header, mlx5_0, port 1, mlx5_1, port 1, port 2
+        assert!(row(3).contains(&state.rate_display.format(rx)));
+        assert!(row(3).contains(&state.rate_display.format(tx)));
+        assert!(row(1).contains(&state.rate_display.format(0.0)));
+    }
+
+    #[test]
+    fn test_detail_reports_vanished_port() {
+        let mut adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Active, PortState::Active]),
+        ];
+        let mut state = AppState::new();
+        render(&adapters, &mut state);
+        state.select_next();
+        state.toggle_detail();
+        render(&adapters, &mut state);
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+
+        // Hot-remove the adapter behind the open detail view
+        adapters.pop();
+        let screen = render(&adapters, &mut state);
+        assert!(screen.contains("mlx5_1:1 is no longer present"));
+        assert!(state.detail_expanded);
+
+        // Closes on its own after a moment
+        for _ in 0..NOTICE_FRAMES {
+            render(&adapters, &mut state);
+        }
+        assert!(!state.detail_expanded);
+        assert_eq!(state.selected_port(), Some(("mlx5_0", 1)));
+    }
+
+    #[test]
+    fn test_selection_follows_port_when_rows_move() {
+        let mut adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Active]),
+        ];
+        let mut state = AppState::new();
+        state.update_selectable_items(&adapters, &MetricsCollector::new());
+        state.select_next();
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+
+        adapters.insert(0, adapter("mlx4_0", &[PortState::Active]));
+        state.update_selectable_items(&adapters, &MetricsCollector::new());
+        assert_eq!(state.selected_port(), Some(("mlx5_1", 1)));
+    }
+
+    #[test]
+    fn test_render_to_text_shows_known_adapters() {
+        let adapters = vec![
+            adapter("mlx5_0", &[PortState::Active]),
+            adapter("mlx5_1", &[PortState::Down]),
+        ];
+        let mut state = AppState::new();
+
+        let text = render_to_text(
+            &adapters,
+            &MetricsCollector::new(),
+            "node01",
+            &mut state,
+            100,
+            20,
+        )
+        .unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 20);
+        assert!(lines[0].starts_with('┌') && lines[0].contains("ibtop @ node01"));
+        assert!(text.contains("mlx5_0") && text.contains("mlx5_1"));
+        assert!(text.contains("ACTIVE") && text.contains("DOWN"));
+    }
+
+    #[test]
+    fn test_header_totals_use_theme_rx_and_tx() {
+        let adapters = vec![adapter("mlx5_0", &[PortState::Active])];
+        let mut state = AppState::new();
+        state.theme = Theme::LIGHT;
+
+        let metrics = MetricsCollector::new();
+        let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+        terminal
+            .draw(|frame| draw(frame, &adapters, &metrics, "node01", &mut state))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let color_of = |symbol| {
+            (0..100)
+                .map(|x| &buffer[(x, 0)])
+                .find(|cell| cell.symbol() == symbol)
+                .map(|cell| cell.fg)
+        };
+        assert_eq!(color_of("▲"), Some(Theme::LIGHT.rx));
+        assert_eq!(color_of("▼"), Some(Theme::LIGHT.tx));
+    }
+
+    #[test]
+    fn test_summary_strip_counts_errors_and_port_states() {
+        let sample = |errors, rx_bytes| {
+            let mut adapters = vec![
+                adapter("mlx5_0", &[PortState::Active, PortState::Down]),
+                adapter("mlx5_1", &[PortState::Active, PortState::Init]),
+            ];
+            for port in adapters.iter_mut().map(|a| &mut a.ports[0]) {
+                port.counters.rx_errors = errors;
+                port.counters.rx_bytes = rx_bytes;
+            }
+            adapters
+        };
+        let mut metrics = MetricsCollector::new();
+        metrics.update(&sample(0, 0));
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(5, 1_000_000));
+        let adapters = sample(5, 1_000_000);
+
+        let summary = node_summary(&adapters, &metrics);
+        assert_eq!((summary.active_ports, summary.down_ports), (2, 1));
+        let per_port = metrics.get_metrics("mlx5_0", 1).unwrap().error_rate;
+        assert!(per_port > 0.0);
+        assert!((summary.error_rate - 2.0 * per_port).abs() < 1e-6);
+
+        // While the trip meter runs, the strip sums the rates the rows show:
+        // averages since the zero point, not the idle latest interval
+        metrics.set_reference();
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(5, 2_000_000));
+        std::thread::sleep(Duration::from_millis(10));
+        metrics.update(&sample(5, 2_000_000));
+        let summary = node_summary(&sample(5, 2_000_000), &metrics);
+        let trip_rx: f64 = ["mlx5_0", "mlx5_1"]
+            .iter()
+            .map(|name| metrics.trip(name, 1).unwrap().rates.rx_bytes_per_sec)
+            .sum();
+        assert!(trip_rx > 0.0);
+        assert!((summary.total_rx - trip_rx).abs() < 1e-6);
+
+        let mut state = AppState::new();
+        let text = render_to_text(
+            &adapters,
+            &MetricsCollector::new(),
+            "node01",
+            &mut state,
+            130,
+            12,
+        )
+        .unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        // Above the two-line help footer
+        let strip = lines[lines.len() - 3];
+        assert!(strip.starts_with(" Σ RX"), "{strip}");
+        assert!(
+            strip.ends_with("errors 0.0/s  ports 2 active 1 down"),
+            "{strip}"
+        );
+    }
+
+    #[test]
+    fn test_strip_keeps_health_when_aggregates_are_hidden() {
+        let adapters = vec![adapter("mlx5_0", &[PortState::Active, PortState::Down])];
+        let mut state = AppState::new();
+        state.toggle_summary();
+
+        let text = render_to_text(
+            &adapters,
+            &MetricsCollector::new(),
+            "node01",
+            &mut state,
+            130,
+            10,
+        )
+        .unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let strip = lines[lines.len() - 3];
+        assert_eq!(strip, " errors 0.0/s  ports 1 active 1 down");
+    }
+
+    #[test]
+    fn test_title_names_the_session() {
+        let adapters = vec![adapter("mlx5_0", &[PortState::Active])];
+        let metrics = MetricsCollector::new();
+        let mut state = AppState::new();
+        state.title = Some("Rack 3 Node 7".to_string());
+
+        let text = render_to_text(&adapters, &metrics, "node07", &mut state, 100, 10).unwrap();
+        let header = text.lines().next().unwrap();
+        assert!(
+            header.contains("ibtop @ Rack 3 Node 7 (node07)"),
+            "{header}"
+        );
+
+        let table = plain_text_table(&adapters, &metrics, "node07", &state);
+        assert!(table.starts_with("ibtop @ Rack 3 Node 7 (node07)  RX"));
+    }
+
+    #[test]
+    fn test_plain_text_table_follows_view() {
+        let adapters = vec![
+            adapter("mlx5_0", &[PortState::Active, PortState::Down]),
+            adapter("mlx5_1", &[PortState::Down]),
+        ];
+        let mut state = AppState::new();
+        state.columns = vec![Column::Port, Column::State, Column::Rx];
+        state.collapse_down = true;
+
+        let text = plain_text_table(&adapters, &MetricsCollector::new(), "node01", &state);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], "ibtop @ node01  RX 0.00B/s  TX 0.00B/s");
+        assert_eq!(lines[1], "Port State    RX");
+        assert_eq!(lines[2], "      mlx5_0");
+        assert!(lines[3].starts_with("  1  ") && lines[3].ends_with("--"));
+        assert_eq!(lines[4], "     ○DOWN×1");
+        assert_eq!(lines.len(), 1 + 1 + 5);
+        // Columns left out of the view stay out
+        assert!(!text.contains("Load"));
+        assert!(lines.iter().all(|line| *line == line.trim_end()));
+    }
+
+    #[test]
+    fn test_chart_ago_label_units() {
+        // 360 samples at 250ms cover 90s
+        let span = 360.0 * 0.25;
+        assert_eq!(ago_label(span, TimeUnit::Auto), "2m ago");
+        assert_eq!(ago_label(span, TimeUnit::Seconds), "90s ago");
+        assert_eq!(ago_label(span, TimeUnit::Minutes), "1.5m ago");
+
+        assert_eq!(ago_label(30.0, TimeUnit::Auto), "30s ago");
+        assert_eq!(ago_label(30.0, TimeUnit::Seconds), "30s ago");
+        assert_eq!(ago_label(30.0, TimeUnit::Minutes), "0.5m ago");
+        assert_eq!(ago_label(120.0, TimeUnit::Minutes), "2m ago");
+
+        assert_eq!("s".parse(), Ok(TimeUnit::Seconds));
+        assert_eq!("minutes".parse(), Ok(TimeUnit::Minutes));
+        assert!("hours".parse::<TimeUnit>().is_err());
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_millis(200)), "0.2s");
+        assert_eq!(format_age(Duration::from_millis(9_940)), "9.9s");
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(125)), "2m05s");
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(900)), "0s");
+        assert_eq!(format_elapsed(Duration::from_secs(42)), "42s");
+        assert_eq!(format_elapsed(Duration::from_mins(1)), "1m 00s");
+        assert_eq!(format_elapsed(Duration::from_secs(303)), "5m 03s");
+        assert_eq!(format_elapsed(Duration::from_mins(125)), "2h 05m");
+        assert_eq!(format_elapsed(Duration::from_hours(76)), "3d 04h");
+    }
+
+    #[test]
+    fn test_stall_threshold() {
+        let interval = Duration::from_secs(1);
+        assert!(!is_stalled(Duration::from_millis(200), interval));
+        assert!(!is_stalled(Duration::from_secs(3), interval));
+        assert!(is_stalled(Duration::from_millis(3_100), interval));
+        // The threshold scales with the refresh interval
+        assert!(!is_stalled(Duration::from_secs(10), Duration::from_secs(5)));
+        assert!(is_stalled(
+            Duration::from_secs(2),
+            Duration::from_millis(500)
+        ));
+    }
+
+    #[test]
+    fn test_degraded_hides_sparkline_until_restored() {
+        let mut state = AppState::new();
+        state.set_degraded(true);
+        assert!(!state.visible_columns().contains(&Column::History));
+        assert!(state.active_notice().is_some());
+
+        state.set_degraded(false);
+        assert!(state.visible_columns().contains(&Column::History));
+    }
+
+    #[test]
+    fn test_hidden_sparkline_leaves_column_set() {
+        let mut state = AppState::new();
+        assert!(state.visible_columns().contains(&Column::History));
+
+        state.toggle_sparkline();
+        let visible = state.visible_columns();
+        assert!(!visible.contains(&Column::History));
+        assert_eq!(visible.len(), DEFAULT_COLUMNS.len() - 1);
+        // The chosen columns are kept for when it is shown again
+        assert_eq!(state.columns, DEFAULT_COLUMNS);
+
+        let text = plain_text_table(&[], &MetricsCollector::new(), "node01", &state);
+        assert!(!text.contains("History"));
+
+        // A table of only the History column keeps it
+        state.columns = vec![Column::History];
+        assert_eq!(state.visible_columns(), vec![Column::History]);
+    }
+
+    #[test]
+    fn test_app_state_toggle_detail() {
+        let mut state = AppState::new();
+        assert!(!state.detail_expanded);
+
+        state.toggle_detail();
+        assert!(state.detail_expanded);
+
+        state.toggle_detail();
+        assert!(!state.detail_expanded);
+    }
+
+    #[test]
+    fn test_detail_ratio_clamping() {
+        assert_eq!(clamp_detail_ratio(0), DETAIL_RATIO_MIN);
+        assert_eq!(clamp_detail_ratio(50), 50);
+        assert_eq!(clamp_detail_ratio(100), DETAIL_RATIO_MAX);
+
+        let mut state = AppState::new();
+        assert_eq!(state.detail_ratio, DETAIL_RATIO_DEFAULT);
+        for _ in 0..10 {
+            state.grow_detail();
+        }
+        assert_eq!(state.detail_ratio, DETAIL_RATIO_MAX);
+        for _ in 0..10 {
+            state.shrink_detail();
+        }
+        assert_eq!(state.detail_ratio, DETAIL_RATIO_MIN);
+    }
+
+    #[test]
+    fn test_adjust_interval_clamping() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(adjust_interval(ms(250), true), ms(500));
+        assert_eq!(adjust_interval(ms(250), false), ms(100));
+        // Clamped at both ends
+        assert_eq!(adjust_interval(ms(100), false), ms(100));
+        assert_eq!(adjust_interval(ms(10_000), true), ms(10_000));
+        // Off-ladder values snap to the neighbouring step
+        assert_eq!(adjust_interval(ms(300), true), ms(500));
+        assert_eq!(adjust_interval(ms(300), false), ms(250));
+        assert_eq!(adjust_interval(ms(20), false), ms(100));
+        assert_eq!(adjust_interval(ms(60_000), true), ms(10_000));
+    }
+
+    #[test]
+    fn test_over_rate_band_covers_only_capacity_overrun() {
+        // 6 rows of 2 units each: cell centers at 1, 3, ..., 11
+        let band = over_rate_band(10.0, 12.0, [0.0, 8.0], 4, 6);
+        assert_eq!(
+            band,
+            vec![(1.0, 11.0), (3.0, 11.0), (5.0, 11.0), (7.0, 11.0)]
+        );
+
+        let band = over_rate_band(4.0, 12.0, [0.0, 8.0], 4, 6);
+        assert_eq!(band.len(), 4 * 4);
+        assert!(band.iter().all(|(_, y)| *y > 4.0 && *y < 12.0));
+
+        assert!(over_rate_band(4.0, 12.0, [0.0, 8.0], 0, 6).is_empty());
+    }
+
+    #[test]
+    fn test_headroom_percent() {
+        let line_rate = parse_max_rate("100 Gb/sec (4X EDR)");
+        assert!((headroom_percent(0.0, line_rate) - 100.0).abs() < 1e-9);
+        assert!((headroom_percent(line_rate / 4.0, line_rate) - 75.0).abs() < 1e-9);
+        // Counter glitches above line rate do not go negative
+        assert!(headroom_percent(line_rate * 2.0, line_rate).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chart_ago_label_sums_sample_intervals() {
+        // 10 s at 2 s per sample, then 2 s at 250 ms
+        let mut history = PortHistory::with_capacity(100);
+        for _ in 0..5 {
+            history.record(1_000.0, 0.0, 0.0, 0.0, 0.0, Duration::from_secs(2));
+        }
+        for _ in 0..8 {
+            history.record(4_000.0, 0.0, 0.0, 0.0, 0.0, Duration::from_millis(250));
+        }
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal
+            .draw(|frame| {
+                let area = frame.area();
+                draw_chart(
+                    frame,
+                    area,
+                    &history,
+                    0,
+                    false,
+                    TimeUnit::Seconds,
+                    None,
+                    None,
+                    false,
+                    Theme::DARK,
+                );
+            })
+            .unwrap();
+        let mut text = String::new();
+        push_buffer_text(&mut text, terminal.backend().buffer());
+        assert!(text.contains("12s ago"), "{text}");
+    }
+
+    #[test]
+    fn test_wall_clock_axis_ticks() {
+        // 14:05:12 UTC, 30 seconds of samples
+        let latest = 1_700_000_000.0 - 1_700_000_000.0 % 86_400.0 + 50_712.0;
+        let (bounds, labels) = wall_clock_axis(latest, 30.0, 0);
+
+        assert_eq!(
+            labels,
+            vec!["14:04:40", "14:04:50", "14:05:00", "14:05:10", "14:05:20"]
+        );
+        assert!(bounds[0] <= latest - 30.0 && latest <= bounds[1]);
+
+        // Labels don't move while the newest sample stays within a tick
+        assert_eq!(wall_clock_axis(latest + 7.0, 30.0, 0).1, labels);
+        assert_ne!(wall_clock_axis(latest + 9.0, 30.0, 0).1, labels);
+
+        // Local offset only shifts the labels
+        let shifted = wall_clock_axis(latest, 30.0, 2 * 3_600).1;
+        assert_eq!(shifted[0], "16:04:40");
+    }
+
+    #[test]
+    fn test_format_clock_wraps_days() {
+        assert_eq!(format_clock(0), "00:00:00");
+        assert_eq!(format_clock(86_399), "23:59:59");
+        assert_eq!(format_clock(86_400 + 61), "00:01:01");
+        assert_eq!(format_clock(-1), "23:59:59");
+    }
+
+    #[test]
+    fn test_adaptive_interval_decision() {
+        let ms = Duration::from_millis;
+
+        // Bursts step faster, down to the lower bound
+        assert_eq!(adaptive_interval(ms(1_000), 0.5), ms(500));
+        assert_eq!(adaptive_interval(ms(100), 0.5), ADAPTIVE_MIN_INTERVAL);
+        // Steady traffic steps slower, up to the upper bound
+        assert_eq!(adaptive_interval(ms(500), 0.01), ms(1_000));
+        assert_eq!(adaptive_interval(ms(2_000), 0.0), ADAPTIVE_MAX_INTERVAL);
+        // Moderate change keeps the interval
+        assert_eq!(adaptive_interval(ms(500), 0.1), ms(500));
+        // Manual intervals outside the bounds are pulled back in
+        assert_eq!(adaptive_interval(ms(10_000), 0.1), ADAPTIVE_MAX_INTERVAL);
+    }
+
+    #[test]
+    fn test_divergence_percent() {
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-9;
+        assert!(close(divergence_percent(18.0, 12.0), 50.0));
+        assert!(close(divergence_percent(6.0, 12.0), -50.0));
+        assert!(close(divergence_percent(12.0, 12.0), 0.0));
+        assert_eq!(divergence_percent(5.0, 0.0), None);
+        assert_eq!(divergence_percent(0.0, 0.0), None);
+    }
+
+    #[test]
+    fn test_format_interval() {
+        assert_eq!(format_interval(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_interval(Duration::from_secs(2)), "2s");
+        assert_eq!(format_interval(Duration::from_millis(2500)), "2.5s");
+    }
+
+    #[test]
+    fn test_app_state_tab_cycling() {
+        let mut state = AppState::new();
+        assert_eq!(state.detail_tab, 0);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, 1);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, 2);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, INFO_TAB);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, RAW_TAB);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, VPORT_TAB);
+
+        state.next_tab();
+        assert_eq!(state.detail_tab, 0);
+
+        state.prev_tab();
+        assert_eq!(state.detail_tab, VPORT_TAB);
+    }
+}
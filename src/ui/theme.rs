@@ -0,0 +1,163 @@
+//! Colour themes
+//!
+//! Every colour the screen is drawn with comes from a `Theme`, named for
+//! what it marks rather than its hue, so one preset recolours everything:
+//! `dark` (the default), `light` for light terminal backgrounds and `mono`,
+//! which leaves the terminal's own colours alone and marks the selection
+//! and badges with reverse video instead. `NO_COLOR` makes `mono` the
+//! default.
+
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Colours and styles for every role on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Titles, port numbers and the selected adapter
+    pub accent: Color,
+    /// Plain values
+    pub text: Color,
+    /// Labels, borders and idle values
+    pub muted: Color,
+    /// Healthy states: ACTIVE ports, informational alerts
+    pub good: Color,
+    /// Warnings and states worth a look
+    pub warn: Color,
+    /// Errors and ports that are down
+    pub bad: Color,
+    /// RX throughput
+    pub rx: Color,
+    /// TX throughput
+    pub tx: Color,
+    /// ARMED ports, one step further into bringup than INIT (`warn`)
+    pub bringup: Color,
+    /// The row under the cursor
+    pub selected: Style,
+    /// Rows matching `--highlight`
+    pub highlight: Style,
+    /// Rows over `--error-threshold`
+    pub error_row: Style,
+    /// The node-over-limit banner
+    pub alarm: Style,
+    /// The PAUSED badge
+    pub notice: Style,
+}
+
+impl Theme {
+    /// Bright colours for dark backgrounds
+    pub const DARK: Theme = Theme {
+        accent: Color::Cyan,
+        text: Color::White,
+        muted: Color::DarkGray,
+        good: Color::Green,
+        warn: Color::Yellow,
+        bad: Color::Red,
+        rx: Color::Blue,
+        tx: Color::Magenta,
+        bringup: Color::Rgb(255, 165, 0),
+        selected: Style::new().bg(Color::DarkGray),
+        highlight: Style::new().bg(Color::Blue).add_modifier(Modifier::BOLD),
+        error_row: Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        alarm: Style::new()
+            .fg(Color::White)
+            .bg(Color::Red)
+            .add_modifier(Modifier::BOLD),
+        notice: Style::new()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    };
+
+    /// Darker colours that stay readable on white; 256-colour indices, as
+    /// the 16 named ones include yellow and cyan shades lost on white
+    pub const LIGHT: Theme = Theme {
+        accent: Color::Indexed(31),
+        text: Color::Black,
+        muted: Color::Indexed(244),
+        good: Color::Indexed(28),
+        warn: Color::Indexed(130),
+        bad: Color::Indexed(160),
+        rx: Color::Indexed(25),
+        tx: Color::Indexed(127),
+        bringup: Color::Indexed(166),
+        selected: Style::new().bg(Color::Indexed(252)),
+        highlight: Style::new()
+            .bg(Color::Indexed(153))
+            .add_modifier(Modifier::BOLD),
+        error_row: Style::new()
+            .fg(Color::Indexed(160))
+            .add_modifier(Modifier::BOLD),
+        alarm: Style::new()
+            .fg(Color::White)
+            .bg(Color::Indexed(160))
+            .add_modifier(Modifier::BOLD),
+        notice: Style::new()
+            .fg(Color::Black)
+            .bg(Color::Indexed(221))
+            .add_modifier(Modifier::BOLD),
+    };
+
+    /// No colour at all; emphasis through bold, underline and reverse video
+    pub const MONO: Theme = Theme {
+        accent: Color::Reset,
+        text: Color::Reset,
+        muted: Color::Reset,
+        good: Color::Reset,
+        warn: Color::Reset,
+        bad: Color::Reset,
+        rx: Color::Reset,
+        tx: Color::Reset,
+        bringup: Color::Reset,
+        selected: Style::new().add_modifier(Modifier::REVERSED),
+        highlight: Style::new().add_modifier(Modifier::UNDERLINED.union(Modifier::BOLD)),
+        error_row: Style::new().add_modifier(Modifier::BOLD),
+        alarm: Style::new().add_modifier(Modifier::REVERSED.union(Modifier::BOLD)),
+        notice: Style::new().add_modifier(Modifier::REVERSED.union(Modifier::BOLD)),
+    };
+
+    /// The theme used when none is asked for: `mono` when `no_color` (the
+    /// `NO_COLOR` variable) is set to anything but the empty string
+    pub fn default_for(no_color: Option<OsString>) -> Theme {
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            Theme::MONO
+        } else {
+            Theme::DARK
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Theme::DARK),
+            "light" => Ok(Theme::LIGHT),
+            "mono" | "none" => Ok(Theme::MONO),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_color_defaults_to_mono() {
+        assert_eq!(Theme::default_for(None), Theme::DARK);
+        assert_eq!(Theme::default_for(Some(OsString::new())), Theme::DARK);
+        assert_eq!(Theme::default_for(Some("1".into())), Theme::MONO);
+
+        assert_eq!("none".parse(), Ok(Theme::MONO));
+        assert_eq!("Dark".parse::<Theme>(), Err(()));
+    }
+}
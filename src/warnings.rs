@@ -0,0 +1,265 @@
+//! Adapter-level health warnings
+//!
+//! Most checks here look at how an adapter's ports relate to each other
+//! rather than at any single port's counters; `detect_packet_anomaly` is the
+//! exception, reading one port's live rates.
+
+use crate::metrics::PortMetrics;
+use crate::types::{AdapterInfo, PortState};
+use crate::ui::port_rate_bps;
+
+/// A problem worth pointing out on one adapter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub adapter: String,
+    pub message: String,
+}
+
+/// Whether `adapter` is a bonded device (mlx5 names RoCE LAG devices `mlx5_bond<N>`)
+pub fn is_bond(adapter: &AdapterInfo) -> bool {
+    adapter.name.contains("bond")
+}
+
+/// Warn when the active members of a bond report different link rates
+///
+/// Members of a bond should train to the same speed; a slower one usually
+/// means a lane or cable problem and caps the bond's throughput.
+pub fn detect_bond_rate_mismatch(adapter: &AdapterInfo) -> Option<Warning> {
+    if !is_bond(adapter) {
+        return None;
+    }
+
+    let rates: Vec<(u16, f64)> = adapter
+        .ports
+        .iter()
+        .filter(|port| port.state == PortState::Active)
+        .filter_map(|port| Some((port.port_number, port_rate_bps(port)?)))
+        .collect();
+    let (_, first) = rates.first()?;
+    if rates.iter().all(|(_, rate)| (rate - first).abs() < 1.0) {
+        return None;
+    }
+
+    let members = rates
+        .iter()
+        .map(|(port, rate)| format!("port {port} {} Gb/s", rate / 1e9))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(Warning {
+        adapter: adapter.name.clone(),
+        message: format!("bond members run at different rates ({members})"),
+    })
+}
+
+/// Warn when a bond has member links that are not up
+///
+/// The bonded device stays ACTIVE as long as one member is up, so this is
+/// the only sign of a bond running on reduced bandwidth.
+pub fn detect_bond_member_down(adapter: &AdapterInfo) -> Option<Warning> {
+    let down: Vec<&str> = adapter
+        .bond_members
+        .iter()
+        .filter(|(_, state)| *state != PortState::Active)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if down.is_empty() {
+        return None;
+    }
+    Some(Warning {
+        adapter: adapter.name.clone(),
+        message: format!(
+            "{} of {} bond members not up ({})",
+            down.len(),
+            adapter.bond_members.len(),
+            down.join(", ")
+        ),
+    })
+}
+
+/// Warn when discovery renamed an adapter because its name was taken
+///
+/// Its data is kept apart from the first adapter of that name, but it no
+/// longer matches a sysfs directory, so the Raw and Info tabs stay empty.
+pub fn detect_duplicate_name(adapter: &AdapterInfo) -> Option<Warning> {
+    let original = crate::discovery::duplicated_name(&adapter.name)?;
+    Some(Warning {
+        adapter: adapter.name.clone(),
+        message: format!(
+            "another adapter is also named {original}; shown as {}",
+            adapter.name
+        ),
+    })
+}
+
+/// Packet rate from which a direction is judged at all; a handful of
+/// management packets says nothing about the traffic
+const ANOMALY_MIN_PACKETS_PER_SEC: f64 = 1_000.0;
+
+/// Packet rate from which small packets count as a flood
+const FLOOD_PACKETS_PER_SEC: f64 = 1_000_000.0;
+
+/// Smaller than any real packet: an Ethernet frame, which RoCE rides in,
+/// is at least 64 bytes
+const TINY_PACKET_BYTES: f64 = 64.0;
+
+/// Warn when a port's packets are implausibly small for their number
+///
+/// Packets that carry (next to) no bytes, or a flood of packets below the
+/// minimum frame size, point at a misbehaving application or an attack on
+/// RoCE rather than at ordinary traffic.
+pub fn detect_packet_anomaly(adapter: &str, port: u16, metrics: &PortMetrics) -> Option<Warning> {
+    let directions = [
+        (
+            "RX",
+            metrics.rx_packets_per_sec,
+            metrics.avg_rx_packet_size(),
+        ),
+        (
+            "TX",
+            metrics.tx_packets_per_sec,
+            metrics.avg_tx_packet_size(),
+        ),
+    ];
+    let problems: Vec<String> = directions
+        .into_iter()
+        .filter(|(_, packets, _)| *packets >= ANOMALY_MIN_PACKETS_PER_SEC)
+        .filter_map(|(direction, packets, size)| {
+            let size = size?;
+            if size < 1.0 {
+                Some(format!("{direction} packets carry no data"))
+            } else if size < TINY_PACKET_BYTES && packets >= FLOOD_PACKETS_PER_SEC {
+                Some(format!(
+                    "{direction} flood of tiny packets ({:.1}M/s averaging {size:.0}B)",
+                    packets / 1e6
+                ))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if problems.is_empty() {
+        return None;
+    }
+    Some(Warning {
+        adapter: adapter.to_string(),
+        message: format!("port {port}: {}", problems.join(", ")),
+    })
+}
+
+/// Every warning for one adapter
+pub fn for_adapter(adapter: &AdapterInfo) -> Vec<Warning> {
+    [
+        detect_bond_rate_mismatch(adapter),
+        detect_bond_member_down(adapter),
+        detect_duplicate_name(adapter),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Every warning for `adapters`
+pub fn collect(adapters: &[AdapterInfo]) -> Vec<Warning> {
+    adapters.iter().flat_map(for_adapter).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PortInfo;
+
+    fn adapter(name: &str, rates: &[&str]) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_string(),
+            ports: (1..)
+                .zip(rates)
+                .map(|(port_number, rate)| PortInfo {
+                    port_number,
+                    state: PortState::Active,
+                    rate: (*rate).to_string(),
+                    ..PortInfo::default()
+                })
+                .collect(),
+            ..AdapterInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_bond_rate_mismatch() {
+        let mismatched = adapter(
+            "mlx5_bond0",
+            &["100 Gb/sec (4X EDR)", "200 Gb/sec (4X HDR)"],
+        );
+        let warning = detect_bond_rate_mismatch(&mismatched).unwrap();
+        assert_eq!(warning.adapter, "mlx5_bond0");
+        assert_eq!(
+            warning.message,
+            "bond members run at different rates (port 1 100 Gb/s, port 2 200 Gb/s)"
+        );
+        assert_eq!(collect(&[mismatched]), vec![warning]);
+
+        let matched = adapter("mlx5_bond0", &["200 Gb/sec (4X HDR)", "200 Gb/sec"]);
+        assert_eq!(detect_bond_rate_mismatch(&matched), None);
+
+        // Independent ports may legitimately differ
+        let plain = adapter("mlx5_0", &["100 Gb/sec (4X EDR)", "200 Gb/sec (4X HDR)"]);
+        assert_eq!(detect_bond_rate_mismatch(&plain), None);
+    }
+
+    #[test]
+    fn test_bond_member_down() {
+        let mut bond = adapter("mlx5_bond0", &["200 Gb/sec (4X HDR)"]);
+        bond.bond_members = vec![
+            ("ens1f0np0".to_string(), PortState::Active),
+            ("ens1f1np1".to_string(), PortState::Down),
+        ];
+        assert_eq!(
+            detect_bond_member_down(&bond).unwrap().message,
+            "1 of 2 bond members not up (ens1f1np1)"
+        );
+        assert_eq!(collect(&[bond.clone()]).len(), 1);
+
+        bond.bond_members[1].1 = PortState::Active;
+        assert_eq!(detect_bond_member_down(&bond), None);
+    }
+
+    #[test]
+    fn test_packet_anomaly() {
+        let rates = |rx_bytes_per_sec, rx_packets_per_sec| PortMetrics {
+            rx_bytes_per_sec,
+            rx_packets_per_sec,
+            tx_bytes_per_sec: 12.0e9,
+            tx_packets_per_sec: 3.0e6,
+            ..PortMetrics::default()
+        };
+
+        // Bulk transfers, small messages at moderate rates, and idle ports
+        assert_eq!(
+            detect_packet_anomaly("mlx5_0", 1, &rates(12.0e9, 3.0e6)),
+            None
+        );
+        assert_eq!(
+            detect_packet_anomaly("mlx5_0", 1, &rates(20.0e6, 500_000.0)),
+            None
+        );
+        assert_eq!(detect_packet_anomaly("mlx5_0", 1, &rates(0.0, 0.0)), None);
+        assert_eq!(detect_packet_anomaly("mlx5_0", 1, &rates(0.0, 10.0)), None);
+
+        let flood = detect_packet_anomaly("mlx5_0", 1, &rates(80.0e6, 5.0e6)).unwrap();
+        assert_eq!(flood.adapter, "mlx5_0");
+        assert_eq!(
+            flood.message,
+            "port 1: RX flood of tiny packets (5.0M/s averaging 16B)"
+        );
+
+        let empty = detect_packet_anomaly("mlx5_0", 2, &rates(0.0, 50_000.0)).unwrap();
+        assert_eq!(empty.message, "port 2: RX packets carry no data");
+    }
+
+    #[test]
+    fn test_down_bond_member_is_ignored() {
+        let mut bond = adapter("mlx5_bond0", &["200 Gb/sec (4X HDR)", "10 Gb/sec (4X SDR)"]);
+        bond.ports[1].state = PortState::Down;
+        assert_eq!(detect_bond_rate_mismatch(&bond), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Change notifications for `--refresh-on-change`
+//!
+//! Watches the sysfs port directories with inotify so an idle node is not
+//! re-read every interval. Kernels only raise events for attributes whose
+//! drivers call `sysfs_notify`, so the caller still refreshes on a slow
+//! fallback timer. Without the `inotify` feature no watcher is ever created
+//! and ibtop keeps polling.
+
+use std::path::PathBuf;
+
+#[cfg(feature = "inotify")]
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+
+/// Reports whether any watched path changed since the last check
+#[cfg(feature = "inotify")]
+pub struct ChangeWatcher {
+    inotify: Inotify,
+    watches: Vec<WatchDescriptor>,
+    paths: Vec<PathBuf>,
+}
+
+#[cfg(feature = "inotify")]
+impl ChangeWatcher {
+    /// Create a watcher, or `None` where inotify is unavailable
+    pub fn new() -> Option<Self> {
+        Some(Self {
+            inotify: Inotify::init().ok()?,
+            watches: Vec::new(),
+            paths: Vec::new(),
+        })
+    }
+
+    /// Watch exactly `paths`, re-registering only when the set changed
+    pub fn watch(&mut self, paths: Vec<PathBuf>) {
+        if paths == self.paths {
+            return;
+        }
+
+        for wd in self.watches.drain(..) {
+            let _ = self.inotify.watches().remove(wd);
+        }
+        let mask = WatchMask::MODIFY
+            | WatchMask::ATTRIB
+            | WatchMask::CLOSE_WRITE
+            | WatchMask::CREATE
+            | WatchMask::DELETE;
+        // Paths that vanished in the meantime are picked up on the next refresh
+        self.watches = paths
+            .iter()
+            .filter_map(|path| self.inotify.watches().add(path, mask).ok())
+            .collect();
+        self.paths = paths;
+    }
+
+    /// Drain pending events; true if anything changed since the last call
+    pub fn changed(&mut self) -> bool {
+        let mut buffer = [0u8; 4096];
+        let mut changed = false;
+        while let Ok(mut events) = self.inotify.read_events(&mut buffer) {
+            if events.next().is_none() {
+                break;
+            }
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Without inotify support no watcher can exist, so callers always poll
+#[cfg(not(feature = "inotify"))]
+pub enum ChangeWatcher {}
+
+#[cfg(not(feature = "inotify"))]
+impl ChangeWatcher {
+    pub fn new() -> Option<Self> {
+        None
+    }
+
+    pub fn watch(&mut self, _paths: Vec<PathBuf>) {
+        match *self {}
+    }
+
+    pub fn changed(&mut self) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "inotify"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_change_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let counters = dir.path().join("counters");
+        std::fs::create_dir(&counters).unwrap();
+        std::fs::write(counters.join("port_rcv_data"), "1").unwrap();
+
+        let mut watcher = ChangeWatcher::new().unwrap();
+        watcher.watch(vec![counters.clone()]);
+        assert!(!watcher.changed());
+
+        std::fs::write(counters.join("port_rcv_data"), "2").unwrap();
+        assert!(watcher.changed());
+        // Events are consumed by the check
+        assert!(!watcher.changed());
+    }
+}